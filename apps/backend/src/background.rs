@@ -37,11 +37,25 @@ pub async fn media_jobs(_information: ScheduledJob, ctx: JobContext) -> Result<(
     let service = ctx.data::<Arc<MiscellaneousService>>().unwrap();
     if env::var("DISABLE_INVALIDATE_IMPORT_JOBS").is_err() {
         tracing::trace!("Invalidating invalid media import jobs");
-        ctx.data::<Arc<ImporterService>>()
+        if let Err(e) = ctx
+            .data::<Arc<ImporterService>>()
             .unwrap()
             .invalidate_import_jobs()
             .await
-            .unwrap();
+        {
+            tracing::error!("Could not invalidate import jobs: {e:?}");
+        }
+    }
+    if env::var("DISABLE_RUN_SCHEDULED_IMPORTS").is_err() {
+        tracing::trace!("Running scheduled imports");
+        if let Err(e) = ctx
+            .data::<Arc<ImporterService>>()
+            .unwrap()
+            .run_scheduled_imports()
+            .await
+        {
+            tracing::error!("Could not run scheduled imports: {e:?}");
+        }
     }
     if env::var("DISABLE_UPDATE_WATCHLIST_MEDIA").is_err() {
         tracing::trace!("Checking for updates for media in Watchlist");
@@ -144,10 +158,51 @@ pub async fn perform_core_application_job(
     Ok(())
 }
 
+// The imports, which run on their own workers so that they do not hold up the
+// other jobs.
+#[derive(Debug, Deserialize, Serialize, Display)]
+pub enum ImportJob {
+    ImportFromExternalSource(i32, Box<DeployImportJobInput>),
+}
+
+impl Job for ImportJob {
+    const NAME: &'static str = "apalis::ImportJob";
+}
+
+pub async fn perform_import_job(information: ImportJob, ctx: JobContext) -> Result<(), JobError> {
+    let name = information.to_string();
+    tracing::trace!("Started job: {:#?}", name);
+    let importer_service = ctx.data::<Arc<ImporterService>>().unwrap();
+    let start = Instant::now();
+    let status = match information {
+        // DEV: The import would be lost if it could not be postponed, so the job
+        // fails to have it retried
+        ImportJob::ImportFromExternalSource(user_id, input)
+            if importer_service.is_worker_paused() =>
+        {
+            importer_service
+                .postpone_import_job(user_id, input)
+                .await
+                .map_err(|e| JobError::Failed(e.message.into()))?;
+            true
+        }
+        ImportJob::ImportFromExternalSource(user_id, input) => importer_service
+            .start_importing(user_id, input)
+            .await
+            .is_ok(),
+    };
+    tracing::trace!(
+        "Job: {:#?}, Time Taken: {}ms, Successful = {}",
+        name,
+        (Instant::now() - start).as_millis(),
+        status
+    );
+    Ok(())
+}
+
 // The background jobs which can be deployed by the application.
 #[derive(Debug, Deserialize, Serialize, Display)]
 pub enum ApplicationJob {
-    ImportFromExternalSource(i32, Box<DeployImportJobInput>),
     ReEvaluateUserWorkouts(i32),
     UpdateMetadata(i32),
     UpdateExerciseJob(Exercise),
@@ -169,16 +224,11 @@ pub async fn perform_application_job(
 ) -> Result<(), JobError> {
     let name = information.to_string();
     tracing::trace!("Started job: {:#?}", name);
-    let importer_service = ctx.data::<Arc<ImporterService>>().unwrap();
     let exporter_service = ctx.data::<Arc<ExporterService>>().unwrap();
     let misc_service = ctx.data::<Arc<MiscellaneousService>>().unwrap();
     let exercise_service = ctx.data::<Arc<ExerciseService>>().unwrap();
     let start = Instant::now();
     let status = match information {
-        ApplicationJob::ImportFromExternalSource(user_id, input) => importer_service
-            .start_importing(user_id, input)
-            .await
-            .is_ok(),
         ApplicationJob::RecalculateUserSummary(user_id) => misc_service
             .calculate_user_summary(user_id, true)
             .await
@@ -13,12 +13,14 @@ use super::prelude::Collection;
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
+    pub created_on: DateTimeUtc,
     pub last_updated_on: DateTimeUtc,
     pub collection_id: i32,
     pub metadata_id: Option<i32>,
     pub person_id: Option<i32>,
     pub metadata_group_id: Option<i32>,
     pub exercise_id: Option<String>,
+    pub import_report_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
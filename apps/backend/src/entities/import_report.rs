@@ -5,7 +5,7 @@ use database::ImportSource;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::importer::ImportResultResponse;
+use crate::importer::{ImportCursor, ImportResultResponse};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize, SimpleObject)]
 #[sea_orm(table_name = "import_report")]
@@ -19,6 +19,22 @@ pub struct Model {
     pub finished_on: Option<DateTimeUtc>,
     pub details: Option<ImportResultResponse>,
     pub success: Option<bool>,
+    pub progress: Option<i32>,
+    pub was_cancelled: Option<bool>,
+    #[graphql(skip)]
+    pub job_id: Option<String>,
+    /// How far the import got, so that it can be resumed if it is interrupted.
+    #[graphql(skip)]
+    pub cursor: Option<ImportCursor>,
+    /// The key sent by the client when deploying the import, used to detect when
+    /// the same import is deployed again.
+    #[graphql(skip)]
+    pub idempotency_key: Option<String>,
+    /// The id of the background job that runs the import.
+    #[graphql(skip)]
+    pub application_job_id: Option<String>,
+    /// The id shared by the imports that were deployed together in a bulk import.
+    pub bulk_id: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
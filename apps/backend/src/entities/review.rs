@@ -35,6 +35,7 @@ pub struct Model {
     pub manga_extra_information: Option<SeenMangaExtraInformation>,
     #[sea_orm(column_type = "Json")]
     pub comments: Vec<ImportOrExportItemReviewComment>,
+    pub import_report_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -37,6 +37,8 @@ pub struct Model {
     pub podcast_extra_information: Option<SeenPodcastExtraInformation>,
     pub anime_extra_information: Option<SeenAnimeExtraInformation>,
     pub manga_extra_information: Option<SeenMangaExtraInformation>,
+    #[graphql(skip)]
+    pub import_report_id: Option<i32>,
     // Generated columns
     pub last_updated_on: DateTimeUtc,
     pub num_times_updated: i32,
@@ -11,6 +11,7 @@ use sea_orm::{entity::prelude::*, ActiveValue, FromQueryResult};
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    importer::UserScheduledImport,
     models::media::UserSummary,
     users::{UserNotification, UserPreferences, UserSinkIntegration, UserYankIntegration},
 };
@@ -70,6 +71,9 @@ pub struct Model {
     pub notifications: Vec<UserNotification>,
     #[graphql(skip)]
     pub summary: Option<UserSummary>,
+    #[sea_orm(column_type = "Json")]
+    #[graphql(skip)]
+    pub scheduled_imports: Option<Vec<UserScheduledImport>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
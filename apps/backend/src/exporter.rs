@@ -10,9 +10,13 @@ use serde::{Deserialize, Serialize};
 use struson::writer::{JsonStreamWriter, JsonWriter};
 
 use crate::{
-    background::ApplicationJob, file_storage::FileStorageService,
-    fitness::resolver::ExerciseService, miscellaneous::resolver::MiscellaneousService,
-    models::ExportItem, traits::AuthProvider, utils::TEMP_DIR,
+    background::ApplicationJob,
+    file_storage::FileStorageService,
+    fitness::resolver::ExerciseService,
+    miscellaneous::resolver::MiscellaneousService,
+    models::ExportItem,
+    traits::AuthProvider,
+    utils::{TEMP_DIR, VERSION},
 };
 
 #[derive(Debug, Serialize, Deserialize, SimpleObject, Clone)]
@@ -97,6 +101,8 @@ impl ExporterService {
         let file = File::create(&export_path).unwrap();
         let mut writer = JsonStreamWriter::new(file);
         writer.begin_object().unwrap();
+        writer.name("version")?;
+        writer.string_value(VERSION)?;
         for export in to_export.iter() {
             writer.name(&export.to_string())?;
             writer.begin_array().unwrap();
@@ -19,6 +19,7 @@ use serde::{Deserialize, Serialize};
 use slug::slugify;
 use strum::IntoEnumIterator;
 use struson::writer::{JsonStreamWriter, JsonWriter};
+use surf::http::headers::USER_AGENT;
 use tracing::instrument;
 
 use crate::{
@@ -41,7 +42,7 @@ use crate::{
         ChangeCollectionToEntityInput, SearchDetails, SearchInput, SearchResults, StoredUrl,
     },
     traits::{AuthProvider, GraphqlRepresentation},
-    utils::{add_entity_to_collection, entity_in_collections, partial_user_by_id},
+    utils::{add_entity_to_collection, entity_in_collections, partial_user_by_id, user_agent},
 };
 
 static JSON_URL: &str =
@@ -318,6 +319,7 @@ impl ExerciseService {
 
     async fn get_all_exercises_from_dataset(&self) -> Result<Vec<GithubExercise>> {
         let data: Vec<GithubExercise> = surf::get(JSON_URL)
+            .header(USER_AGENT, user_agent())
             .send()
             .await
             .unwrap()
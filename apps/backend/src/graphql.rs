@@ -1,9 +1,9 @@
-use async_graphql::{extensions::Tracing, EmptySubscription, MergedObject, Schema};
+use async_graphql::{extensions::Tracing, MergedObject, MergedSubscription, Schema};
 
 use crate::{
     exporter::{ExporterMutation, ExporterQuery},
     fitness::resolver::{ExerciseMutation, ExerciseQuery},
-    importer::{ImporterMutation, ImporterQuery},
+    importer::{ImporterMutation, ImporterQuery, ImporterSubscription},
     miscellaneous::resolver::{MiscellaneousMutation, MiscellaneousQuery},
     utils::AppServices,
 };
@@ -24,13 +24,16 @@ pub struct MutationRoot(
     ExerciseMutation,
 );
 
-pub type GraphqlSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+#[derive(MergedSubscription, Default)]
+pub struct SubscriptionRoot(ImporterSubscription);
+
+pub type GraphqlSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
 pub async fn get_schema(app_services: &AppServices) -> GraphqlSchema {
     Schema::build(
         QueryRoot::default(),
         MutationRoot::default(),
-        EmptySubscription,
+        SubscriptionRoot::default(),
     )
     .extension(Tracing)
     .data(app_services.media_service.clone())
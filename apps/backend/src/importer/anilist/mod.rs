@@ -0,0 +1,259 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_graphql::Result;
+use chrono::NaiveDate;
+use database::{MediaSource, MetadataLot};
+use graphql_client::{GraphQLQuery, Response};
+use http_types::mime;
+use itertools::Itertools;
+use rs_utils::convert_naive_to_utc;
+use rust_decimal::{prelude::FromPrimitive, Decimal};
+use sea_orm::prelude::DateTimeUtc;
+use serde::Deserialize;
+use surf::{http::headers::ACCEPT, Client, StatusCode};
+
+use crate::{
+    importer::{
+        DeployAnilistImportInput, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportRatingScale, ImportResult,
+    },
+    miscellaneous::DefaultCollection,
+    models::media::{
+        CreateOrUpdateCollectionInput, ImportOrExportItemRating, ImportOrExportMediaItemSeen,
+    },
+    utils::get_base_http_client,
+};
+
+static URL: &str = "https://graphql.anilist.co";
+const PER_PAGE: i64 = 50;
+const MAX_RETRIES: usize = 5;
+const DEFAULT_RETRY_AFTER: u64 = 60;
+// DEV: Scores are requested in the `POINT_100` format
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Hundred;
+
+type Json = serde_json::Value;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "src/providers/anilist/schema.json",
+    query_path = "src/importer/anilist/media_list.graphql",
+    response_derives = "Debug,Clone",
+    variables_derives = "Debug"
+)]
+struct MediaListQuery;
+
+#[derive(Debug, Deserialize)]
+struct CustomList {
+    name: String,
+    enabled: bool,
+}
+
+pub async fn import(input: DeployAnilistImportInput) -> Result<ImportResult> {
+    let client = get_base_http_client(URL, vec![(ACCEPT, mime::JSON)]);
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let mut collections = vec![];
+    for (lot, media_type) in [
+        (MetadataLot::Anime, media_list_query::MediaType::ANIME),
+        (MetadataLot::Manga, media_list_query::MediaType::MANGA),
+    ] {
+        let mut page = 1;
+        loop {
+            tracing::debug!("Fetching {lot:?} list page {page} for {}", input.username);
+            let data = match fetch_page(
+                &client,
+                media_list_query::Variables {
+                    user_name: input.username.clone(),
+                    page,
+                    type_: media_type.clone(),
+                    per_page: PER_PAGE,
+                },
+            )
+            .await
+            {
+                Ok(d) => d,
+                Err(e) => {
+                    failed_items.push(ImportFailedItem::new(
+                        Some(lot),
+                        ImportFailStep::ItemDetailsFromSource,
+                        format!("Page {page}"),
+                        Some(e.to_string()),
+                    ));
+                    break;
+                }
+            };
+            let Some(data) = data.page else {
+                break;
+            };
+            for entry in data.media_list.into_iter().flatten().flatten() {
+                let Some(details) = entry.media else {
+                    continue;
+                };
+                let title = details
+                    .title
+                    .and_then(|t| t.user_preferred)
+                    .unwrap_or_else(|| details.id.to_string());
+                let mut item_collections = entry
+                    .custom_lists
+                    .and_then(|l| serde_json::from_value::<Vec<CustomList>>(l).ok())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|l| l.enabled)
+                    .map(|l| l.name)
+                    .collect_vec();
+                for name in item_collections.iter() {
+                    if !collections
+                        .iter()
+                        .any(|c: &CreateOrUpdateCollectionInput| &c.name == name)
+                    {
+                        collections.push(CreateOrUpdateCollectionInput {
+                            name: name.to_owned(),
+                            ..Default::default()
+                        });
+                    }
+                }
+                let progress = entry.progress.unwrap_or_default();
+                let mut seen_history = vec![];
+                match entry.status {
+                    Some(media_list_query::MediaListStatus::PLANNING) => {
+                        item_collections.push(DefaultCollection::Watchlist.to_string());
+                    }
+                    Some(
+                        media_list_query::MediaListStatus::CURRENT
+                        | media_list_query::MediaListStatus::REPEATING,
+                    ) => {
+                        item_collections.push(DefaultCollection::InProgress.to_string());
+                        seen_history = convert_progress(lot, progress, &entry.started_at, &None);
+                    }
+                    Some(media_list_query::MediaListStatus::COMPLETED) => {
+                        seen_history =
+                            convert_progress(lot, progress, &entry.started_at, &entry.completed_at);
+                    }
+                    _ => {}
+                }
+                let reviews = Vec::from_iter(
+                    entry
+                        .score
+                        .filter(|s| *s > 0.0)
+                        .and_then(Decimal::from_f64)
+                        .map(|r| ImportOrExportItemRating {
+                            rating: Some(RATING_SCALE.normalize(r.round_dp(2))),
+                            ..Default::default()
+                        }),
+                );
+                media.push(ImportOrExportMediaItem {
+                    source_id: title.clone(),
+                    lot,
+                    source: MediaSource::Anilist,
+                    identifier: "".to_string(),
+                    internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                        identifier: details.id.to_string(),
+                        title,
+                    }),
+                    seen_history,
+                    reviews,
+                    collections: item_collections,
+                    added_on: None,
+                    tags: vec![],
+                    images: vec![],
+                    monitored: None,
+                    matched_by_title: false,
+                    status: None,
+                });
+            }
+            let has_next_page = data
+                .page_info
+                .and_then(|p| p.has_next_page)
+                .unwrap_or(false);
+            if !has_next_page {
+                break;
+            }
+            page += 1;
+        }
+    }
+    Ok(ImportResult {
+        collections,
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        measurements: vec![],
+    })
+}
+
+async fn fetch_page(
+    client: &Client,
+    variables: media_list_query::Variables,
+) -> anyhow::Result<media_list_query::ResponseData> {
+    let body = MediaListQuery::build_query(variables);
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let mut rsp = client
+            .post("")
+            .body_json(&body)
+            .unwrap()
+            .send()
+            .await
+            .map_err(|e| anyhow!(e))?;
+        if rsp.status() == StatusCode::TooManyRequests && attempt < MAX_RETRIES {
+            let retry_after = rsp
+                .header("Retry-After")
+                .and_then(|h| h.as_str().parse::<u64>().ok())
+                .unwrap_or(DEFAULT_RETRY_AFTER);
+            tracing::debug!("Rate limited by AniList, retrying after {retry_after} seconds");
+            tokio::time::sleep(Duration::from_secs(retry_after)).await;
+            continue;
+        }
+        let data = rsp
+            .body_json::<Response<media_list_query::ResponseData>>()
+            .await
+            .map_err(|e| anyhow!(e))?;
+        if let Some(errors) = data.errors.filter(|e| !e.is_empty()) {
+            return Err(anyhow!(errors.into_iter().map(|e| e.message).join(", ")));
+        }
+        return data
+            .data
+            .ok_or_else(|| anyhow!("No data returned by AniList"));
+    }
+}
+
+fn convert_date(year: Option<i64>, month: Option<i64>, day: Option<i64>) -> Option<DateTimeUtc> {
+    NaiveDate::from_ymd_opt(
+        year? as i32,
+        month.unwrap_or(1) as u32,
+        day.unwrap_or(1) as u32,
+    )
+    .map(convert_naive_to_utc)
+}
+
+fn convert_progress(
+    lot: MetadataLot,
+    progress: i64,
+    started_at: &Option<media_list_query::MediaListQueryPageMediaListStartedAt>,
+    completed_at: &Option<media_list_query::MediaListQueryPageMediaListCompletedAt>,
+) -> Vec<ImportOrExportMediaItemSeen> {
+    let started_on = started_at
+        .as_ref()
+        .and_then(|d| convert_date(d.year, d.month, d.day));
+    let ended_on = completed_at
+        .as_ref()
+        .and_then(|d| convert_date(d.year, d.month, d.day));
+    let total = progress.max(0) as i32;
+    (1..=total)
+        .map(|num| ImportOrExportMediaItemSeen {
+            started_on: if num == 1 { started_on } else { None },
+            ended_on: if num == total { ended_on } else { None },
+            anime_episode_number: match lot {
+                MetadataLot::Anime => Some(num),
+                _ => None,
+            },
+            manga_chapter_number: match lot {
+                MetadataLot::Manga => Some(num),
+                _ => None,
+            },
+            ..Default::default()
+        })
+        .collect()
+}
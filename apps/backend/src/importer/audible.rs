@@ -0,0 +1,175 @@
+use std::fs;
+
+use async_graphql::Result;
+use chrono::{DateTime, NaiveDate};
+use csv::Reader;
+use database::{MediaSource, MetadataLot};
+use itertools::Itertools;
+use rs_utils::convert_naive_to_utc;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use sea_orm::prelude::DateTimeUtc;
+use serde::Deserialize;
+
+use crate::{
+    importer::{
+        DeployAudibleImportInput, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportResult,
+    },
+    miscellaneous::{resolver::Provider, DefaultCollection},
+    models::media::ImportOrExportMediaItemSeen,
+};
+
+// DEV: The headers differ between the tools that export the library, so the
+// common variants are accepted as aliases.
+#[derive(Debug, Deserialize)]
+struct LibraryItem {
+    #[serde(alias = "ASIN", default)]
+    asin: Option<String>,
+    #[serde(alias = "Title")]
+    title: String,
+    #[serde(alias = "Is Finished", alias = "Finished", default)]
+    is_finished: Option<String>,
+    #[serde(alias = "Percent Complete", alias = "Progress", default)]
+    percent_complete: Option<Decimal>,
+    #[serde(alias = "Date Added", default)]
+    date_added: Option<String>,
+    #[serde(alias = "Date Finished", default)]
+    date_finished: Option<String>,
+}
+
+fn parse_date(date: &str) -> Option<DateTimeUtc> {
+    DateTime::parse_from_rfc3339(date)
+        .map(|d| d.into())
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(date.get(..10)?, "%Y-%m-%d")
+                .ok()
+                .map(convert_naive_to_utc)
+        })
+}
+
+pub async fn import(
+    input: DeployAudibleImportInput,
+    audible_service: Option<&Provider>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::AudioBook;
+    let source = MediaSource::Audible;
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let export = fs::read_to_string(&input.csv)?;
+    let library_reader = Reader::from_reader(export.as_bytes())
+        .deserialize()
+        .collect_vec();
+    let total = library_reader.len();
+    for (idx, result) in library_reader.into_iter().enumerate() {
+        let record: LibraryItem = match result {
+            Ok(r) => r,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(e.to_string()),
+                ));
+                continue;
+            }
+        };
+        tracing::debug!(
+            "Getting details for {title:?} ({idx}/{total})",
+            title = record.title
+        );
+        let matched_by_title = record.asin.as_deref().map_or(true, |a| a.trim().is_empty());
+        let (identifier, title) = match (
+            record.asin.filter(|a| !a.trim().is_empty()),
+            audible_service,
+        ) {
+            (Some(asin), _) => (asin.trim().to_owned(), record.title.clone()),
+            (None, None) => (String::new(), record.title.clone()),
+            (None, Some(audible_service)) => {
+                let search = match audible_service
+                    .metadata_search(&record.title, None, true)
+                    .await
+                {
+                    Ok(s) => s.items,
+                    Err(e) => {
+                        failed_items.push(ImportFailedItem::new(
+                            Some(lot),
+                            ImportFailStep::MediaDetailsFromProvider,
+                            record.title,
+                            Some(e.to_string()),
+                        ));
+                        continue;
+                    }
+                };
+                let found = search
+                    .iter()
+                    .find(|s| s.title.eq_ignore_ascii_case(&record.title))
+                    .or_else(|| search.first());
+                let Some(found) = found else {
+                    failed_items.push(ImportFailedItem::new(
+                        Some(lot),
+                        ImportFailStep::MediaDetailsFromProvider,
+                        record.title,
+                        Some("No ASIN present and no matching audiobook found".to_owned()),
+                    ));
+                    continue;
+                };
+                (found.identifier.clone(), found.title.clone())
+            }
+        };
+        let is_finished = record
+            .is_finished
+            .map(|f| f.eq_ignore_ascii_case("true") || f.eq_ignore_ascii_case("yes"))
+            .unwrap_or(false);
+        let progress = record
+            .percent_complete
+            .and_then(|p| p.round().to_i32())
+            .filter(|p| *p > 0);
+        let mut seen_history = vec![];
+        let mut collections = vec![];
+        if is_finished || progress >= Some(100) {
+            seen_history.push(ImportOrExportMediaItemSeen {
+                ended_on: record
+                    .date_finished
+                    .or(record.date_added)
+                    .and_then(|d| parse_date(d.trim())),
+                ..Default::default()
+            });
+        } else if let Some(progress) = progress {
+            seen_history.push(ImportOrExportMediaItemSeen {
+                progress: Some(progress),
+                ..Default::default()
+            });
+            collections.push(DefaultCollection::InProgress.to_string());
+        } else {
+            collections.push(DefaultCollection::Watchlist.to_string());
+        }
+        media.push(ImportOrExportMediaItem {
+            source_id: record.title.clone(),
+            lot,
+            source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title,
+            }),
+            seen_history,
+            reviews: vec![],
+            collections,
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title,
+            status: None,
+        });
+    }
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
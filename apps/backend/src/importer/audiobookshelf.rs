@@ -96,24 +96,29 @@ pub async fn import(input: DeployAudiobookshelfImportInput) -> Result<ImportResu
                             }],
                             collections: vec![],
                             reviews: vec![],
+                            added_on: None,
+                            tags: vec![],
+                            images: vec![],
                             monitored: None,
+                            matched_by_title: false,
+                            status: None,
                         })
                     } else {
-                        failed_items.push(ImportFailedItem {
-                            error: Some("No ASIN found".to_string()),
-                            identifier: metadata.title.unwrap_or_default(),
-                            lot: Some(lot),
-                            step: ImportFailStep::InputTransformation,
-                        });
+                        failed_items.push(ImportFailedItem::new(
+                            Some(lot),
+                            ImportFailStep::InputTransformation,
+                            metadata.title.unwrap_or_default(),
+                            Some("No ASIN found".to_string()),
+                        ));
                     }
                 }
                 s => {
-                    failed_items.push(ImportFailedItem {
-                        error: Some(format!("Import of {s:#?} media type is not supported yet")),
-                        identifier: metadata.title.unwrap_or_default(),
-                        lot: None,
-                        step: ImportFailStep::ItemDetailsFromSource,
-                    });
+                    failed_items.push(ImportFailedItem::new(
+                        None,
+                        ImportFailStep::ItemDetailsFromSource,
+                        metadata.title.unwrap_or_default(),
+                        Some(format!("Import of {s:#?} media type is not supported yet")),
+                    ));
                 }
             }
         }
@@ -0,0 +1,179 @@
+use std::fs;
+
+use async_graphql::Result;
+use csv::Reader;
+use database::{MediaSource, MetadataLot};
+use itertools::Itertools;
+use rs_utils::{convert_naive_to_utc, convert_string_to_date};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sea_orm::prelude::DateTimeUtc;
+use serde::Deserialize;
+
+use crate::{
+    importer::{
+        find_by_exact_title, mark_current_seen_in_progress, DeployBackloggdImportInput,
+        ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem,
+        ImportRatingScale, ImportResult,
+    },
+    miscellaneous::{resolver::Provider, DefaultCollection},
+    models::media::{
+        ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportMediaItemSeen,
+    },
+};
+
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Five;
+const WISHLIST_COLLECTION: &str = "Wishlist";
+
+#[derive(Debug, Deserialize)]
+struct Game {
+    #[serde(rename = "Game", alias = "Title", alias = "Name")]
+    title: String,
+    #[serde(rename = "Status", default)]
+    status: Option<String>,
+    #[serde(rename = "Rating", default)]
+    rating: Option<Decimal>,
+    #[serde(rename = "Review", default)]
+    review: Option<String>,
+    #[serde(rename = "Start Date", default)]
+    start_date: Option<String>,
+    #[serde(rename = "Finish Date", default)]
+    finish_date: Option<String>,
+}
+
+#[derive(Debug)]
+enum Status {
+    Played,
+    Playing,
+    Backlog,
+    Wishlist,
+}
+
+impl Status {
+    // DEV: Played games can also have the status they were left in, for eg:
+    // "Completed" or "Abandoned"
+    fn parse(status: &str) -> Option<Self> {
+        match status.trim().to_lowercase().as_str() {
+            "played" | "completed" | "retired" | "shelved" | "abandoned" => Some(Self::Played),
+            "playing" => Some(Self::Playing),
+            "backlog" => Some(Self::Backlog),
+            "wishlist" => Some(Self::Wishlist),
+            _ => None,
+        }
+    }
+}
+
+fn parse_date(date: Option<&str>) -> Option<DateTimeUtc> {
+    convert_string_to_date(date?.trim()).map(convert_naive_to_utc)
+}
+
+pub async fn import(
+    input: DeployBackloggdImportInput,
+    igdb_service: Option<&Provider>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::VideoGame;
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let export = fs::read_to_string(&input.csv)?;
+    let games_reader = Reader::from_reader(export.as_bytes())
+        .deserialize()
+        .collect_vec();
+    let total = games_reader.len();
+    for (idx, result) in games_reader.into_iter().enumerate() {
+        let record: Game = match result {
+            Ok(r) => r,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(e.to_string()),
+                ));
+                continue;
+            }
+        };
+        tracing::debug!(
+            "Searching for {title:?} ({idx}/{total})",
+            title = record.title
+        );
+        let identifier = match find_by_exact_title(igdb_service, lot, &record.title).await {
+            Ok(identifier) => identifier,
+            Err(failed) => {
+                failed_items.push(failed);
+                continue;
+            }
+        };
+        let started_on = parse_date(record.start_date.as_deref());
+        let ended_on = parse_date(record.finish_date.as_deref());
+        let mut seen_history = vec![];
+        if started_on.is_some() || ended_on.is_some() {
+            seen_history.push(ImportOrExportMediaItemSeen {
+                started_on,
+                ended_on,
+                ..Default::default()
+            });
+        }
+        let mut collections = vec![];
+        match record.status.as_deref().and_then(Status::parse) {
+            Some(Status::Played) if seen_history.is_empty() => {
+                seen_history.push(ImportOrExportMediaItemSeen::default());
+            }
+            Some(Status::Playing) => {
+                collections.push(DefaultCollection::InProgress.to_string());
+                mark_current_seen_in_progress(&mut seen_history);
+            }
+            Some(Status::Backlog) => collections.push(DefaultCollection::Watchlist.to_string()),
+            Some(Status::Wishlist) => collections.push(WISHLIST_COLLECTION.to_owned()),
+            Some(Status::Played) | None => {}
+        }
+        let rating = record
+            .rating
+            .filter(|r| *r > dec!(0))
+            .map(|r| RATING_SCALE.normalize(r));
+        let review =
+            record
+                .review
+                .filter(|r| !r.trim().is_empty())
+                .map(|r| ImportOrExportItemReview {
+                    date: None,
+                    spoiler: Some(false),
+                    text: Some(r),
+                    visibility: None,
+                });
+        let mut reviews = vec![];
+        if review.is_some() || rating.is_some() {
+            reviews.push(ImportOrExportItemRating {
+                review,
+                rating,
+                ..Default::default()
+            });
+        }
+        media.push(ImportOrExportMediaItem {
+            source_id: record.title.clone(),
+            lot,
+            source: MediaSource::Igdb,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title: record.title,
+            }),
+            seen_history,
+            reviews,
+            collections,
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: true,
+            status: None,
+        });
+    }
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use async_graphql::{Error, Result};
+use chrono::{DateTime, Datelike, Utc};
+use database::{MediaSource, MetadataLot};
+use itertools::Itertools;
+use rust_decimal::Decimal;
+use sea_orm::prelude::DateTimeUtc;
+use sqlx::{sqlite::SqliteConnectOptions, ConnectOptions, SqliteConnection};
+
+use crate::{
+    importer::{
+        get_import_candidates, DeployCalibreImportInput, ImportFailStep, ImportFailedItem,
+        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportRatingScale, ImportResult,
+    },
+    miscellaneous::resolver::RateLimited,
+    models::media::{ImportOrExportItemRating, ImportOrExportMediaItemSeen},
+    providers::{google_books::GoogleBooksService, openlibrary::OpenlibraryService},
+    traits::MediaProvider,
+};
+
+// DEV: Ratings are given in half stars out of 5 and stored as a number out of 10
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Ten;
+const OWNED_COLLECTION: &str = "Owned";
+
+#[derive(Debug, Default)]
+struct Book {
+    title: String,
+    identifiers: HashMap<String, String>,
+    tags: Vec<String>,
+    rating: Option<i64>,
+    read_on: Option<DateTimeUtc>,
+}
+
+async fn fetch<T>(conn: &mut SqliteConnection, query: &str) -> Result<Vec<T>>
+where
+    T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+{
+    sqlx::query_as(query)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| Error::new(format!("Could not read the Calibre library: {e}")))
+}
+
+// DEV: Calibre stores dates that were never set as the first day of year 101
+fn parse_date(date: &str) -> Option<DateTimeUtc> {
+    DateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S%.f%:z")
+        .ok()
+        .filter(|d| d.year() > 101)
+        .map(|d| d.with_timezone(&Utc))
+}
+
+/// The custom column with the dates on which the books were read, which is the
+/// column with the given label or else the first date column that has "read" in
+/// its name.
+async fn read_date_column(conn: &mut SqliteConnection, label: Option<&str>) -> Result<Option<i64>> {
+    let columns: Vec<(i64, String, String)> = fetch(
+        conn,
+        "SELECT id, label, name FROM custom_columns WHERE datatype = 'datetime' ORDER BY id",
+    )
+    .await?;
+    let column = match label {
+        Some(label) => {
+            let label = label.trim_start_matches('#');
+            let Some(column) = columns.into_iter().find(|(_, l, _)| l == label) else {
+                return Err(Error::new(format!(
+                    "The Calibre library does not have a date column with the label #{label}"
+                )));
+            };
+            Some(column)
+        }
+        None => columns
+            .into_iter()
+            .find(|(_, l, n)| l.contains("read") || n.to_lowercase().contains("read")),
+    };
+    Ok(column.map(|(id, _, _)| id))
+}
+
+/// Read all the books from the library before anything is looked up, so that a
+/// file which is not a Calibre library fails the import right away.
+async fn read_books(input: &DeployCalibreImportInput) -> Result<Vec<Book>> {
+    let mut conn = SqliteConnectOptions::new()
+        .filename(&input.db_file)
+        .read_only(true)
+        .connect()
+        .await
+        .map_err(|e| Error::new(format!("Could not open the Calibre library: {e}")))?;
+    let mut books: HashMap<i64, Book> = fetch(&mut conn, "SELECT id, title FROM books")
+        .await?
+        .into_iter()
+        .map(|(id, title): (i64, String)| {
+            (
+                id,
+                Book {
+                    title,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+    let identifiers: Vec<(i64, String, String)> =
+        fetch(&mut conn, "SELECT book, type, val FROM identifiers").await?;
+    for (book, kind, value) in identifiers {
+        if let Some(book) = books.get_mut(&book) {
+            book.identifiers.insert(kind, value);
+        }
+    }
+    let tags: Vec<(i64, String)> = fetch(
+        &mut conn,
+        "SELECT l.book, t.name FROM books_tags_link l JOIN tags t ON t.id = l.tag ORDER BY t.name",
+    )
+    .await?;
+    for (book, tag) in tags {
+        if let Some(book) = books.get_mut(&book) {
+            book.tags.push(tag);
+        }
+    }
+    let ratings: Vec<(i64, i64)> = fetch(
+        &mut conn,
+        "SELECT l.book, r.rating FROM books_ratings_link l JOIN ratings r ON r.id = l.rating",
+    )
+    .await?;
+    for (book, rating) in ratings {
+        if let Some(book) = books.get_mut(&book) {
+            book.rating = Some(rating).filter(|r| *r > 0);
+        }
+    }
+    if let Some(column) = read_date_column(&mut conn, input.read_date_column.as_deref()).await? {
+        let dates: Vec<(i64, String)> = fetch(
+            &mut conn,
+            &format!("SELECT book, value FROM custom_column_{column}"),
+        )
+        .await?;
+        for (book, date) in dates {
+            if let Some(book) = books.get_mut(&book) {
+                book.read_on = parse_date(&date);
+            }
+        }
+    }
+    Ok(books
+        .into_values()
+        .sorted_by(|a, b| a.title.cmp(&b.title))
+        .collect())
+}
+
+pub async fn import(
+    input: DeployCalibreImportInput,
+    isbn_service: Option<&RateLimited<GoogleBooksService>>,
+    openlibrary_service: Option<&RateLimited<OpenlibraryService>>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::Book;
+    let books = read_books(&input).await?;
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let total = books.len();
+    for (idx, book) in books.into_iter().enumerate() {
+        tracing::debug!(
+            "Getting details for {title:?} ({idx}/{total})",
+            title = book.title
+        );
+        let isbn = book.identifiers.get("isbn").map(|i| i.replace('-', ""));
+        let services = isbn_service.zip(openlibrary_service);
+        let found = match (book.identifiers.get("google"), isbn.as_deref(), services) {
+            (Some(id), _, _) => Some((MediaSource::GoogleBooks, id.to_owned())),
+            (None, Some(isbn), Some((isbn_service, openlibrary_service))) => {
+                match openlibrary_service.request(|s| s.id_from_isbn(isbn)).await {
+                    Some(id) => Some((MediaSource::Openlibrary, id)),
+                    None => isbn_service
+                        .request(|s| s.id_from_isbn(isbn))
+                        .await
+                        .map(|id| (MediaSource::GoogleBooks, id)),
+                }
+            }
+            // DEV: Previews do not look up the ISBN, so the book only has its title
+            (None, Some(_), None) => Some((MediaSource::Openlibrary, String::new())),
+            (None, None, _) => None,
+        };
+        let Some((source, identifier)) = found else {
+            let candidates = match openlibrary_service {
+                Some(openlibrary_service) => openlibrary_service
+                    .request(|s| s.metadata_search(&book.title, None, true))
+                    .await
+                    .map(|s| get_import_candidates(&s.items))
+                    .unwrap_or_default(),
+                None => vec![],
+            };
+            let error = match isbn {
+                Some(isbn) => {
+                    format!("Could not convert ISBN: {isbn} to Openlibrary or Google Books ID")
+                }
+                None => "Book does not have an ISBN or Google Books ID".to_owned(),
+            };
+            failed_items.push(ImportFailedItem {
+                candidates,
+                ..ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::MediaDetailsFromProvider,
+                    book.title,
+                    Some(error),
+                )
+            });
+            continue;
+        };
+        let mut seen_history = vec![];
+        let mut collections = vec![];
+        match book.read_on {
+            Some(read_on) => seen_history.push(ImportOrExportMediaItemSeen {
+                ended_on: Some(read_on),
+                ..Default::default()
+            }),
+            None => collections.push(OWNED_COLLECTION.to_owned()),
+        }
+        let reviews = book
+            .rating
+            .map(|r| ImportOrExportItemRating {
+                rating: Some(RATING_SCALE.normalize(Decimal::from(r))),
+                ..Default::default()
+            })
+            .into_iter()
+            .collect();
+        media.push(ImportOrExportMediaItem {
+            source_id: book.title.clone(),
+            lot,
+            source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title: book.title,
+            }),
+            seen_history,
+            reviews,
+            collections,
+            added_on: None,
+            tags: book.tags,
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        });
+    }
+
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
@@ -0,0 +1,166 @@
+use std::fs;
+
+use async_graphql::Result;
+use csv::{Reader, StringRecord};
+use database::{MediaSource, MetadataLot};
+use rs_utils::{convert_naive_to_utc, convert_string_to_date};
+use rust_decimal::Decimal;
+use sea_orm::Iterable;
+
+use crate::{
+    importer::{
+        DeployGenericCsvImportInput, ImportFailStep, ImportFailedItem,
+        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportRatingScale, ImportResult,
+    },
+    models::media::{ImportOrExportItemRating, ImportOrExportMediaItemSeen},
+};
+
+fn get_column(headers: &StringRecord, name: &str) -> Result<usize> {
+    headers
+        .iter()
+        .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| format!("Column {name:?} is not present in the CSV file").into())
+}
+
+fn get_value(record: &StringRecord, column: Option<usize>) -> Option<&str> {
+    column
+        .and_then(|c| record.get(c))
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+}
+
+fn parse_enum<T: Iterable + ToString>(value: &str) -> Option<T> {
+    T::iter().find(|v| v.to_string().eq_ignore_ascii_case(value))
+}
+
+pub async fn import(input: DeployGenericCsvImportInput) -> Result<ImportResult> {
+    let mapping = input.column_mapping;
+    let rating_scale = input
+        .source_rating_scale
+        .unwrap_or(ImportRatingScale::Hundred);
+    let export = fs::read_to_string(&input.csv)?;
+    let mut reader = Reader::from_reader(export.as_bytes());
+    let headers = reader.headers()?.clone();
+    let title_column = get_column(&headers, &mapping.title)?;
+    let identifier_column = get_column(&headers, &mapping.identifier)?;
+    let lot_column = get_column(&headers, &mapping.lot)?;
+    let source_column = get_column(&headers, &mapping.source)?;
+    let rating_column = mapping
+        .rating
+        .map(|c| get_column(&headers, &c))
+        .transpose()?;
+    let watched_date_column = mapping
+        .watched_date
+        .map(|c| get_column(&headers, &c))
+        .transpose()?;
+
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    for (idx, result) in reader.records().enumerate() {
+        let record = match result {
+            Ok(r) => r,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    None,
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(e.to_string()),
+                ));
+                continue;
+            }
+        };
+        let title = get_value(&record, Some(title_column))
+            .map(String::from)
+            .unwrap_or_else(|| idx.to_string());
+        let Some(identifier) = get_value(&record, Some(identifier_column)) else {
+            failed_items.push(ImportFailedItem::new(
+                None,
+                ImportFailStep::InputTransformation,
+                title,
+                Some("Identifier is empty".to_owned()),
+            ));
+            continue;
+        };
+        let lot_value = get_value(&record, Some(lot_column)).unwrap_or_default();
+        let Some(lot) = parse_enum::<MetadataLot>(lot_value) else {
+            failed_items.push(ImportFailedItem::new(
+                None,
+                ImportFailStep::InputTransformation,
+                title,
+                Some(format!("{lot_value:?} is not a valid lot")),
+            ));
+            continue;
+        };
+        let source_value = get_value(&record, Some(source_column)).unwrap_or_default();
+        let Some(source) = parse_enum::<MediaSource>(source_value) else {
+            failed_items.push(ImportFailedItem::new(
+                Some(lot),
+                ImportFailStep::InputTransformation,
+                title,
+                Some(format!("{source_value:?} is not a valid source")),
+            ));
+            continue;
+        };
+        let rating = match get_value(&record, rating_column).map(|r| r.parse::<Decimal>()) {
+            Some(Ok(r)) => Some(rating_scale.normalize(r)),
+            Some(Err(e)) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    title,
+                    Some(format!("Rating could not be parsed: {e}")),
+                ));
+                continue;
+            }
+            None => None,
+        };
+        let seen_history = match get_value(&record, watched_date_column) {
+            Some(d) => match convert_string_to_date(d) {
+                Some(d) => vec![ImportOrExportMediaItemSeen {
+                    ended_on: Some(convert_naive_to_utc(d)),
+                    ..Default::default()
+                }],
+                None => {
+                    failed_items.push(ImportFailedItem::new(
+                        Some(lot),
+                        ImportFailStep::InputTransformation,
+                        title,
+                        Some(format!("Watched date {d:?} could not be parsed")),
+                    ));
+                    continue;
+                }
+            },
+            None => vec![],
+        };
+        media.push(ImportOrExportMediaItem {
+            source_id: title.clone(),
+            lot,
+            source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier: identifier.to_owned(),
+                title,
+            }),
+            seen_history,
+            reviews: Vec::from_iter(rating.map(|r| ImportOrExportItemRating {
+                rating: Some(r),
+                ..Default::default()
+            })),
+            collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        });
+    }
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
@@ -6,45 +6,74 @@ use convert_case::{Case, Casing};
 use csv::Reader;
 use database::{MediaSource, MetadataLot};
 use itertools::Itertools;
+use rs_utils::convert_naive_to_utc;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::Deserialize;
 
 use crate::{
-    importer::{DeployGoodreadsImportInput, ImportFailStep, ImportFailedItem, ImportResult},
+    importer::{
+        get_import_candidates, mark_current_seen_in_progress, DeployGoodreadsImportInput,
+        ImportFailStep, ImportFailedItem, ImportRatingScale, ImportResult,
+    },
+    miscellaneous::resolver::RateLimited,
+    miscellaneous::DefaultCollection,
     models::media::{
         ImportOrExportItemIdentifier, ImportOrExportItemRating, ImportOrExportItemReview,
-        ImportOrExportMediaItem, ImportOrExportMediaItemSeen,
+        ImportOrExportItemStatus, ImportOrExportMediaItem, ImportOrExportMediaItemSeen,
     },
-    providers::google_books::GoogleBooksService,
+    providers::{google_books::GoogleBooksService, openlibrary::OpenlibraryService},
+    traits::MediaProvider,
 };
 
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Five;
+
+static EXCLUSIVE_SHELVES: [&str; 5] = [
+    "read",
+    "currently-reading",
+    "to-read",
+    "dnf",
+    "did-not-finish",
+];
+
 #[derive(Debug, Deserialize)]
 struct Book {
     #[serde(rename = "Book Id")]
     id: String,
     #[serde(rename = "Title")]
     title: String,
+    #[serde(rename = "ISBN")]
+    isbn: String,
     #[serde(rename = "ISBN13")]
     isbn13: String,
     #[serde(rename = "My Rating")]
     rating: Decimal,
     #[serde(rename = "Date Read")]
     date_read: Option<String>,
+    #[serde(rename = "Date Added", default)]
+    date_added: Option<String>,
     #[serde(rename = "Bookshelves")]
-    bookshelf: String,
+    bookshelves: String,
+    #[serde(rename = "Exclusive Shelf", default)]
+    exclusive_shelf: String,
     #[serde(rename = "My Review")]
     review: String,
     #[serde(rename = "Read Count")]
     read_count: usize,
 }
 
+// DEV: Goodreads wraps ISBNs like `="9780140449136"` so that spreadsheets do
+// not treat them as numbers
+fn clean_isbn(isbn: &str) -> String {
+    isbn.trim_start_matches('=').trim_matches('"').to_owned()
+}
+
 pub async fn import(
     input: DeployGoodreadsImportInput,
-    isbn_service: &GoogleBooksService,
+    isbn_service: Option<&RateLimited<GoogleBooksService>>,
+    openlibrary_service: Option<&RateLimited<OpenlibraryService>>,
 ) -> Result<ImportResult> {
     let lot = MetadataLot::Book;
-    let source = MediaSource::GoogleBooks;
     let mut media = vec![];
     let mut failed_items = vec![];
     let export = fs::read_to_string(&input.csv_path)?;
@@ -56,12 +85,12 @@ pub async fn import(
         let record: Book = match result {
             Ok(r) => r,
             Err(e) => {
-                failed_items.push(ImportFailedItem {
-                    lot: Some(lot),
-                    step: ImportFailStep::InputTransformation,
-                    identifier: idx.to_string(),
-                    error: Some(e.to_string()),
-                });
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(e.to_string()),
+                ));
                 continue;
             }
         };
@@ -69,49 +98,87 @@ pub async fn import(
             "Getting details for {title:?} ({idx}/{total})",
             title = record.title
         );
-        let isbn = record.isbn13[2..record.isbn13.len() - 1].to_owned();
+        let isbn = match clean_isbn(&record.isbn13) {
+            i if i.is_empty() => clean_isbn(&record.isbn),
+            i => i,
+        };
         if isbn.is_empty() {
+            let candidates = match isbn_service {
+                Some(isbn_service) => isbn_service
+                    .request(|s| s.metadata_search(&record.title, None, true))
+                    .await
+                    .map(|s| get_import_candidates(&s.items))
+                    .unwrap_or_default(),
+                None => vec![],
+            };
             failed_items.push(ImportFailedItem {
-                lot: Some(lot),
-                step: ImportFailStep::InputTransformation,
-                identifier: record.title,
-                error: Some("ISBN is empty".to_owned()),
+                candidates,
+                ..ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::MediaDetailsFromProvider,
+                    record.title,
+                    Some("ISBN is empty".to_owned()),
+                )
             });
             continue;
         }
-        if let Some(identifier) = isbn_service.id_from_isbn(&isbn).await {
-            let mut seen_history = vec![
-                ImportOrExportMediaItemSeen {
-                    started_on: None,
-                    ended_on: None,
+        let found = match isbn_service.zip(openlibrary_service) {
+            Some((isbn_service, openlibrary_service)) => {
+                match isbn_service.request(|s| s.id_from_isbn(&isbn)).await {
+                    Some(id) => Some((MediaSource::GoogleBooks, id)),
+                    None => openlibrary_service
+                        .request(|s| s.id_from_isbn(&isbn))
+                        .await
+                        .map(|id| (MediaSource::Openlibrary, id)),
+                }
+            }
+            // DEV: Previews do not look up the ISBN, so the book only has its title
+            None => Some((MediaSource::GoogleBooks, String::new())),
+        };
+        if let Some((source, identifier)) = found {
+            let mut seen_history = vec![];
+            if record.read_count > 0 {
+                seen_history.push(ImportOrExportMediaItemSeen {
+                    times: Some(record.read_count.try_into().unwrap()),
                     ..Default::default()
-                };
-                record.read_count
-            ];
+                });
+            }
             if let Some(w) = record.date_read {
                 let w = NaiveDate::parse_from_str(&w, "%Y/%m/%d").unwrap();
                 let read_at = Some(DateTime::from_naive_utc_and_offset(
                     NaiveDateTime::new(w, NaiveTime::from_hms_opt(0, 0, 0).unwrap()),
                     Utc,
                 ));
-                seen_history.first_mut().unwrap().ended_on = read_at;
+                match seen_history.first_mut() {
+                    Some(s) => s.ended_on = read_at,
+                    None => seen_history.push(ImportOrExportMediaItemSeen {
+                        ended_on: read_at,
+                        ..Default::default()
+                    }),
+                }
             }
-            let mut collections = vec![];
-            if !record.bookshelf.is_empty() {
-                collections.push(match record.bookshelf.as_str() {
-                    "to-read" => "Watchlist".to_owned(),
-                    "currently-reading" => "In Progress".to_owned(),
-                    s => s.to_case(Case::Title),
-                });
+            let mut collections = record
+                .bookshelves
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty() && !EXCLUSIVE_SHELVES.contains(s))
+                .map(|s| s.to_case(Case::Title))
+                .collect_vec();
+            let mut status = None;
+            match record.exclusive_shelf.as_str() {
+                "to-read" => collections.push(DefaultCollection::Watchlist.to_string()),
+                // DEV: Goodreads does not have a shelf for this, but it is the name
+                // most people give to the one they create
+                "dnf" | "did-not-finish" => status = Some(ImportOrExportItemStatus::Dropped),
+                "currently-reading" => {
+                    collections.push(DefaultCollection::InProgress.to_string());
+                    mark_current_seen_in_progress(&mut seen_history);
+                }
+                _ => {}
             }
             let mut rating = None;
             if record.rating > dec!(0) {
-                rating = Some(
-                    record
-                        .rating
-                        // DEV: Rates items out of 10
-                        .saturating_mul(dec!(10)),
-                );
+                rating = Some(RATING_SCALE.normalize(record.rating));
             }
             let mut review = None;
             if !record.review.is_empty() {
@@ -142,18 +209,26 @@ pub async fn import(
                 seen_history,
                 reviews,
                 collections,
+                added_on: record
+                    .date_added
+                    .and_then(|d| NaiveDate::parse_from_str(&d, "%Y/%m/%d").ok())
+                    .map(convert_naive_to_utc),
+                tags: vec![],
+                images: vec![],
                 monitored: None,
+                matched_by_title: false,
+                status,
             });
         } else {
-            failed_items.push(ImportFailedItem {
-                lot: Some(lot),
-                step: ImportFailStep::InputTransformation,
-                identifier: record.title,
-                error: Some(format!(
-                    "Could not convert ISBN: {} to Google Books ID",
+            failed_items.push(ImportFailedItem::new(
+                Some(lot),
+                ImportFailStep::InputTransformation,
+                record.title,
+                Some(format!(
+                    "Could not convert ISBN: {} to Google Books or Openlibrary ID",
                     isbn,
                 )),
-            })
+            ))
         }
     }
     Ok(ImportResult {
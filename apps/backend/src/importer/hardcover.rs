@@ -0,0 +1,306 @@
+use anyhow::anyhow;
+use async_graphql::Result;
+use chrono::NaiveDate;
+use database::{MediaSource, MetadataLot};
+use http_types::mime;
+use itertools::Itertools;
+use rs_utils::convert_naive_to_utc;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sea_orm::prelude::DateTimeUtc;
+use serde::Deserialize;
+use serde_json::json;
+use surf::{
+    http::headers::{ACCEPT, AUTHORIZATION},
+    Client,
+};
+
+use crate::{
+    importer::{
+        mark_current_seen_in_progress, DeployHardcoverImportInput, ImportFailStep,
+        ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportRatingScale,
+        ImportResult,
+    },
+    miscellaneous::resolver::RateLimited,
+    miscellaneous::DefaultCollection,
+    models::media::{ImportOrExportItemRating, ImportOrExportMediaItemSeen},
+    providers::{google_books::GoogleBooksService, openlibrary::OpenlibraryService},
+    utils::get_base_http_client,
+};
+
+static URL: &str = "https://api.hardcover.app/v1/graphql";
+const PER_PAGE: usize = 100;
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Five;
+
+const USER_BOOKS_QUERY: &str = r#"
+query UserBooks($limit: Int!, $offset: Int!) {
+  me {
+    user_books(limit: $limit, offset: $offset, order_by: { id: asc }) {
+      status_id
+      rating
+      book { title }
+      edition { isbn_13 isbn_10 }
+      user_book_reads(order_by: { id: asc }) { started_at finished_at }
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Deserialize)]
+struct GraphqlError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphqlResponse {
+    data: Option<Data>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Data {
+    me: Vec<Me>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Me {
+    user_books: Vec<UserBook>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Book {
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Edition {
+    isbn_13: Option<String>,
+    isbn_10: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Read {
+    started_at: Option<String>,
+    finished_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserBook {
+    status_id: i32,
+    rating: Option<Decimal>,
+    book: Book,
+    edition: Option<Edition>,
+    #[serde(default)]
+    user_book_reads: Vec<Read>,
+}
+
+// DEV: These are the ids of the statuses on Hardcover
+#[derive(Debug)]
+enum Status {
+    WantToRead,
+    CurrentlyReading,
+    Read,
+    Other,
+}
+
+impl From<i32> for Status {
+    fn from(id: i32) -> Self {
+        match id {
+            1 => Self::WantToRead,
+            2 => Self::CurrentlyReading,
+            3 => Self::Read,
+            _ => Self::Other,
+        }
+    }
+}
+
+fn parse_date(date: Option<&str>) -> Option<DateTimeUtc> {
+    NaiveDate::parse_from_str(date?, "%Y-%m-%d")
+        .ok()
+        .map(convert_naive_to_utc)
+}
+
+async fn fetch_page(client: &Client, offset: usize) -> anyhow::Result<Vec<UserBook>> {
+    let rsp: GraphqlResponse = client
+        .post("")
+        .body_json(&json!({
+            "query": USER_BOOKS_QUERY,
+            "variables": { "limit": PER_PAGE, "offset": offset },
+        }))
+        .unwrap()
+        .await
+        .map_err(|e| anyhow!(e))?
+        .body_json()
+        .await
+        .map_err(|e| anyhow!(e))?;
+    if !rsp.errors.is_empty() {
+        return Err(anyhow!(rsp
+            .errors
+            .into_iter()
+            .map(|e| e.message)
+            .join(", ")));
+    }
+    let me = rsp
+        .data
+        .and_then(|d| d.me.into_iter().next())
+        .ok_or_else(|| anyhow!("No user returned by Hardcover"))?;
+    Ok(me.user_books)
+}
+
+pub async fn import(
+    input: DeployHardcoverImportInput,
+    isbn_service: Option<&RateLimited<GoogleBooksService>>,
+    openlibrary_service: Option<&RateLimited<OpenlibraryService>>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::Book;
+    // DEV: The token shown in the Hardcover settings already starts with "Bearer"
+    let token = input.api_token.trim().trim_start_matches("Bearer ");
+    let client = get_base_http_client(
+        URL,
+        vec![
+            (ACCEPT, mime::JSON.to_string()),
+            (AUTHORIZATION, format!("Bearer {token}")),
+        ],
+    );
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let mut user_books = vec![];
+    let mut offset = 0;
+    loop {
+        tracing::debug!("Fetching user books from offset {offset}");
+        match fetch_page(&client, offset).await {
+            Ok(books) => {
+                let fetched = books.len();
+                user_books.extend(books);
+                if fetched < PER_PAGE {
+                    break;
+                }
+                offset += fetched;
+            }
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::ItemDetailsFromSource,
+                    format!("Books from {offset}"),
+                    Some(e.to_string()),
+                ));
+                break;
+            }
+        }
+    }
+
+    let total = user_books.len();
+    for (idx, user_book) in user_books.into_iter().enumerate() {
+        let title = user_book.book.title;
+        tracing::debug!("Getting details for {title:?} ({idx}/{total})");
+        let isbns = user_book
+            .edition
+            .map(|e| [e.isbn_13, e.isbn_10])
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|i| !i.trim().is_empty())
+            .collect_vec();
+        if isbns.is_empty() {
+            failed_items.push(ImportFailedItem::new(
+                Some(lot),
+                ImportFailStep::InputTransformation,
+                title,
+                Some("No ISBN found".to_owned()),
+            ));
+            continue;
+        }
+        let found = match isbn_service.zip(openlibrary_service) {
+            Some((isbn_service, openlibrary_service)) => {
+                let mut found = None;
+                for isbn in isbns.iter() {
+                    found = match isbn_service.request(|s| s.id_from_isbn(isbn)).await {
+                        Some(id) => Some((MediaSource::GoogleBooks, id)),
+                        None => openlibrary_service
+                            .request(|s| s.id_from_isbn(isbn))
+                            .await
+                            .map(|id| (MediaSource::Openlibrary, id)),
+                    };
+                    if found.is_some() {
+                        break;
+                    }
+                }
+                found
+            }
+            // DEV: Previews do not look up the ISBN, so the book only has its title
+            None => Some((MediaSource::GoogleBooks, String::new())),
+        };
+        let Some((source, identifier)) = found else {
+            failed_items.push(ImportFailedItem::new(
+                Some(lot),
+                ImportFailStep::InputTransformation,
+                title,
+                Some(format!(
+                    "Could not convert ISBN: {} to Google Books or Openlibrary ID",
+                    isbns.join(", ")
+                )),
+            ));
+            continue;
+        };
+        let status = Status::from(user_book.status_id);
+        let mut seen_history = user_book
+            .user_book_reads
+            .iter()
+            .map(|r| ImportOrExportMediaItemSeen {
+                started_on: parse_date(r.started_at.as_deref()),
+                ended_on: parse_date(r.finished_at.as_deref()),
+                ..Default::default()
+            })
+            .collect_vec();
+        let mut collections = vec![];
+        match status {
+            Status::WantToRead => {
+                seen_history.clear();
+                collections.push(DefaultCollection::Watchlist.to_string());
+            }
+            Status::CurrentlyReading => {
+                collections.push(DefaultCollection::InProgress.to_string());
+                mark_current_seen_in_progress(&mut seen_history);
+            }
+            Status::Read if seen_history.is_empty() => {
+                seen_history.push(ImportOrExportMediaItemSeen::default());
+            }
+            Status::Read | Status::Other => {}
+        }
+        let reviews = Vec::from_iter(user_book.rating.filter(|r| *r > dec!(0)).map(|r| {
+            ImportOrExportItemRating {
+                rating: Some(RATING_SCALE.normalize(r)),
+                ..Default::default()
+            }
+        }));
+        media.push(ImportOrExportMediaItem {
+            source_id: title.clone(),
+            lot,
+            source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title,
+            }),
+            seen_history,
+            reviews,
+            collections,
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        });
+    }
+
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
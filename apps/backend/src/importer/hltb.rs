@@ -0,0 +1,125 @@
+use std::fs;
+
+use async_graphql::Result;
+use csv::Reader;
+use database::{MediaSource, MetadataLot};
+use itertools::Itertools;
+use rs_utils::{convert_naive_to_utc, convert_string_to_date};
+use serde::Deserialize;
+
+use crate::{
+    importer::{
+        find_by_exact_title, DeployHltbImportInput, ImportFailStep, ImportFailedItem,
+        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+    },
+    miscellaneous::{resolver::Provider, DefaultCollection},
+    models::media::ImportOrExportMediaItemSeen,
+};
+
+#[derive(Debug, Deserialize)]
+struct Game {
+    #[serde(rename = "Game")]
+    title: String,
+    #[serde(rename = "Playing", default)]
+    playing: Option<String>,
+    #[serde(rename = "Backlog", default)]
+    backlog: Option<String>,
+    #[serde(rename = "Completed", default)]
+    completed: Option<String>,
+    #[serde(rename = "Completion Date", default)]
+    completion_date: Option<String>,
+}
+
+// DEV: The status columns contain a marker (or the list name) when the game is
+// in that list and are empty otherwise
+fn is_set(value: &Option<String>) -> bool {
+    value.as_ref().map_or(false, |v| {
+        let v = v.trim();
+        !v.is_empty() && v != "0" && !v.eq_ignore_ascii_case("false")
+    })
+}
+
+pub async fn import(
+    input: DeployHltbImportInput,
+    igdb_service: Option<&Provider>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::VideoGame;
+    let source = MediaSource::Igdb;
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let export = fs::read_to_string(&input.csv)?;
+    let games_reader = Reader::from_reader(export.as_bytes())
+        .deserialize()
+        .collect_vec();
+    let total = games_reader.len();
+    for (idx, result) in games_reader.into_iter().enumerate() {
+        let record: Game = match result {
+            Ok(r) => r,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(e.to_string()),
+                ));
+                continue;
+            }
+        };
+        tracing::debug!(
+            "Searching for {title:?} ({idx}/{total})",
+            title = record.title
+        );
+        let identifier = match find_by_exact_title(igdb_service, lot, &record.title).await {
+            Ok(identifier) => identifier,
+            Err(failed) => {
+                failed_items.push(failed);
+                continue;
+            }
+        };
+        let mut seen_history = vec![];
+        let mut collections = vec![];
+        if is_set(&record.completed) {
+            seen_history.push(ImportOrExportMediaItemSeen {
+                ended_on: record
+                    .completion_date
+                    .as_deref()
+                    .and_then(|d| convert_string_to_date(d.trim()))
+                    .map(convert_naive_to_utc),
+                ..Default::default()
+            });
+        }
+        if is_set(&record.playing) {
+            collections.push(DefaultCollection::InProgress.to_string());
+        }
+        if is_set(&record.backlog) {
+            collections.push(DefaultCollection::Watchlist.to_string());
+        }
+        media.push(ImportOrExportMediaItem {
+            source_id: record.title.clone(),
+            lot,
+            source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title: record.title,
+            }),
+            seen_history,
+            reviews: vec![],
+            collections,
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: true,
+            status: None,
+        });
+    }
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
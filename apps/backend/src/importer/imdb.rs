@@ -0,0 +1,143 @@
+use std::fs;
+
+use async_graphql::Result;
+use chrono::NaiveDate;
+use csv::Reader;
+use database::{MediaSource, MetadataLot};
+use itertools::Itertools;
+use rs_utils::convert_naive_to_utc;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    importer::{
+        DeployImdbImportInput, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportRatingScale, ImportResult,
+    },
+    miscellaneous::resolver::RateLimited,
+    models::media::{ImportOrExportItemRating, ImportOrExportMediaItemSeen},
+    providers::tmdb::NonMediaTmdbService,
+};
+
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Ten;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Item {
+    #[serde(rename = "Const")]
+    id: String,
+    #[serde(rename = "Title")]
+    title: String,
+    #[serde(rename = "Title Type")]
+    title_type: String,
+    #[serde(rename = "Your Rating", default)]
+    rating: Option<Decimal>,
+    #[serde(rename = "Date Rated", default)]
+    date_rated: Option<NaiveDate>,
+}
+
+fn lot_from_title_type(title_type: &str) -> MetadataLot {
+    match title_type {
+        "tvSeries" | "tvMiniSeries" => MetadataLot::Show,
+        _ => MetadataLot::Movie,
+    }
+}
+
+pub async fn import(
+    input: DeployImdbImportInput,
+    tmdb_service: Option<&RateLimited<NonMediaTmdbService>>,
+) -> Result<ImportResult> {
+    let source = MediaSource::Tmdb;
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let export = fs::read_to_string(&input.csv)?;
+    let items_reader = Reader::from_reader(export.as_bytes())
+        .deserialize()
+        .collect_vec();
+    let total = items_reader.len();
+    for (idx, result) in items_reader.into_iter().enumerate() {
+        let record: Item = match result {
+            Ok(r) => r,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    None,
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(e.to_string()),
+                ));
+                continue;
+            }
+        };
+        if record.title_type == "tvEpisode" {
+            failed_items.push(ImportFailedItem::new(
+                None,
+                ImportFailStep::InputTransformation,
+                record.title,
+                Some("Importing individual episodes is not supported".to_owned()),
+            ));
+            continue;
+        }
+        tracing::debug!(
+            "Getting details for {title:?} ({idx}/{total})",
+            title = record.title
+        );
+        let found = match tmdb_service {
+            Some(tmdb_service) => {
+                tmdb_service
+                    .request(|s| s.find_by_external_id(&record.id, "imdb_id"))
+                    .await
+            }
+            // DEV: Previews do not look up the title, so the type comes from the export
+            None => Ok((lot_from_title_type(&record.title_type), String::new())),
+        };
+        let (lot, identifier) = match found {
+            Ok(d) => d,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    None,
+                    ImportFailStep::ItemDetailsFromSource,
+                    record.title,
+                    Some(e.to_string()),
+                ));
+                continue;
+            }
+        };
+        // DEV: Shows need episode information to be marked as seen
+        let ended_on = match lot {
+            MetadataLot::Movie => record.date_rated.map(convert_naive_to_utc),
+            _ => None,
+        };
+        media.push(ImportOrExportMediaItem {
+            source_id: record.id,
+            lot,
+            source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title: record.title,
+            }),
+            seen_history: Vec::from_iter(ended_on.map(|d| ImportOrExportMediaItemSeen {
+                ended_on: Some(d),
+                ..Default::default()
+            })),
+            reviews: Vec::from_iter(record.rating.map(|r| ImportOrExportItemRating {
+                rating: Some(RATING_SCALE.normalize(r)),
+                ..Default::default()
+            })),
+            collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        });
+    }
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
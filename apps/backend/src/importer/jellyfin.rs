@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use async_graphql::Result;
+use database::{MediaSource, MetadataLot};
+use http_types::mime;
+use sea_orm::prelude::DateTimeUtc;
+use serde::Deserialize;
+use serde_json::json;
+use surf::{http::headers::ACCEPT, Client};
+
+use crate::{
+    importer::{
+        DeployJellyfinImportInput, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportResult,
+    },
+    miscellaneous::resolver::RateLimited,
+    models::media::ImportOrExportMediaItemSeen,
+    providers::tmdb::NonMediaTmdbService,
+    utils::get_base_http_client,
+};
+
+const PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+struct ProviderIds {
+    tmdb: Option<String>,
+    imdb: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct UserData {
+    last_played_date: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Item {
+    id: String,
+    name: String,
+    #[serde(rename = "Type")]
+    item_type: String,
+    #[serde(default)]
+    provider_ids: ProviderIds,
+    user_data: Option<UserData>,
+    series_id: Option<String>,
+    series_name: Option<String>,
+    parent_index_number: Option<i32>,
+    index_number: Option<i32>,
+    #[serde(default)]
+    image_tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ItemsResponse {
+    items: Vec<Item>,
+    total_record_count: usize,
+}
+
+async fn get_items(client: &Client, user_id: &str, query: serde_json::Value) -> Result<Vec<Item>> {
+    let mut items = vec![];
+    loop {
+        let mut query = query.clone();
+        query["StartIndex"] = json!(items.len());
+        query["Limit"] = json!(PAGE_SIZE);
+        let page: ItemsResponse = client
+            .get(&format!("Users/{}/Items", user_id))
+            .query(&query)
+            .unwrap()
+            .await
+            .map_err(|e| anyhow!(e))?
+            .body_json()
+            .await
+            .map_err(|e| anyhow!(e))?;
+        let count = page.items.len();
+        items.extend(page.items);
+        if count == 0 || items.len() >= page.total_record_count {
+            break;
+        }
+    }
+    Ok(items)
+}
+
+async fn get_tmdb_identifier(
+    ids: &ProviderIds,
+    lot: MetadataLot,
+    tmdb_service: Option<&RateLimited<NonMediaTmdbService>>,
+) -> std::result::Result<String, String> {
+    if let Some(id) = &ids.tmdb {
+        return Ok(id.to_owned());
+    }
+    let Some(id) = &ids.imdb else {
+        return Err("Item does not have a TMDB or IMDb id".to_owned());
+    };
+    // DEV: Previews do not look up the IMDb id, so the item only has its title
+    let Some(tmdb_service) = tmdb_service else {
+        return Ok(String::new());
+    };
+    match tmdb_service
+        .request(|s| s.find_by_external_id(id, "imdb_id"))
+        .await
+    {
+        Ok((found_lot, identifier)) if found_lot == lot => Ok(identifier),
+        Ok(_) => Err(format!("No {lot} found on TMDB for IMDb id {id}")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub async fn import(
+    input: DeployJellyfinImportInput,
+    tmdb_service: Option<&RateLimited<NonMediaTmdbService>>,
+) -> Result<ImportResult> {
+    let client = get_base_http_client(
+        &format!("{}/", input.base_url),
+        vec![
+            (ACCEPT, mime::JSON.to_string()),
+            ("X-Emby-Token".into(), input.api_key),
+        ],
+    );
+    let mut failed_items = vec![];
+    let played = get_items(
+        &client,
+        &input.user_id,
+        json!({
+            "Recursive": true,
+            "IsPlayed": true,
+            "IncludeItemTypes": "Movie,Episode",
+            "Fields": "ProviderIds",
+        }),
+    )
+    .await?;
+    tracing::debug!("Got {} played items from Jellyfin", played.len());
+
+    // DEV: Episodes are grouped under the series they belong to
+    let mut grouped: HashMap<String, (MetadataLot, Vec<ImportOrExportMediaItemSeen>)> =
+        HashMap::new();
+    let mut movies = HashMap::new();
+    for item in played {
+        let ended_on = item.user_data.as_ref().and_then(|u| u.last_played_date);
+        match item.item_type.as_str() {
+            "Movie" => {
+                grouped.insert(
+                    item.id.clone(),
+                    (
+                        MetadataLot::Movie,
+                        vec![ImportOrExportMediaItemSeen {
+                            ended_on,
+                            ..Default::default()
+                        }],
+                    ),
+                );
+                movies.insert(item.id.clone(), item);
+            }
+            "Episode" => {
+                let (Some(series_id), Some(season), Some(episode)) =
+                    (item.series_id, item.parent_index_number, item.index_number)
+                else {
+                    failed_items.push(ImportFailedItem::new(
+                        Some(MetadataLot::Show),
+                        ImportFailStep::ItemDetailsFromSource,
+                        item.series_name.unwrap_or(item.name),
+                        Some("Episode does not have a series, season or episode number".to_owned()),
+                    ));
+                    continue;
+                };
+                let seen = ImportOrExportMediaItemSeen {
+                    ended_on,
+                    show_season_number: Some(season),
+                    show_episode_number: Some(episode),
+                    ..Default::default()
+                };
+                grouped
+                    .entry(series_id)
+                    .or_insert_with(|| (MetadataLot::Show, vec![]))
+                    .1
+                    .push(seen);
+            }
+            _ => continue,
+        }
+    }
+
+    let series_ids = grouped
+        .iter()
+        .filter(|(_, (lot, _))| *lot == MetadataLot::Show)
+        .map(|(id, _)| id.to_owned())
+        .collect::<Vec<_>>();
+    let mut series = HashMap::new();
+    for ids in series_ids.chunks(PAGE_SIZE) {
+        let items = get_items(
+            &client,
+            &input.user_id,
+            json!({ "Ids": ids.join(","), "Fields": "ProviderIds" }),
+        )
+        .await?;
+        series.extend(items.into_iter().map(|i| (i.id.clone(), i)));
+    }
+
+    let total = grouped.len();
+    let mut media = vec![];
+    for (idx, (id, (lot, seen_history))) in grouped.into_iter().enumerate() {
+        let details = match lot {
+            MetadataLot::Movie => movies.remove(&id),
+            _ => series.remove(&id),
+        };
+        let Some(details) = details else {
+            failed_items.push(ImportFailedItem::new(
+                Some(lot),
+                ImportFailStep::ItemDetailsFromSource,
+                id,
+                Some("Could not get details of the item from Jellyfin".to_owned()),
+            ));
+            continue;
+        };
+        tracing::debug!(
+            "Getting details for {title:?} ({idx}/{total})",
+            title = details.name
+        );
+        // DEV: Images can be fetched without the API key
+        let images = details
+            .image_tags
+            .get("Primary")
+            .map(|tag| {
+                format!(
+                    "{}/Items/{}/Images/Primary?tag={tag}",
+                    input.base_url, details.id
+                )
+            })
+            .into_iter()
+            .collect();
+        let identifier = match get_tmdb_identifier(&details.provider_ids, lot, tmdb_service).await {
+            Ok(i) => i,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::ItemDetailsFromSource,
+                    details.name,
+                    Some(e),
+                ));
+                continue;
+            }
+        };
+        media.push(ImportOrExportMediaItem {
+            source_id: details.name.clone(),
+            lot,
+            source: MediaSource::Tmdb,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title: details.name,
+            }),
+            seen_history,
+            reviews: vec![],
+            collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images,
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        });
+    }
+
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
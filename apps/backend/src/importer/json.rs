@@ -1,6 +1,7 @@
 use std::{fs, sync::Arc};
 
 use async_graphql::Result;
+use serde::{de::DeserializeOwned, Deserialize};
 
 use crate::{
     entities::{user_measurement, workout},
@@ -11,9 +12,29 @@ use crate::{
     },
 };
 
+/// The file can either be the list of items, or a complete export from which the
+/// list under the key is used, so that exports can be imported as they are.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExportFile<T> {
+    Items(Vec<T>),
+    Complete(serde_json::Map<String, serde_json::Value>),
+}
+
+fn read_export<T: DeserializeOwned>(path: &str, key: &str) -> Result<Vec<T>> {
+    let export = fs::read_to_string(path)?;
+    let items = match serde_json::from_str::<ExportFile<T>>(&export)? {
+        ExportFile::Items(items) => items,
+        ExportFile::Complete(mut complete) => match complete.remove(key) {
+            Some(items) => serde_json::from_value(items)?,
+            None => vec![],
+        },
+    };
+    Ok(items)
+}
+
 pub async fn media_import(input: DeployJsonImportInput) -> Result<ImportResult> {
-    let export = fs::read_to_string(input.export)?;
-    let mut media = serde_json::from_str::<Vec<ImportOrExportMediaItem>>(&export).unwrap();
+    let mut media: Vec<ImportOrExportMediaItem> = read_export(&input.export, "media")?;
     media.iter_mut().for_each(|m| {
         m.internal_identifier = Some(ImportOrExportItemIdentifier::NeedsDetails {
             identifier: m.identifier.clone(),
@@ -31,8 +52,7 @@ pub async fn media_import(input: DeployJsonImportInput) -> Result<ImportResult>
 }
 
 pub async fn measurements_import(input: DeployJsonImportInput) -> Result<ImportResult> {
-    let export = fs::read_to_string(input.export)?;
-    let measurements = serde_json::from_str::<Vec<user_measurement::Model>>(&export).unwrap();
+    let measurements: Vec<user_measurement::Model> = read_export(&input.export, "measurements")?;
     Ok(ImportResult {
         measurements,
         people: vec![],
@@ -44,8 +64,7 @@ pub async fn measurements_import(input: DeployJsonImportInput) -> Result<ImportR
 }
 
 pub async fn people_import(input: DeployJsonImportInput) -> Result<ImportResult> {
-    let export = fs::read_to_string(input.export)?;
-    let people = serde_json::from_str::<Vec<ImportOrExportPersonItem>>(&export).unwrap();
+    let people: Vec<ImportOrExportPersonItem> = read_export(&input.export, "people")?;
     Ok(ImportResult {
         people,
         media: vec![],
@@ -60,8 +79,7 @@ pub async fn workouts_import(
     input: DeployJsonImportInput,
     exercises_service: &Arc<ExerciseService>,
 ) -> Result<ImportResult> {
-    let export = fs::read_to_string(input.export)?;
-    let db_workouts = serde_json::from_str::<Vec<workout::Model>>(&export).unwrap();
+    let db_workouts: Vec<workout::Model> = read_export(&input.export, "workouts")?;
     let workouts = db_workouts
         .into_iter()
         .map(|w| exercises_service.db_workout_to_workout_input(w))
@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use async_graphql::{Error, Result};
+use database::{MediaSource, MetadataLot};
+use rust_decimal::Decimal;
+use sea_orm::prelude::DateTimeUtc;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use surf::{http::headers::ACCEPT, Client};
+
+use crate::{
+    importer::{
+        find_by_exact_title, DeployKitsuImportInput, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportRatingScale, ImportResult,
+    },
+    miscellaneous::{resolver::Provider, DefaultCollection},
+    models::media::{
+        ImportOrExportItemRating, ImportOrExportItemStatus, ImportOrExportMediaItemSeen,
+    },
+    utils::get_base_http_client,
+};
+
+static URL: &str = "https://kitsu.io/api/edge/";
+// DEV: Kitsu shows ratings out of 10 (in steps of 0.5), but the API returns
+// them as a number out of 20
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Twenty;
+/// The largest page size that is allowed for library entries.
+const PAGE_LIMIT: usize = 500;
+const STATUSES: &str = "current,planned,completed,on_hold,dropped";
+
+#[derive(Debug, Deserialize)]
+struct Document<T> {
+    data: T,
+    #[serde(default)]
+    included: Vec<Included>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResourceId {
+    id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ToOne {
+    data: Option<ResourceId>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ToMany {
+    #[serde(default)]
+    data: Vec<ResourceId>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EntryAttributes {
+    status: String,
+    #[serde(default)]
+    progress: i32,
+    rating_twenty: Option<i32>,
+    started_at: Option<DateTimeUtc>,
+    finished_at: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EntryRelationships {
+    #[serde(default)]
+    anime: ToOne,
+    #[serde(default)]
+    manga: ToOne,
+}
+
+#[derive(Debug, Deserialize)]
+struct LibraryEntry {
+    id: String,
+    attributes: EntryAttributes,
+    relationships: EntryRelationships,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MediaAttributes {
+    canonical_title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MediaRelationships {
+    #[serde(default)]
+    mappings: ToMany,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MappingAttributes {
+    /// For eg: "anilist/anime" or "myanimelist/manga".
+    external_site: String,
+    external_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MediaResource {
+    id: String,
+    attributes: MediaAttributes,
+    relationships: MediaRelationships,
+}
+
+#[derive(Debug, Deserialize)]
+struct MappingResource {
+    id: String,
+    attributes: MappingAttributes,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Included {
+    Anime(MediaResource),
+    Manga(MediaResource),
+    Mappings(MappingResource),
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: String,
+}
+
+/// A media item of the library with the ids it has on other sites.
+#[derive(Debug)]
+struct Media {
+    title: String,
+    mappings: Vec<String>,
+}
+
+async fn get<T: DeserializeOwned>(
+    client: &Client,
+    path: &str,
+    query: &serde_json::Value,
+) -> Result<Document<T>> {
+    let data = client
+        .get(path)
+        .query(query)
+        .unwrap()
+        .await
+        .map_err(|e| anyhow!(e))?
+        .body_json()
+        .await
+        .map_err(|e| anyhow!(e))?;
+    Ok(data)
+}
+
+/// The id of the media on Anilist, falling back to MyAnimeList which is used
+/// when Anilist does not have it.
+fn mapped_identifier(
+    lot: MetadataLot,
+    media: &Media,
+    mappings: &HashMap<String, MappingAttributes>,
+) -> Option<(MediaSource, String)> {
+    let kind = match lot {
+        MetadataLot::Anime => "anime",
+        _ => "manga",
+    };
+    [
+        (MediaSource::Anilist, "anilist"),
+        (MediaSource::Mal, "myanimelist"),
+    ]
+    .into_iter()
+    .find_map(|(source, site)| {
+        let site = format!("{site}/{kind}");
+        media
+            .mappings
+            .iter()
+            .filter_map(|id| mappings.get(id))
+            .find(|m| m.external_site == site)
+            .map(|m| (source, m.external_id.clone()))
+    })
+}
+
+/// Each episode (or chapter) that was seen is a separate entry, so that the
+/// progress carries over.
+fn convert_progress(lot: MetadataLot, entry: &EntryAttributes) -> Vec<ImportOrExportMediaItemSeen> {
+    let total = entry.progress.max(0);
+    let mut seen_history: Vec<_> = (1..=total)
+        .map(|number| ImportOrExportMediaItemSeen {
+            started_on: if number == 1 { entry.started_at } else { None },
+            ended_on: if number == total {
+                entry.finished_at
+            } else {
+                None
+            },
+            anime_episode_number: (lot == MetadataLot::Anime).then_some(number),
+            manga_chapter_number: (lot == MetadataLot::Manga).then_some(number),
+            ..Default::default()
+        })
+        .collect();
+    if seen_history.is_empty() && entry.status == "completed" {
+        seen_history.push(ImportOrExportMediaItemSeen {
+            started_on: entry.started_at,
+            ended_on: entry.finished_at,
+            ..Default::default()
+        });
+    }
+    seen_history
+}
+
+pub async fn import(
+    input: DeployKitsuImportInput,
+    anime_service: Option<&Provider>,
+    manga_service: Option<&Provider>,
+) -> Result<ImportResult> {
+    let client = get_base_http_client(URL, vec![(ACCEPT, "application/vnd.api+json")]);
+    let users: Document<Vec<User>> =
+        get(&client, "users", &json!({ "filter[name]": input.username })).await?;
+    let Some(user) = users.data.into_iter().next() else {
+        return Err(Error::new(format!(
+            "No Kitsu user found with the name: {}",
+            input.username
+        )));
+    };
+
+    let mut entries = vec![];
+    let mut media = HashMap::new();
+    let mut mappings = HashMap::new();
+    loop {
+        let page: Document<Vec<LibraryEntry>> = get(
+            &client,
+            "library-entries",
+            &json!({
+                "filter[user_id]": user.id,
+                "filter[status]": STATUSES,
+                "include": "anime,anime.mappings,manga,manga.mappings",
+                "fields[anime]": "canonicalTitle,mappings",
+                "fields[manga]": "canonicalTitle,mappings",
+                "page[limit]": PAGE_LIMIT,
+                "page[offset]": entries.len(),
+            }),
+        )
+        .await?;
+        let count = page.data.len();
+        entries.extend(page.data);
+        for included in page.included {
+            let (lot, resource) = match included {
+                Included::Anime(resource) => (MetadataLot::Anime, resource),
+                Included::Manga(resource) => (MetadataLot::Manga, resource),
+                Included::Mappings(mapping) => {
+                    mappings.insert(mapping.id, mapping.attributes);
+                    continue;
+                }
+            };
+            media.insert(
+                (lot, resource.id),
+                Media {
+                    title: resource.attributes.canonical_title,
+                    mappings: resource
+                        .relationships
+                        .mappings
+                        .data
+                        .into_iter()
+                        .map(|m| m.id)
+                        .collect(),
+                },
+            );
+        }
+        if count < PAGE_LIMIT {
+            break;
+        }
+    }
+
+    let mut media_items = vec![];
+    let mut failed_items = vec![];
+    let total = entries.len();
+    for (idx, entry) in entries.into_iter().enumerate() {
+        let (lot, media_id) = match (
+            entry.relationships.anime.data,
+            entry.relationships.manga.data,
+        ) {
+            (Some(anime), _) => (MetadataLot::Anime, anime.id),
+            (_, Some(manga)) => (MetadataLot::Manga, manga.id),
+            _ => {
+                tracing::debug!("Library entry {id} has no media", id = entry.id);
+                continue;
+            }
+        };
+        let Some(details) = media.get(&(lot, media_id)) else {
+            continue;
+        };
+        tracing::debug!(
+            "Getting details for {title:?} ({idx}/{total})",
+            title = details.title
+        );
+        let (source, identifier, matched_by_title) =
+            match mapped_identifier(lot, details, &mappings) {
+                Some((source, identifier)) => (source, identifier, false),
+                None => {
+                    let service = match lot {
+                        MetadataLot::Anime => anime_service,
+                        _ => manga_service,
+                    };
+                    match find_by_exact_title(service, lot, &details.title).await {
+                        Ok(identifier) => (MediaSource::Anilist, identifier, true),
+                        Err(failed) => {
+                            failed_items.push(failed);
+                            continue;
+                        }
+                    }
+                }
+            };
+        let mut seen_history = vec![];
+        let mut collections = vec![];
+        let mut status = None;
+        match entry.attributes.status.as_str() {
+            "current" => {
+                collections.push(DefaultCollection::InProgress.to_string());
+                seen_history = convert_progress(lot, &entry.attributes);
+            }
+            "completed" => seen_history = convert_progress(lot, &entry.attributes),
+            "planned" => status = Some(ImportOrExportItemStatus::Planned),
+            "on_hold" | "dropped" => {
+                status = Some(if entry.attributes.status == "dropped" {
+                    ImportOrExportItemStatus::Dropped
+                } else {
+                    ImportOrExportItemStatus::OnHold
+                });
+                seen_history = convert_progress(lot, &entry.attributes);
+                // DEV: The entry that is marked as dropped or on hold is added
+                // after the episodes that were seen, so that they are kept
+                seen_history.push(ImportOrExportMediaItemSeen::default());
+            }
+            _ => {}
+        }
+        let reviews = entry
+            .attributes
+            .rating_twenty
+            .filter(|r| *r > 0)
+            .map(|r| ImportOrExportItemRating {
+                rating: Some(RATING_SCALE.normalize(Decimal::from(r))),
+                ..Default::default()
+            })
+            .into_iter()
+            .collect();
+        media_items.push(ImportOrExportMediaItem {
+            source_id: details.title.clone(),
+            lot,
+            source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title: details.title.clone(),
+            }),
+            seen_history,
+            reviews,
+            collections,
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title,
+            status,
+        });
+    }
+
+    Ok(ImportResult {
+        media: media_items,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use async_graphql::{Error, Result};
+use chrono::NaiveDateTime;
+use chrono_tz::Tz;
+use database::{MediaSource, MetadataLot};
+use http_types::{auth::BasicAuth, mime};
+use sea_orm::prelude::DateTimeUtc;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use surf::{
+    http::headers::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+    Client,
+};
+
+use crate::{
+    importer::{
+        local_to_utc, DeployKodiImportInput, ImportFailStep, ImportFailedItem,
+        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+    },
+    miscellaneous::resolver::RateLimited,
+    models::media::ImportOrExportMediaItemSeen,
+    providers::tmdb::NonMediaTmdbService,
+    utils::get_base_http_client,
+};
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Movie {
+    title: String,
+    playcount: usize,
+    lastplayed: String,
+    #[serde(default)]
+    uniqueid: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoviesResponse {
+    #[serde(default)]
+    movies: Vec<Movie>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Episode {
+    tvshowid: i32,
+    showtitle: String,
+    season: i32,
+    episode: i32,
+    playcount: usize,
+    lastplayed: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EpisodesResponse {
+    #[serde(default)]
+    episodes: Vec<Episode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Show {
+    tvshowid: i32,
+    title: String,
+    #[serde(default)]
+    uniqueid: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShowsResponse {
+    #[serde(default)]
+    tvshows: Vec<Show>,
+}
+
+fn get_client(input: &DeployKodiImportInput) -> Client {
+    let auth = BasicAuth::new(&input.username, &input.password);
+    get_base_http_client(
+        &format!("{}/", input.base_url.trim_end_matches('/')),
+        vec![
+            (ACCEPT, mime::JSON.to_string()),
+            (CONTENT_TYPE, mime::JSON.to_string()),
+            (AUTHORIZATION, auth.value().to_string()),
+        ],
+    )
+}
+
+async fn request<T: DeserializeOwned>(
+    client: &Client,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<T> {
+    let mut rsp = client
+        .post("jsonrpc")
+        .body_json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+        .unwrap()
+        .await
+        .map_err(|e| Error::new(format!("Could not connect to Kodi: {e}")))?;
+    if !rsp.status().is_success() {
+        return Err(Error::new(format!(
+            "Kodi rejected the request with status {}",
+            rsp.status()
+        )));
+    }
+    let data: RpcResponse<T> = rsp
+        .body_json()
+        .await
+        .map_err(|_| Error::new("The response from Kodi was not valid"))?;
+    match (data.result, data.error) {
+        (Some(result), _) => Ok(result),
+        (None, Some(error)) => Err(Error::new(format!(
+            "Kodi returned an error: {}",
+            error.message
+        ))),
+        (None, None) => Err(Error::new("Kodi did not return a result")),
+    }
+}
+
+pub async fn validate(input: &DeployKodiImportInput) -> Result<()> {
+    let client = get_client(input);
+    request::<String>(&client, "JSONRPC.Ping", json!({})).await?;
+    Ok(())
+}
+
+// DEV: Kodi only stores the date of the last play, so the rest of the plays are
+// added without a date
+fn get_seen_history(
+    playcount: usize,
+    lastplayed: &str,
+    season: Option<i32>,
+    episode: Option<i32>,
+    timezone: Option<Tz>,
+) -> Vec<ImportOrExportMediaItemSeen> {
+    let ended_on: Option<DateTimeUtc> =
+        NaiveDateTime::parse_from_str(lastplayed, "%Y-%m-%d %H:%M:%S")
+            .ok()
+            .map(|d| local_to_utc(d, timezone));
+    (0..playcount)
+        .map(|idx| ImportOrExportMediaItemSeen {
+            ended_on: ended_on.filter(|_| idx == playcount - 1),
+            show_season_number: season,
+            show_episode_number: episode,
+            ..Default::default()
+        })
+        .collect()
+}
+
+async fn get_tmdb_identifier(
+    ids: &HashMap<String, String>,
+    lot: MetadataLot,
+    tmdb_service: Option<&RateLimited<NonMediaTmdbService>>,
+) -> std::result::Result<String, String> {
+    if let Some(id) = ids.get("tmdb").filter(|i| !i.is_empty()) {
+        return Ok(id.to_owned());
+    }
+    let Some(id) = ids.get("imdb").filter(|i| !i.is_empty()) else {
+        return Err("Item does not have a TMDB or IMDb id".to_owned());
+    };
+    // DEV: Previews do not look up the IMDb id, so the item only has its title
+    let Some(tmdb_service) = tmdb_service else {
+        return Ok(String::new());
+    };
+    match tmdb_service
+        .request(|s| s.find_by_external_id(id, "imdb_id"))
+        .await
+    {
+        Ok((found_lot, identifier)) if found_lot == lot => Ok(identifier),
+        Ok(_) => Err(format!("No {lot} found on TMDB for IMDb id {id}")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub async fn import(
+    input: DeployKodiImportInput,
+    tmdb_service: Option<&RateLimited<NonMediaTmdbService>>,
+    timezone: Option<Tz>,
+) -> Result<ImportResult> {
+    let client = get_client(&input);
+    let played = json!({ "field": "playcount", "operator": "greaterthan", "value": "0" });
+    let movies: MoviesResponse = request(
+        &client,
+        "VideoLibrary.GetMovies",
+        json!({
+            "properties": ["title", "playcount", "lastplayed", "uniqueid"],
+            "filter": played,
+        }),
+    )
+    .await?;
+    let episodes: EpisodesResponse = request(
+        &client,
+        "VideoLibrary.GetEpisodes",
+        json!({
+            "properties": ["tvshowid", "showtitle", "season", "episode", "playcount", "lastplayed"],
+            "filter": played,
+        }),
+    )
+    .await?;
+    let shows: ShowsResponse = request(
+        &client,
+        "VideoLibrary.GetTVShows",
+        json!({ "properties": ["title", "uniqueid"] }),
+    )
+    .await?;
+    tracing::debug!(
+        "Got {} movies and {} episodes from Kodi",
+        movies.movies.len(),
+        episodes.episodes.len()
+    );
+
+    let mut shows: HashMap<_, _> = shows
+        .tvshows
+        .into_iter()
+        .map(|s| (s.tvshowid, (s, vec![])))
+        .collect();
+    let mut failed_items = vec![];
+    for episode in episodes.episodes {
+        let Some((_, seen_history)) = shows.get_mut(&episode.tvshowid) else {
+            failed_items.push(ImportFailedItem::new(
+                Some(MetadataLot::Show),
+                ImportFailStep::ItemDetailsFromSource,
+                episode.showtitle,
+                Some("Could not get details of the show from Kodi".to_owned()),
+            ));
+            continue;
+        };
+        seen_history.extend(get_seen_history(
+            episode.playcount,
+            &episode.lastplayed,
+            Some(episode.season),
+            Some(episode.episode),
+            timezone,
+        ));
+    }
+
+    let items = movies
+        .movies
+        .into_iter()
+        .map(|m| {
+            let seen_history = get_seen_history(m.playcount, &m.lastplayed, None, None, timezone);
+            (MetadataLot::Movie, m.title, m.uniqueid, seen_history)
+        })
+        .chain(
+            shows
+                .into_values()
+                .filter(|(_, seen_history)| !seen_history.is_empty())
+                .map(|(s, seen_history)| (MetadataLot::Show, s.title, s.uniqueid, seen_history)),
+        )
+        .collect::<Vec<_>>();
+    let total = items.len();
+    let mut media = vec![];
+    for (idx, (lot, title, ids, seen_history)) in items.into_iter().enumerate() {
+        tracing::debug!("Getting details for {title:?} ({idx}/{total})");
+        let identifier = match get_tmdb_identifier(&ids, lot, tmdb_service).await {
+            Ok(i) => i,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::ItemDetailsFromSource,
+                    title,
+                    Some(e),
+                ));
+                continue;
+            }
+        };
+        media.push(ImportOrExportMediaItem {
+            source_id: title.clone(),
+            lot,
+            source: MediaSource::Tmdb,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title,
+            }),
+            seen_history,
+            reviews: vec![],
+            collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        });
+    }
+
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use async_graphql::Result;
+use chrono::DateTime;
+use database::{MediaSource, MetadataLot};
+use http_types::mime;
+use itertools::Itertools;
+use serde::Deserialize;
+use serde_json::json;
+use surf::{http::headers::ACCEPT, Client};
+
+use crate::{
+    importer::{
+        find_by_exact_title, podcast_seen_history, DeployLastFmImportInput, ImportFailStep,
+        ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+        PlayedEpisode,
+    },
+    miscellaneous::resolver::Provider,
+    utils::get_base_http_client,
+};
+
+static URL: &str = "https://ws.audioscrobbler.com/2.0/";
+// DEV: The documentation says that at most 200 scrobbles are returned, but the API
+// accepts up to 1000 which makes large histories a lot faster to read
+const PER_PAGE: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+struct Text {
+    #[serde(rename = "#text")]
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Date {
+    uts: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Track {
+    artist: Text,
+    name: String,
+    /// Not present for the track that is being played right now.
+    date: Option<Date>,
+}
+
+// DEV: A page with a single scrobble has it as an object instead of a list
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Tracks {
+    One(Track),
+    Many(Vec<Track>),
+}
+
+impl Default for Tracks {
+    fn default() -> Self {
+        Self::Many(vec![])
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PageAttributes {
+    #[serde(rename = "totalPages")]
+    total_pages: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecentTracks {
+    #[serde(default)]
+    track: Tracks,
+    #[serde(rename = "@attr")]
+    attributes: PageAttributes,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Response {
+    Tracks { recenttracks: RecentTracks },
+    Error { message: String },
+}
+
+/// Get a page of scrobbles along with the total number of pages.
+async fn fetch_page(
+    client: &Client,
+    input: &DeployLastFmImportInput,
+    page: usize,
+) -> anyhow::Result<(Vec<Track>, usize)> {
+    let mut query = json!({
+        "method": "user.getrecenttracks",
+        "user": input.username,
+        "api_key": input.api_key,
+        "format": "json",
+        "limit": PER_PAGE,
+        "page": page,
+    });
+    if let Some(from) = input.from {
+        query["from"] = json!(from.timestamp());
+    }
+    if let Some(to) = input.to {
+        query["to"] = json!(to.timestamp());
+    }
+    let rsp: Response = client
+        .get("")
+        .query(&query)
+        .unwrap()
+        .await
+        .map_err(|e| anyhow!(e))?
+        .body_json()
+        .await
+        .map_err(|e| anyhow!(e))?;
+    let recent = match rsp {
+        Response::Tracks { recenttracks } => recenttracks,
+        Response::Error { message } => return Err(anyhow!(message)),
+    };
+    let total_pages = recent.attributes.total_pages.parse().unwrap_or_default();
+    let tracks = match recent.track {
+        Tracks::One(track) => vec![track],
+        Tracks::Many(tracks) => tracks,
+    };
+    Ok((tracks, total_pages))
+}
+
+pub async fn import(
+    input: DeployLastFmImportInput,
+    itunes_service: Option<&Provider>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::Podcast;
+    let client = get_base_http_client(URL, vec![(ACCEPT, mime::JSON)]);
+    let mut failed_items = vec![];
+    // DEV: Scrobbles do not say whether they are of a song or of a podcast episode,
+    // so the artist is looked up as a podcast and the track as one of its episodes
+    let mut artists: HashMap<String, HashMap<String, PlayedEpisode>> = HashMap::new();
+    let mut page = 1;
+    loop {
+        tracing::debug!("Fetching scrobbles of page {page}");
+        let (tracks, total_pages) = match fetch_page(&client, &input, page).await {
+            Ok(data) => data,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    None,
+                    ImportFailStep::ItemDetailsFromSource,
+                    format!("Scrobbles of page {page}"),
+                    Some(e.to_string()),
+                ));
+                break;
+            }
+        };
+        for track in tracks {
+            let Some(ended_on) = track
+                .date
+                .and_then(|d| d.uts.parse().ok())
+                .and_then(|uts| DateTime::from_timestamp(uts, 0))
+            else {
+                continue;
+            };
+            let identifier = format!("{} - {}", track.artist.text, track.name);
+            let episode = artists
+                .entry(track.artist.text)
+                .or_default()
+                .entry(track.name)
+                .or_default();
+            episode.identifier = identifier;
+            episode.played_on.push(ended_on);
+        }
+        if page >= total_pages {
+            break;
+        }
+        page += 1;
+    }
+
+    let mut media = vec![];
+    let total = artists.len();
+    for (idx, (title, episodes)) in artists
+        .into_iter()
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .enumerate()
+    {
+        tracing::debug!("Searching for {title:?} ({idx}/{total})");
+        let identifier = match find_by_exact_title(itunes_service, lot, &title).await {
+            Ok(identifier) => identifier,
+            Err(mut failed) => {
+                let scrobbles: usize = episodes.values().map(|e| e.played_on.len()).sum();
+                failed.error = Some(format!(
+                    "{error} ({scrobbles} scrobbles of {tracks} tracks)",
+                    error = failed.error.unwrap_or_default(),
+                    tracks = episodes.len()
+                ));
+                failed_items.push(failed);
+                continue;
+            }
+        };
+        let Some(seen_history) = podcast_seen_history(
+            itunes_service,
+            &identifier,
+            &title,
+            episodes,
+            &mut failed_items,
+        )
+        .await
+        else {
+            continue;
+        };
+        media.push(ImportOrExportMediaItem {
+            source_id: title.clone(),
+            lot,
+            source: MediaSource::Itunes,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title,
+            }),
+            seen_history,
+            reviews: vec![],
+            collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: true,
+            status: None,
+        });
+    }
+
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
@@ -0,0 +1,243 @@
+use std::{collections::HashMap, fs::File, io::Read};
+
+use async_graphql::Result;
+use chrono::NaiveDate;
+use csv::Reader;
+use database::{MediaSource, MetadataLot};
+use rs_utils::convert_naive_to_utc;
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use zip::ZipArchive;
+
+use crate::{
+    importer::{
+        DeployLetterboxdImportInput, ImportFailStep, ImportFailedItem,
+        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportRatingScale, ImportResult,
+    },
+    miscellaneous::resolver::Provider,
+    models::media::{
+        ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportMediaItemSeen,
+    },
+};
+
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Five;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Watched {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Year")]
+    year: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Rating {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Year")]
+    year: Option<i32>,
+    #[serde(rename = "Rating")]
+    rating: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Diary {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Year")]
+    year: Option<i32>,
+    #[serde(rename = "Watched Date")]
+    watched_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Review {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Year")]
+    year: Option<i32>,
+    #[serde(rename = "Rating")]
+    rating: Option<Decimal>,
+    #[serde(rename = "Review")]
+    review: String,
+    #[serde(rename = "Watched Date")]
+    watched_date: Option<NaiveDate>,
+    #[serde(rename = "Spoiler", default)]
+    spoiler: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Film {
+    rating: Option<Decimal>,
+    seen_history: Vec<ImportOrExportMediaItemSeen>,
+    reviews: Vec<ImportOrExportItemRating>,
+}
+
+type FilmKey = (String, Option<i32>);
+
+fn read_csv<T>(
+    archive: &mut ZipArchive<File>,
+    file_name: &str,
+    failed_items: &mut Vec<ImportFailedItem>,
+) -> Result<Vec<T>>
+where
+    T: DeserializeOwned,
+{
+    let mut contents = String::new();
+    match archive.by_name(file_name) {
+        Ok(mut f) => f.read_to_string(&mut contents)?,
+        Err(_) => {
+            tracing::debug!("Export does not contain {file_name}, skipping it");
+            return Ok(vec![]);
+        }
+    };
+    let mut records = vec![];
+    for (idx, result) in Reader::from_reader(contents.as_bytes())
+        .deserialize()
+        .enumerate()
+    {
+        match result {
+            Ok(r) => records.push(r),
+            Err(e) => failed_items.push(ImportFailedItem::new(
+                Some(MetadataLot::Movie),
+                ImportFailStep::InputTransformation,
+                idx.to_string(),
+                Some(format!("{file_name}: {e:#?}")),
+            )),
+        }
+    }
+    Ok(records)
+}
+
+pub async fn import(
+    input: DeployLetterboxdImportInput,
+    tmdb_service: Option<&Provider>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::Movie;
+    let source = MediaSource::Tmdb;
+    let mut failed_items = vec![];
+    let mut archive = ZipArchive::new(File::open(&input.export_archive)?)?;
+    let diary: Vec<Diary> = read_csv(&mut archive, "diary.csv", &mut failed_items)?;
+    let ratings: Vec<Rating> = read_csv(&mut archive, "ratings.csv", &mut failed_items)?;
+    let reviews: Vec<Review> = read_csv(&mut archive, "reviews.csv", &mut failed_items)?;
+    let watched: Vec<Watched> = read_csv(&mut archive, "watched.csv", &mut failed_items)?;
+
+    let mut films: HashMap<FilmKey, Film> = HashMap::new();
+    for record in diary {
+        films
+            .entry((record.name, record.year))
+            .or_default()
+            .seen_history
+            .push(ImportOrExportMediaItemSeen {
+                ended_on: record.watched_date.map(convert_naive_to_utc),
+                ..Default::default()
+            });
+    }
+    for record in ratings {
+        films.entry((record.name, record.year)).or_default().rating =
+            Some(RATING_SCALE.normalize(record.rating));
+    }
+    for record in reviews {
+        let spoiler = record
+            .spoiler
+            .map(|s| s.eq_ignore_ascii_case("yes") || s.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        films
+            .entry((record.name, record.year))
+            .or_default()
+            .reviews
+            .push(ImportOrExportItemRating {
+                rating: record.rating.map(|r| RATING_SCALE.normalize(r)),
+                review: Some(ImportOrExportItemReview {
+                    date: record.watched_date.map(convert_naive_to_utc),
+                    spoiler: Some(spoiler),
+                    text: Some(record.review),
+                    visibility: None,
+                }),
+                ..Default::default()
+            });
+    }
+    for record in watched {
+        let film = films.entry((record.name, record.year)).or_default();
+        if film.seen_history.is_empty() {
+            film.seen_history.push(ImportOrExportMediaItemSeen {
+                ..Default::default()
+            });
+        }
+    }
+
+    let total = films.len();
+    let mut media = vec![];
+    for (idx, ((name, year), mut film)) in films.into_iter().enumerate() {
+        tracing::debug!("Searching for {name:?} ({year:?}) ({idx}/{total})");
+        let (identifier, title) = match tmdb_service {
+            Some(tmdb_service) => {
+                let search = match tmdb_service.metadata_search(&name, None, true).await {
+                    Ok(s) => s.items,
+                    Err(e) => {
+                        failed_items.push(ImportFailedItem::new(
+                            Some(lot),
+                            ImportFailStep::MediaDetailsFromProvider,
+                            name,
+                            Some(e.to_string()),
+                        ));
+                        continue;
+                    }
+                };
+                let found = search
+                    .iter()
+                    .find(|s| year.is_some() && s.publish_year == year)
+                    .or_else(|| search.first());
+                let Some(found) = found else {
+                    failed_items.push(ImportFailedItem::new(
+                        Some(lot),
+                        ImportFailStep::MediaDetailsFromProvider,
+                        name,
+                        Some("No matching movie found on TMDB".to_owned()),
+                    ));
+                    continue;
+                };
+                (found.identifier.clone(), found.title.clone())
+            }
+            // DEV: Previews do not search for the film, so it only has its name
+            None => (String::new(), name.clone()),
+        };
+        if film.reviews.iter().all(|r| r.rating.is_none()) {
+            if let Some(review) = film.reviews.first_mut() {
+                review.rating = film.rating;
+            } else if film.rating.is_some() {
+                film.reviews.push(ImportOrExportItemRating {
+                    rating: film.rating,
+                    ..Default::default()
+                });
+            }
+        }
+        media.push(ImportOrExportMediaItem {
+            source_id: name.clone(),
+            lot,
+            source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title,
+            }),
+            seen_history: film.seen_history,
+            reviews: film.reviews,
+            collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: true,
+            status: None,
+        });
+    }
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
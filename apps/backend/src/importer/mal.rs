@@ -6,20 +6,22 @@ use std::{
 use async_graphql::Result;
 use database::{MediaSource, MetadataLot};
 use flate2::bufread::GzDecoder;
+use itertools::Itertools;
 use rs_utils::{convert_naive_to_utc, convert_string_to_date};
 use rust_decimal::{prelude::FromPrimitive, Decimal};
-use rust_decimal_macros::dec;
 use sea_orm::prelude::DateTimeUtc;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
-    importer::{DeployMalImportInput, ImportResult},
+    importer::{DeployMalImportInput, ImportRatingScale, ImportResult},
     models::media::{
         ImportOrExportItemIdentifier, ImportOrExportItemRating, ImportOrExportMediaItem,
         ImportOrExportMediaItemSeen,
     },
 };
 
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Ten;
+
 fn decode_data<T>(path: &str) -> Result<T>
 where
     T: DeserializeOwned,
@@ -41,25 +43,37 @@ fn get_date(date: String) -> Option<DateTimeUtc> {
 }
 
 fn convert_to_format(item: Item, lot: MetadataLot) -> ImportOrExportMediaItem {
-    let progress = if item.done != 0 && item.total != 0 {
-        Some(item.done / item.total)
+    let started_on = get_date(item.my_start_date);
+    let ended_on = get_date(item.my_finish_date);
+    // DEV: Each episode/chapter is imported as a separate seen item so that the
+    // progress carries over.
+    let mut seen_history = (1..=item.done)
+        .map(|number| {
+            let (anime_episode_number, manga_chapter_number) = match lot {
+                MetadataLot::Anime => (Some(number), None),
+                _ => (None, Some(number)),
+            };
+            ImportOrExportMediaItemSeen {
+                anime_episode_number,
+                manga_chapter_number,
+                ..Default::default()
+            }
+        })
+        .collect_vec();
+    if let Some(first) = seen_history.first_mut() {
+        first.started_on = started_on;
+    }
+    if let Some(last) = seen_history.last_mut() {
+        last.ended_on = ended_on;
+    }
+    let reviews = if item.my_score == 0 {
+        vec![]
     } else {
-        None
-    };
-    let seen_item = ImportOrExportMediaItemSeen {
-        started_on: get_date(item.my_start_date),
-        ended_on: get_date(item.my_finish_date),
-        progress,
-        ..Default::default()
-    };
-    let review_item = ImportOrExportItemRating {
-        review: None,
-        rating: if item.my_score == 0 {
-            None
-        } else {
-            Some(Decimal::from_u32(item.my_score).unwrap() * dec!(10))
-        },
-        ..Default::default()
+        vec![ImportOrExportItemRating {
+            review: None,
+            rating: Some(RATING_SCALE.normalize(Decimal::from_u32(item.my_score).unwrap())),
+            ..Default::default()
+        }]
     };
     ImportOrExportMediaItem {
         source_id: item.title.clone(),
@@ -70,22 +84,36 @@ fn convert_to_format(item: Item, lot: MetadataLot) -> ImportOrExportMediaItem {
             identifier: item.identifier.to_string(),
             title: item.title,
         }),
-        seen_history: vec![seen_item],
-        reviews: vec![review_item],
+        seen_history,
+        reviews,
         collections: vec![],
+        added_on: None,
+        tags: item
+            .my_tags
+            .split(',')
+            .map(|t| t.trim().to_owned())
+            .filter(|t| !t.is_empty())
+            .collect(),
+        images: vec![],
         monitored: None,
+        matched_by_title: false,
+        status: None,
     }
 }
 
 pub async fn import(input: DeployMalImportInput) -> Result<ImportResult> {
-    let anime_data = decode_data::<DataRoot>(&input.anime_path)?;
-    let manga_data = decode_data::<DataRoot>(&input.manga_path)?;
     let mut media = vec![];
-    for item in anime_data.items.into_iter() {
-        media.push(convert_to_format(item, MetadataLot::Anime));
+    if let Some(anime_path) = input.anime_path {
+        let anime_data = decode_data::<DataRoot>(&anime_path)?;
+        for item in anime_data.items.into_iter() {
+            media.push(convert_to_format(item, MetadataLot::Anime));
+        }
     }
-    for item in manga_data.items.into_iter() {
-        media.push(convert_to_format(item, MetadataLot::Manga));
+    if let Some(manga_path) = input.manga_path {
+        let manga_data = decode_data::<DataRoot>(&manga_path)?;
+        for item in manga_data.items.into_iter() {
+            media.push(convert_to_format(item, MetadataLot::Manga));
+        }
     }
     Ok(ImportResult {
         media,
@@ -109,11 +137,11 @@ struct Item {
     identifier: u32,
     #[serde(alias = "series_title", alias = "manga_title")]
     title: String,
-    #[serde(alias = "series_episodes", alias = "manga_chapters")]
-    total: i32,
     #[serde(alias = "my_watched_episodes", alias = "my_read_chapters")]
     done: i32,
     my_start_date: String,
     my_finish_date: String,
     my_score: u32,
+    #[serde(default)]
+    my_tags: String,
 }
@@ -1,17 +1,19 @@
-use async_graphql::Result;
+use std::time::Duration;
+
+use async_graphql::{Error, Result};
 use database::{MediaSource, MetadataLot, Visibility};
 use rust_decimal::Decimal;
-use rust_decimal_macros::dec;
 use sea_orm::prelude::DateTimeUtc;
 use serde::{Deserialize, Serialize};
 use serde_with::{formats::Flexible, serde_as, TimestampMilliSeconds};
-use surf::{http::headers::USER_AGENT, Client, Config, Url};
+use surf::{http::headers::HeaderName, Client, Url};
+use tokio::time::sleep;
 use uuid::Uuid;
 
 use crate::{
     importer::{
         DeployMediaTrackerImportInput, ImportFailStep, ImportFailedItem, ImportOrExportMediaItem,
-        ImportResult,
+        ImportRatingScale, ImportResult,
     },
     models::{
         media::{
@@ -22,9 +24,12 @@ use crate::{
         IdObject,
     },
     providers::openlibrary::get_key,
-    utils::USER_AGENT_STR,
+    utils::get_base_http_client,
 };
 
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Five;
+const ITEMS_PER_PAGE: usize = 100;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "snake_case")]
 enum MediaType {
@@ -71,11 +76,22 @@ struct ListItemResponse {
     media_item: Item,
 }
 
+#[serde_as]
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Item {
     id: i32,
     media_type: Option<MediaType>,
+    #[serde_as(as = "Option<TimestampMilliSeconds<i64, Flexible>>")]
+    #[serde(default)]
+    last_seen_at: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemsPage {
+    data: Vec<Item>,
+    total_pages: usize,
 }
 
 #[serde_as]
@@ -95,6 +111,7 @@ struct ItemEpisode {
     id: i32,
     season_number: i32,
     episode_number: i32,
+    user_rating: Option<ItemReview>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -138,15 +155,90 @@ struct ItemDetails {
     number_of_pages: Option<ItemNumberOfPages>,
 }
 
+fn get_client(input: &DeployMediaTrackerImportInput) -> Result<Client> {
+    let url = Url::parse(&format!("{}/api/", input.api_url))
+        .map_err(|_| Error::new("The MediaTracker url is not valid"))?;
+    Ok(get_base_http_client(
+        url.as_str(),
+        vec![(HeaderName::from("Access-Token"), input.api_key.as_str())],
+    ))
+}
+
+/// Make sure that the MediaTracker instance is reachable and that the api key
+/// is valid by fetching the profile of the user it belongs to.
+pub async fn validate(input: &DeployMediaTrackerImportInput) -> Result<()> {
+    let client = get_client(input)?;
+    let mut rsp = client
+        .get("user")
+        .await
+        .map_err(|e| Error::new(format!("Could not connect to MediaTracker: {e}")))?;
+    if !rsp.status().is_success() {
+        return Err(Error::new(format!(
+            "MediaTracker rejected the credentials with status {}",
+            rsp.status()
+        )));
+    }
+    rsp.body_json::<IdObject>()
+        .await
+        .map_err(|_| Error::new("The response from MediaTracker was not valid"))?;
+    Ok(())
+}
+
+/// Wait before the next request, if the user asked for the requests to be spaced
+/// out.
+async fn throttle(delay: Option<Duration>) {
+    if let Some(delay) = delay {
+        sleep(delay).await;
+    }
+}
+
+/// Get the items that were seen after the given date, or all the seen items if
+/// it is not set. The items are sorted by when they were last seen, so the pages
+/// after the first item that was seen before it are not fetched.
+async fn get_seen_items(
+    client: &Client,
+    delay: Option<Duration>,
+    since: Option<DateTimeUtc>,
+) -> Result<Vec<Item>> {
+    let mut items = vec![];
+    let mut page = 1;
+    loop {
+        throttle(delay).await;
+        let mut rsp = client
+            .get("items/paginated")
+            .query(&serde_json::json!({
+                "page": page,
+                "numberOfItemsPerPage": ITEMS_PER_PAGE,
+                "orderBy": "lastSeen",
+                "sortOrder": "desc",
+                "onlySeenItems": true,
+            }))
+            .unwrap()
+            .await
+            .map_err(|e| Error::new(format!("Could not get the items from MediaTracker: {e}")))?;
+        let data: ItemsPage = rsp
+            .body_json()
+            .await
+            .map_err(|_| Error::new("The response from MediaTracker was not valid"))?;
+        let mut reached_since = false;
+        for item in data.data {
+            if since.is_some_and(|s| item.last_seen_at.is_some_and(|l| l <= s)) {
+                reached_since = true;
+                break;
+            }
+            items.push(item);
+        }
+        if reached_since || page >= data.total_pages {
+            break;
+        }
+        page += 1;
+    }
+    Ok(items)
+}
+
 pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult> {
-    let client: Client = Config::new()
-        .add_header(USER_AGENT, USER_AGENT_STR)
-        .unwrap()
-        .add_header("Access-Token", input.api_key)
-        .unwrap()
-        .set_base_url(Url::parse(&format!("{}/api/", input.api_url)).unwrap())
-        .try_into()
-        .unwrap();
+    let client = get_client(&input)?;
+    let delay = input.request_delay_ms.map(Duration::from_millis);
 
     let mut rsp = client.get("user").await.unwrap();
     let data: IdObject = rsp.body_json().await.unwrap();
@@ -177,6 +269,7 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
         })
         .collect();
     for list in lists.iter_mut() {
+        throttle(delay).await;
         let mut rsp = client
             .get("list/items")
             .query(&serde_json::json!({ "listId": list.id }))
@@ -190,8 +283,13 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
     let mut failed_items = vec![];
 
     // all items returned here are seen at least once
-    let mut rsp = client.get("items").await.unwrap();
-    let mut data: Vec<Item> = rsp.body_json().await.unwrap();
+    let mut data = get_seen_items(&client, delay, input.since).await?;
+    if let Some(since) = input.since {
+        tracing::debug!(
+            "Loaded {total} items seen after {since}",
+            total = data.len()
+        );
+    }
 
     // There are a few items that are added to lists but have not been seen, so will
     // add them manually.
@@ -200,6 +298,7 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
             data.push(Item {
                 id: i.media_item.id,
                 media_type: i.media_item.media_type.clone(),
+                last_seen_at: None,
             })
         })
     });
@@ -213,27 +312,28 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
         let media_type = match d.media_type {
             Some(m) => m.clone(),
             None => {
-                failed_items.push(ImportFailedItem {
-                    lot: None,
-                    step: ImportFailStep::ItemDetailsFromSource,
-                    identifier: d.id.to_string(),
-                    error: Some("No media type".to_string()),
-                });
+                failed_items.push(ImportFailedItem::new(
+                    None,
+                    ImportFailStep::ItemDetailsFromSource,
+                    d.id.to_string(),
+                    Some("No media type".to_string()),
+                ));
                 continue;
             }
         };
         let lot = MetadataLot::from(media_type.clone());
+        throttle(delay).await;
         let mut rsp = client.get(format!("details/{}", d.id)).await.unwrap();
         let details: ItemDetails = match rsp.body_json().await {
             Ok(s) => s,
             Err(e) => {
                 tracing::error!("Encountered error for id = {id:?}: {e:?}", id = d.id);
-                failed_items.push(ImportFailedItem {
-                    lot: Some(lot),
-                    step: ImportFailStep::ItemDetailsFromSource,
-                    identifier: d.id.to_string(),
-                    error: Some(e.to_string()),
-                });
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::ItemDetailsFromSource,
+                    d.id.to_string(),
+                    Some(e.to_string()),
+                ));
                 continue;
             }
         };
@@ -321,23 +421,37 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
                     title: details.title,
                 },
             }),
-            reviews: Vec::from_iter(details.user_rating.map(|r| {
-                let review = if let Some(_s) = r.clone().review {
-                    Some(ImportOrExportItemReview {
-                        date: r.date,
-                        spoiler: Some(false),
-                        text: r.review,
-                        visibility: None,
-                    })
-                } else {
-                    None
-                };
-                ImportOrExportItemRating {
-                    review,
-                    rating: r.rating.map(|d| d.saturating_mul(dec!(20))),
-                    ..Default::default()
-                }
-            })),
+            reviews: details
+                .user_rating
+                .map(|r| (None, r))
+                .into_iter()
+                .chain(
+                    details
+                        .seasons
+                        .iter()
+                        .flat_map(|s| s.episodes.iter())
+                        .filter_map(|e| e.user_rating.clone().map(|r| (Some(e), r))),
+                )
+                .map(|(episode, r)| {
+                    let review = if let Some(_s) = r.clone().review {
+                        Some(ImportOrExportItemReview {
+                            date: r.date,
+                            spoiler: Some(false),
+                            text: r.review,
+                            visibility: None,
+                        })
+                    } else {
+                        None
+                    };
+                    ImportOrExportItemRating {
+                        review,
+                        rating: r.rating.map(|d| RATING_SCALE.normalize(d)),
+                        show_season_number: episode.map(|e| e.season_number),
+                        show_episode_number: episode.map(|e| e.episode_number),
+                        ..Default::default()
+                    }
+                })
+                .collect(),
             seen_history: details
                 .seen_history
                 .iter()
@@ -361,7 +475,12 @@ pub async fn import(input: DeployMediaTrackerImportInput) -> Result<ImportResult
                     }
                 })
                 .collect(),
+            added_on: None,
+            tags: vec![],
+            images: vec![],
             monitored: None,
+            matched_by_title: false,
+            status: None,
         };
         final_data.push(item);
     }
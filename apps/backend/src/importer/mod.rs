@@ -1,133 +1,548 @@
-use std::sync::Arc;
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    iter,
+    panic::AssertUnwindSafe,
+    sync::{atomic::AtomicBool, Arc, Mutex},
+    time::{Duration as StdDuration, Instant},
+};
 
-use apalis::prelude::Storage;
-use async_graphql::{Context, Enum, InputObject, Object, Result, SimpleObject};
-use chrono::{Duration, Utc};
-use database::{ImportSource, MetadataLot};
+use apalis::sqlite::SqliteStorage;
+use async_graphql::{
+    Context, Enum, Error, InputObject, Object, Result, SimpleObject, Subscription, Union,
+};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use config::AppConfig;
+use database::{ImportSource, MediaSource, MetadataLot, SeenState, Visibility};
+use futures::{stream, stream::BoxStream, FutureExt, StreamExt};
 use itertools::Itertools;
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 use rust_decimal_macros::dec;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, FromJsonQueryResult, QueryFilter,
-    QueryOrder,
+    prelude::{DateTimeUtc, Expr},
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseTransaction, EntityTrait,
+    FromJsonQueryResult, Iterable, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+    TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
-use tracing::instrument;
+use sha2::{Digest, Sha256};
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        OnceCell,
+    },
+    time::sleep,
+};
+use tracing::{instrument, Level};
 
 use crate::{
-    background::ApplicationJob,
+    background::ImportJob,
     entities::{
-        import_report, prelude::ImportReport, user::UserWithOnlyPreferences, user_measurement,
+        collection, collection_to_entity, import_report, metadata, metadata_to_metadata_group,
+        prelude::{
+            Collection, CollectionToEntity, ImportReport, Metadata, MetadataToMetadataGroup,
+            Review, Seen,
+        },
+        review, seen,
+        user::UserWithOnlyPreferences,
+        user_measurement,
     },
     fitness::resolver::ExerciseService,
-    miscellaneous::resolver::MiscellaneousService,
+    miscellaneous::{
+        resolver::{MiscellaneousService, Provider},
+        DefaultCollection,
+    },
     models::{
         fitness::UserWorkoutInput,
         media::{
             CommitPersonInput, CreateOrUpdateCollectionInput, ImportOrExportItemIdentifier,
-            ImportOrExportItemRating, ImportOrExportMediaItem, ImportOrExportPersonItem,
-            PartialMetadataWithoutId, PostReviewInput, ProgressUpdateInput,
-            ToggleMediaMonitorInput,
+            ImportOrExportItemRating, ImportOrExportItemStatus, ImportOrExportMediaItem,
+            ImportOrExportMediaItemSeen, ImportOrExportPersonItem, MediaDetails, MetadataImage,
+            MetadataImageLot, MetadataSearchItem, PartialMetadataWithoutId, PostReviewInput,
+            ProgressUpdateInput, ToggleMediaMonitorInput,
         },
-        BackgroundJob, ChangeCollectionToEntityInput, IdObject,
+        BackgroundJob, ChangeCollectionToEntityInput, IdObject, StoredUrl,
     },
     traits::AuthProvider,
     users::{UserPreferences, UserReviewScale},
-    utils::partial_user_by_id,
+    utils::{add_entity_to_collection, partial_user_by_id},
 };
 
+mod anilist;
+mod audible;
 mod audiobookshelf;
+mod backloggd;
+mod calibre;
+mod generic_csv;
 mod goodreads;
+mod hardcover;
+mod hltb;
+mod imdb;
+mod jellyfin;
 mod json;
+mod kitsu;
+mod kodi;
+mod lastfm;
+mod letterboxd;
 mod mal;
 mod media_tracker;
 mod movary;
+mod openlibrary_log;
+mod opml;
+mod plex;
+mod queue;
+mod report;
+mod ryot;
+mod schedule;
+mod serializd;
+mod simkl;
+mod spotify;
+mod steam;
 mod story_graph;
+mod streaming_csv;
 mod strong_app;
+mod tmdb;
 mod trakt;
+mod tv_time;
+
+pub use schedule::{ImportSchedule, UserScheduledImport};
+
+use self::{
+    queue::{BulkImportResponse, ImportQueueStatus},
+    report::ImportReconcileResult,
+};
+
+/// The number of items after which the progress of an import job is saved.
+const PROGRESS_UPDATE_INTERVAL: usize = 10;
+/// How similar (in percent) titles that were found by searching need to be to
+/// the title in the source, when the import does not set it.
+const DEFAULT_MIN_TITLE_SIMILARITY: u8 = 70;
+/// The number of progress events that are buffered for slow subscribers.
+const PROGRESS_EVENTS_CAPACITY: usize = 256;
+/// The number of search results that are suggested for an item that could not
+/// be resolved.
+const MAX_IMPORT_CANDIDATES: usize = 3;
+/// The number of log lines that are kept in the report of an import.
+const MAX_IMPORT_LOGS: usize = 500;
+/// The maximum number of characters in the name of an imported collection.
+const MAX_COLLECTION_NAME_LENGTH: usize = 100;
+/// The name given to imported collections whose name is empty once sanitized.
+const UNNAMED_COLLECTION: &str = "Imported Collection";
+/// The collection that media which was not finished in the source is added to.
+const DROPPED_COLLECTION: &str = "Dropped";
+/// The collection that media which was put on hold in the source is added to.
+const ON_HOLD_COLLECTION: &str = "On Hold";
 
-#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct DeployMediaTrackerImportInput {
     /// The base url where the resource is present at.
     api_url: String,
     /// An application token generated by an admin.
     api_key: String,
+    /// The number of milliseconds to wait between successive requests, for
+    /// instances that can not keep up with the import.
+    request_delay_ms: Option<u64>,
+    /// Only import the items that were seen after this date. Scheduled imports
+    /// continue from the most recent seen entry of their previous run.
+    since: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployCalibreImportInput {
+    // The file path of the uploaded `metadata.db` file of the library.
+    db_file: String,
+    // The label of the custom date column with the dates on which the books were
+    // read. Defaults to the first date column that has "read" in its name.
+    read_date_column: Option<String>,
 }
 
-#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct DeployGoodreadsImportInput {
     // The file path of the uploaded CSV export file.
     csv_path: String,
 }
 
-#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployOpenlibraryLogImportInput {
+    // The file path of the uploaded CSV export of the reading log.
+    csv_path: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct DeployTraktImportInput {
     // The public username in Trakt.
     username: String,
+    // The client id of a personal Trakt application. Uses the Ryot one if not provided.
+    client_id: Option<String>,
 }
 
-#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct DeployMovaryImportInput {
     // The file path of the uploaded CSV history file.
     history: String,
     // The file path of the uploaded CSV ratings file.
-    ratings: String,
+    ratings: Option<String>,
     // The file path of the uploaded CSV watchlist file.
     watchlist: String,
 }
 
-#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployImdbImportInput {
+    // The file path of the uploaded CSV ratings or list export.
+    csv: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployRyotImportInput {
+    // The file path of the uploaded complete export of another Ryot instance.
+    export: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeploySerializdImportInput {
+    // The file path of the uploaded ZIP export.
+    export_archive: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployLastFmImportInput {
+    /// The name of the Last.fm account.
+    username: String,
+    /// The Last.fm API key.
+    api_key: String,
+    /// Only import the scrobbles after this date.
+    from: Option<DateTimeUtc>,
+    /// Only import the scrobbles before this date.
+    to: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeploySpotifyImportInput {
+    // The file path of the uploaded ZIP export of the extended streaming history.
+    export_archive: String,
+    // Plays shorter than this number of seconds are skipped.
+    min_seconds_played: Option<u32>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeploySteamImportInput {
+    /// The Steam Web API key.
+    api_key: String,
+    /// The 64 bit id of the Steam account, for eg: "76561197960287930".
+    steam_id: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployTvTimeImportInput {
+    // The file path of the uploaded ZIP export.
+    export_archive: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployLetterboxdImportInput {
+    // The file path of the uploaded ZIP export.
+    export_archive: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployAnilistImportInput {
+    // The public username in AniList.
+    username: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployKitsuImportInput {
+    // The public username in Kitsu.
+    username: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployPlexImportInput {
+    /// The base url of the Plex server.
+    base_url: String,
+    /// The authentication token of the Plex account.
+    token: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployJellyfinImportInput {
+    /// The base url of the Jellyfin server.
+    base_url: String,
+    /// An API key generated in the Jellyfin dashboard.
+    api_key: String,
+    /// The id of the Jellyfin user whose history will be imported.
+    user_id: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployKodiImportInput {
+    /// The base url of the Kodi web server, for eg: `http://localhost:8080`.
+    base_url: String,
+    username: String,
+    password: String,
+}
+
+/// The names of the columns in a CSV file that hold the details of an item.
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct GenericCsvColumnMapping {
+    /// The column containing the title.
+    title: String,
+    /// The column containing the identifier of the item in the source.
+    identifier: String,
+    /// The column containing the lot, for eg: `Movie` or `Book`.
+    lot: String,
+    /// The column containing the source, for eg: `Tmdb` or `Openlibrary`.
+    source: String,
+    /// The column containing the rating (out of 100).
+    rating: Option<String>,
+    /// The column containing the date when the item was watched.
+    watched_date: Option<String>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployGenericCsvImportInput {
+    // The file path of the uploaded CSV file.
+    csv: String,
+    column_mapping: GenericCsvColumnMapping,
+    // The scale of the ratings in the CSV file. They are taken to be out of 100
+    // if not set.
+    source_rating_scale: Option<ImportRatingScale>,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployHltbImportInput {
+    // The file path of the uploaded CSV export file.
+    csv: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployBackloggdImportInput {
+    // The file path of the uploaded CSV export file.
+    csv: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployNetflixImportInput {
+    // The file path of the uploaded viewing activity CSV file.
+    csv: String,
+}
+
+/// A streaming service whose viewing activity export can be imported.
+#[derive(Debug, Enum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum StreamingService {
+    Disney,
+    Hulu,
+    Netflix,
+    Prime,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployStreamingCsvImportInput {
+    // The file path of the uploaded viewing activity CSV file.
+    csv: String,
+    // The service that the file was exported from, which decides its columns.
+    service: StreamingService,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployPodcastOpmlImportInput {
+    // The file path of the uploaded OPML file.
+    opml: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct DeployMalImportInput {
     /// The anime export file path (uploaded via temporary upload).
-    anime_path: String,
+    anime_path: Option<String>,
     /// The manga export file path (uploaded via temporary upload).
-    manga_path: String,
+    manga_path: Option<String>,
 }
 
-#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployHardcoverImportInput {
+    /// The API token from the Hardcover account settings.
+    api_token: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct DeployStoryGraphImportInput {
     // The file path of the uploaded CSV export file.
     export: String,
 }
 
-#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct StrongAppImportMapping {
     source_name: String,
     target_name: String,
     multiplier: Option<Decimal>,
 }
 
-#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct DeployStrongAppImportInput {
     // The path to the CSV file in the local file system.
     export_path: String,
     mapping: Vec<StrongAppImportMapping>,
 }
 
-#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct DeployJsonImportInput {
     // The file path of the uploaded JSON export.
     export: String,
 }
 
-#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployAudibleImportInput {
+    // The file path of the uploaded CSV library export.
+    csv: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeploySimklImportInput {
+    /// An access token obtained by authorizing the application.
+    access_token: String,
+    /// The client id of the Simkl application.
+    client_id: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeployTmdbImportInput {
+    /// The API key of the TMDB application.
+    api_key: String,
+    /// A session id authorized by the user for the application.
+    session_id: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct DeployAudiobookshelfImportInput {
     api_url: String,
     api_key: String,
 }
 
-#[derive(Debug, InputObject, Serialize, Deserialize, Clone)]
+/// Add items from a collection in the source to a collection with a different
+/// name in Ryot.
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ImportCollectionMapping {
+    /// The name of the collection in the source.
+    source: String,
+    /// The name of the collection in Ryot. It will be created if it does not exist.
+    target: String,
+}
+
+#[derive(Debug, InputObject, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct DeployImportJobInput {
     pub source: ImportSource,
     pub media_tracker: Option<DeployMediaTrackerImportInput>,
     pub goodreads: Option<DeployGoodreadsImportInput>,
+    pub openlibrary_log: Option<DeployOpenlibraryLogImportInput>,
+    pub calibre: Option<DeployCalibreImportInput>,
+    pub hardcover: Option<DeployHardcoverImportInput>,
+    pub generic_csv: Option<DeployGenericCsvImportInput>,
+    pub hltb: Option<DeployHltbImportInput>,
+    pub backloggd: Option<DeployBackloggdImportInput>,
     pub trakt: Option<DeployTraktImportInput>,
+    pub tv_time: Option<DeployTvTimeImportInput>,
+    pub letterboxd: Option<DeployLetterboxdImportInput>,
+    pub imdb: Option<DeployImdbImportInput>,
     pub movary: Option<DeployMovaryImportInput>,
+    pub netflix: Option<DeployNetflixImportInput>,
+    pub plex: Option<DeployPlexImportInput>,
+    pub jellyfin: Option<DeployJellyfinImportInput>,
+    pub kodi: Option<DeployKodiImportInput>,
+    pub lastfm: Option<DeployLastFmImportInput>,
     pub mal: Option<DeployMalImportInput>,
+    pub podcast_opml: Option<DeployPodcastOpmlImportInput>,
+    pub anilist: Option<DeployAnilistImportInput>,
+    pub kitsu: Option<DeployKitsuImportInput>,
     pub story_graph: Option<DeployStoryGraphImportInput>,
     pub strong_app: Option<DeployStrongAppImportInput>,
+    pub tmdb: Option<DeployTmdbImportInput>,
+    pub simkl: Option<DeploySimklImportInput>,
+    pub ryot: Option<DeployRyotImportInput>,
+    pub serializd: Option<DeploySerializdImportInput>,
+    pub spotify: Option<DeploySpotifyImportInput>,
+    pub steam: Option<DeploySteamImportInput>,
+    pub streaming_csv: Option<DeployStreamingCsvImportInput>,
     pub audiobookshelf: Option<DeployAudiobookshelfImportInput>,
+    pub audible: Option<DeployAudibleImportInput>,
     pub json: Option<DeployJsonImportInput>,
+    /// Only report what would be imported without changing the library.
+    pub dry_run: Option<bool>,
+    /// Skip seen entries and reviews that already exist in the library.
+    pub deduplicate: Option<bool>,
+    /// The visibility of the imported reviews that the source does not set one
+    /// for, for eg: `PRIVATE` to go through them before they are shown to others.
+    pub default_visibility: Option<Visibility>,
+    /// Leave out the media items that the provider marks as being for adults.
+    /// Their details are fetched during the import if they are not in the
+    /// library yet, which makes the import slower.
+    pub exclude_adult: Option<bool>,
+    /// Get the details of media items whose details are already in the source
+    /// from the provider again, since they might be outdated. The details from
+    /// the source are used if the provider does not have them.
+    pub refresh_details: Option<bool>,
+    /// Link the imported media items to the series they belong to, for eg: the
+    /// TMDB collection of a movie, so that the other entries of a franchise can
+    /// be found from them. Only providers that report series are supported.
+    pub link_series: Option<bool>,
+    /// Collections in the source that should be renamed when importing. Other
+    /// collections keep their original names.
+    pub collection_mapping: Option<Vec<ImportCollectionMapping>>,
+    /// Merge the collections touched by this import with the existing
+    /// collections whose names only differ in case or punctuation, for eg:
+    /// "read" and "Read".
+    pub merge_collections_on_import: Option<bool>,
+    /// Only import media items of these lots. All lots are imported if not set.
+    pub lots: Option<Vec<MetadataLot>>,
+    /// Only import the first media items of the source, for eg: to try out an
+    /// import. The number of items that were left out is shown as `limited` in
+    /// the report.
+    pub limit: Option<usize>,
+    /// Stop importing media items after this many minutes, for eg: when a
+    /// provider is very slow. The items that were not imported yet are
+    /// reported as failed so that they can be retried.
+    pub max_runtime_minutes: Option<u32>,
+    /// The IANA name of the timezone of the user, for eg: `Europe/Berlin`. Dates
+    /// in the source without a timezone are read in it, and the dates of seen
+    /// entries are calculated in it. Defaults to UTC.
+    pub timezone: Option<String>,
+    /// A HTTP(S) URL to which the result of the import is sent (as a JSON POST
+    /// request) once it finishes. It can not point to a loopback, private or
+    /// link-local address unless its host is in `importer.callback_allowed_hosts`.
+    pub callback_url: Option<String>,
+    /// Run the import again periodically. Every run only imports the items that
+    /// do not exist in the library yet.
+    pub schedule: Option<ImportSchedule>,
+    /// How items are matched with the providers. Defaults to `FUZZY_TITLE`.
+    pub matching: Option<MatchStrategy>,
+    /// How similar (in percent) the title of an item that was found by searching
+    /// needs to be to the title in the source. Less similar items are reported as
+    /// failed instead of being imported. Defaults to 70.
+    pub min_title_similarity: Option<u8>,
+    /// A key that identifies this import. If an import with the same key is still
+    /// running, it is not deployed again and the id of its report is returned.
+    pub idempotency_key: Option<String>,
+    /// Set when the job is deployed so that the report can be found again if the
+    /// job is run again after an interruption.
+    #[graphql(skip)]
+    #[serde(default)]
+    pub job_id: Option<String>,
+    /// Set when the import is deployed as part of a bulk import.
+    #[graphql(skip)]
+    #[serde(default)]
+    pub bulk_id: Option<String>,
+}
+
+/// How the items of an import are matched with the items on the providers.
+#[derive(Debug, Enum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchStrategy {
+    /// Only import items that the source identifies by an id, for eg: a TMDB id
+    /// or an ISBN.
+    StrictId,
+    /// Also import items that were found by searching for their title.
+    #[default]
+    FuzzyTitle,
 }
 
 /// The various steps in which media importing can fail
@@ -143,6 +558,11 @@ pub enum ImportFailStep {
     SeenHistoryConversion,
     /// Failed to save a review/rating item
     ReviewConversion,
+    /// Failed to add an item to a collection
+    CollectionConversion,
+    /// Failed unexpectedly while importing the item, for eg: because of data that
+    /// could not be handled
+    ItemImport,
 }
 
 #[derive(
@@ -153,11 +573,492 @@ pub struct ImportFailedItem {
     step: ImportFailStep,
     identifier: String,
     error: Option<String>,
+    /// The item from the source, needed to import it again when retrying the import.
+    #[graphql(skip)]
+    #[serde(default)]
+    item: Option<ImportRetryItem>,
+    /// Possible matches found on the provider which the user can pick from to
+    /// resolve this item manually.
+    #[serde(default)]
+    candidates: Vec<ImportCandidate>,
+    /// The kind of error, for failures whose cause is known.
+    #[serde(default)]
+    error_code: Option<ImportErrorCode>,
+}
+
+impl ImportFailedItem {
+    /// A failed item without a retry item, candidates or error code, which can be
+    /// set afterwards for the failures that have them.
+    pub fn new(
+        lot: Option<MetadataLot>,
+        step: ImportFailStep,
+        identifier: String,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            lot,
+            step,
+            identifier,
+            error,
+            item: None,
+            candidates: vec![],
+            error_code: None,
+        }
+    }
+}
+
+/// A media item from the source along with the identifier that it is committed
+/// with, which is not part of its serialized form.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(transparent)]
+pub struct ImportRetryItem(serde_json::Value);
+
+impl ImportRetryItem {
+    fn new(item: &ImportOrExportMediaItem) -> Option<Self> {
+        let identifier = item.internal_identifier.as_ref()?;
+        serde_json::to_value((item, identifier)).ok().map(Self)
+    }
+
+    fn item(&self) -> Option<ImportOrExportMediaItem> {
+        let (mut item, identifier): (ImportOrExportMediaItem, ImportOrExportItemIdentifier) =
+            serde_json::from_value(self.0.clone()).ok()?;
+        item.internal_identifier = Some(identifier);
+        Some(item)
+    }
+}
+
+/// The settings of an import that decide how its items are saved, kept so that
+/// failed items are saved the same way when they are retried.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+pub struct ImportItemSettings {
+    deduplicate: bool,
+    default_visibility: Option<Visibility>,
+    exclude_adult: bool,
+    timezone: Option<String>,
+}
+
+/// The causes of failures of imported items, which decide whether it is worth
+/// retrying them.
+#[derive(Debug, Enum, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum ImportErrorCode {
+    /// The item does not exist on the provider
+    ProviderNotFound,
+    /// More than one item on the provider matches the item
+    AmbiguousMatch,
+    /// The provider rejected the request because too many were made
+    RateLimited,
+    /// The provider did not respond in time
+    Timeout,
+    /// The data in the source could not be read
+    InvalidData,
+}
+
+impl ImportErrorCode {
+    /// Guess the cause of a failure from its step and error message, since
+    /// providers only return the message.
+    fn from_failure(step: ImportFailStep, error: &str) -> Option<Self> {
+        let error = error.to_lowercase();
+        if ["429", "rate limit", "too many requests"]
+            .iter()
+            .any(|e| error.contains(e))
+        {
+            Some(Self::RateLimited)
+        } else if ["timed out", "timeout"].iter().any(|e| error.contains(e)) {
+            Some(Self::Timeout)
+        } else if step == ImportFailStep::InputTransformation {
+            Some(Self::InvalidData)
+        } else if ["404", "not found", "no results found"]
+            .iter()
+            .any(|e| error.contains(e))
+        {
+            Some(Self::ProviderNotFound)
+        } else {
+            None
+        }
+    }
+
+    /// Whether retrying the item can succeed, which is not the case when the
+    /// provider does not have it or the source data is wrong.
+    fn can_be_retried(code: Option<Self>) -> bool {
+        matches!(code, None | Some(Self::RateLimited) | Some(Self::Timeout))
+    }
+}
+
+fn parse_timezone(timezone: Option<&str>) -> Result<Option<Tz>> {
+    timezone
+        .map(|t| {
+            t.parse::<Tz>()
+                .map_err(|_| Error::new(format!("Unknown timezone: {t}")))
+        })
+        .transpose()
+}
+
+/// Read a date without a timezone from the source as being in the timezone of
+/// the import.
+fn local_to_utc(date: NaiveDateTime, timezone: Option<Tz>) -> DateTimeUtc {
+    timezone
+        .and_then(|tz| tz.from_local_datetime(&date).earliest())
+        .map_or_else(|| date.and_utc(), |d| d.with_timezone(&Utc))
+}
+
+/// The day on which an item was seen, in the timezone of the import.
+fn seen_date(date: DateTimeUtc, timezone: Option<Tz>) -> NaiveDate {
+    // DEV: Sources that only have the day store it at midnight UTC, which would
+    // move to the day before in timezones behind UTC
+    match timezone {
+        Some(tz) if date.time() != NaiveTime::MIN => date.with_timezone(&tz).date_naive(),
+        _ => date.date_naive(),
+    }
+}
+
+/// The metadata that an item is saved as, before its details are fetched.
+fn partial_metadata(
+    item: &ImportOrExportMediaItem,
+    identifier: &ImportOrExportItemIdentifier,
+) -> PartialMetadataWithoutId {
+    match identifier {
+        ImportOrExportItemIdentifier::NeedsDetails { identifier, title } => {
+            PartialMetadataWithoutId {
+                identifier: identifier.to_owned(),
+                title: title.to_owned(),
+                image: None,
+                lot: item.lot,
+                source: item.source,
+            }
+        }
+        ImportOrExportItemIdentifier::AlreadyFilled(a) => PartialMetadataWithoutId {
+            identifier: a.identifier.to_owned(),
+            title: a.title.to_owned(),
+            image: None,
+            lot: a.lot,
+            source: a.source,
+        },
+    }
+}
+
+/// Set the error code of the failed items that do not have one yet.
+fn set_error_codes(failed_items: &mut [ImportFailedItem]) {
+    for item in failed_items.iter_mut().filter(|i| i.error_code.is_none()) {
+        item.error_code = item
+            .error
+            .as_deref()
+            .and_then(|e| ImportErrorCode::from_failure(item.step, e));
+    }
 }
 
 #[derive(Debug, SimpleObject, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ImportCandidate {
+    identifier: String,
+    title: String,
+    publish_year: Option<i32>,
+}
+
+impl From<&MetadataSearchItem> for ImportCandidate {
+    fn from(item: &MetadataSearchItem) -> Self {
+        Self {
+            identifier: item.identifier.clone(),
+            title: item.title.clone(),
+            publish_year: item.publish_year,
+        }
+    }
+}
+
+/// Convert the first few results of a provider search into the candidates of a
+/// failed item.
+fn get_import_candidates<'a>(
+    items: impl IntoIterator<Item = &'a MetadataSearchItem>,
+) -> Vec<ImportCandidate> {
+    items
+        .into_iter()
+        .take(MAX_IMPORT_CANDIDATES)
+        .map(ImportCandidate::from)
+        .collect()
+}
+
+// DEV: Titles are compared without punctuation since sources and providers
+// format them differently, for eg: "Marvel's Spider-Man" and "Marvels Spider Man"
+fn normalize_title(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Get the service that the items of an import are looked up with. Previews do
+/// not contact any provider, so the service is not even created for them.
+async fn lookup_service<T>(
+    preview: bool,
+    service: impl Future<Output = Result<T>>,
+) -> Result<Option<T>> {
+    if preview {
+        return Ok(None);
+    }
+    service.await.map(Some)
+}
+
+/// Find the identifier of the item with exactly the given title on the provider.
+/// When no match (or more than one match) is found, the failed item contains the
+/// closest results so that the item can be added manually. Without a provider (for
+/// eg: when previewing an import), the item is not looked up and has no identifier.
+async fn find_by_exact_title(
+    provider: Option<&Provider>,
+    lot: MetadataLot,
+    title: &str,
+) -> std::result::Result<String, ImportFailedItem> {
+    let Some(provider) = provider else {
+        return Ok(String::new());
+    };
+    let failed = |error: String, candidates, error_code| ImportFailedItem {
+        lot: Some(lot),
+        step: ImportFailStep::MediaDetailsFromProvider,
+        identifier: title.to_owned(),
+        error: Some(error),
+        item: None,
+        candidates,
+        error_code,
+    };
+    let search = provider
+        .metadata_search(title, None, true)
+        .await
+        .map_err(|e| failed(e.to_string(), vec![], None))?
+        .items;
+    let normalized = normalize_title(title);
+    let matches = search
+        .iter()
+        .filter(|s| normalize_title(&s.title) == normalized)
+        .collect_vec();
+    match matches.as_slice() {
+        [found] => Ok(found.identifier.clone()),
+        [] => Err(failed(
+            if search.is_empty() {
+                "No results found on the provider".to_owned()
+            } else {
+                "No exact match found on the provider".to_owned()
+            },
+            get_import_candidates(&search),
+            Some(ImportErrorCode::ProviderNotFound),
+        )),
+        _ => Err(failed(
+            format!(
+                "Ambiguous match, {} results on the provider have this title",
+                matches.len()
+            ),
+            get_import_candidates(matches),
+            Some(ImportErrorCode::AmbiguousMatch),
+        )),
+    }
+}
+
+/// An episode of a podcast that was played, with the dates of all its plays.
+#[derive(Debug, Default)]
+struct PlayedEpisode {
+    /// What the episode is reported as when it can not be matched.
+    identifier: String,
+    played_on: Vec<DateTimeUtc>,
+}
+
+/// The seen entries of the played episodes of a podcast, which are matched by
+/// their name since the sources do not have their numbers. Episodes that could
+/// not be matched are added to the failed items. Without a provider (for eg:
+/// when previewing an import), every play is a seen entry without an episode.
+async fn podcast_seen_history(
+    provider: Option<&Provider>,
+    identifier: &str,
+    title: &str,
+    episodes: HashMap<String, PlayedEpisode>,
+    failed_items: &mut Vec<ImportFailedItem>,
+) -> Option<Vec<ImportOrExportMediaItemSeen>> {
+    let lot = MetadataLot::Podcast;
+    let Some(provider) = provider else {
+        return Some(
+            episodes
+                .into_values()
+                .flat_map(|e| e.played_on)
+                .map(|ended_on| ImportOrExportMediaItemSeen {
+                    ended_on: Some(ended_on),
+                    ..Default::default()
+                })
+                .sorted_by_key(|s| s.ended_on)
+                .collect(),
+        );
+    };
+    let specifics = match provider.metadata_details(identifier).await {
+        Ok(details) => details.podcast_specifics.unwrap_or_default(),
+        Err(e) => {
+            failed_items.push(ImportFailedItem::new(
+                Some(lot),
+                ImportFailStep::MediaDetailsFromProvider,
+                title.to_owned(),
+                Some(e.to_string()),
+            ));
+            return None;
+        }
+    };
+    let mut seen_history = vec![];
+    for (name, episode) in episodes {
+        let normalized = normalize_title(&name);
+        let Some(found) = specifics
+            .episodes
+            .iter()
+            .find(|e| normalize_title(&e.title) == normalized)
+        else {
+            failed_items.push(ImportFailedItem::new(
+                Some(lot),
+                ImportFailStep::MediaDetailsFromProvider,
+                episode.identifier,
+                Some(format!("Could not find the episode {name:?} of {title:?}")),
+            ));
+            continue;
+        };
+        seen_history.extend(episode.played_on.into_iter().map(|ended_on| {
+            ImportOrExportMediaItemSeen {
+                ended_on: Some(ended_on),
+                podcast_episode_number: Some(found.number),
+                ..Default::default()
+            }
+        }));
+    }
+    seen_history.sort_by_key(|s| s.ended_on);
+    Some(seen_history)
+}
+
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
 pub struct ImportDetails {
     pub total: usize,
+    /// The number of seen entries and reviews that were not imported because
+    /// they already existed.
+    #[serde(default)]
+    pub skipped: usize,
+    /// The number of media items that were not imported because their lot was
+    /// not selected.
+    #[serde(default)]
+    pub filtered: usize,
+    /// The number of media items that were not imported because of the limit of
+    /// the import.
+    #[serde(default)]
+    pub limited: usize,
+    /// The number of media items that were not imported because they are for
+    /// adults.
+    #[serde(default)]
+    pub excluded_adult: usize,
+    /// The number of media items that were imported.
+    #[serde(default)]
+    pub media_imported: usize,
+    /// The number of media items that could not be read from the source or
+    /// fetched from the provider.
+    #[serde(default)]
+    pub media_failed: usize,
+    /// The number of collections that did not exist before the import.
+    #[serde(default)]
+    pub collections_created: usize,
+    /// The number of reviews and ratings that were imported.
+    #[serde(default)]
+    pub reviews_imported: usize,
+    /// The number of seen entries that were imported.
+    #[serde(default)]
+    pub seen_entries_imported: usize,
+    /// The number of series that the imported media items were linked to.
+    #[serde(default)]
+    pub series_linked: usize,
+    /// Whether the import stopped because it ran for longer than its maximum
+    /// runtime. The items that it did not get to are reported as failed.
+    #[serde(default)]
+    pub timed_out: bool,
+    /// The error that stopped the import before all of its items were processed.
+    /// The items that it did not get to are reported as failed.
+    #[serde(default)]
+    pub error: Option<String>,
+    /// The date of the most recent seen entry of the imported items. It is not
+    /// set if some items were left out because of the limit or the maximum
+    /// runtime of the import.
+    #[serde(default)]
+    pub last_seen_on: Option<DateTimeUtc>,
+    /// The time spent in requests to providers during the import, grouped by the
+    /// provider.
+    #[serde(default)]
+    pub provider_timings: Vec<ImportProviderTiming>,
+}
+
+impl ImportResultResponse {
+    /// A short summary of the import that can be read by the user, for eg:
+    /// "Imported 342 of 350 items from Goodreads; 8 failed."
+    fn summary(&self, source: ImportSource, unfinished: bool) -> String {
+        let failed = self.failed_items.len();
+        let imported = match source {
+            ImportSource::StrongApp
+            | ImportSource::WorkoutsJson
+            | ImportSource::PeopleJson
+            | ImportSource::MeasurementsJson => self.import.total.saturating_sub(failed),
+            _ => self.import.media_imported,
+        };
+        let mut summary = format!(
+            "Imported {imported} of {total} items from {source}; {failed} failed.",
+            total = self.import.total
+        );
+        if unfinished {
+            summary.push_str(" The import was stopped before it finished.");
+        }
+        summary
+    }
+}
+
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ImportProviderTiming {
+    pub source: MediaSource,
+    /// The number of requests made to the provider.
+    pub count: usize,
+    /// The total time taken in milliseconds.
+    pub total_ms: u64,
+    /// The time taken by the slowest request in milliseconds.
+    pub max_ms: u64,
+}
+
+/// The scale in which a source rates items. Ratings are stored out of 100, so
+/// every importer converts them using the scale of its source.
+#[derive(Debug, Clone, Copy, Enum, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ImportRatingScale {
+    /// Ratings out of 3, for eg: bad, ok and good.
+    Three,
+    /// Ratings out of 5, for eg: stars.
+    Five,
+    /// Ratings out of 10.
+    Ten,
+    /// Ratings out of 20, for eg: half stars out of 10.
+    Twenty,
+    /// Ratings out of 100, for eg: percentages. This is how Ryot stores them.
+    Hundred,
+}
+
+impl ImportRatingScale {
+    /// The highest rating of the scale.
+    fn max(self) -> Decimal {
+        match self {
+            Self::Three => dec!(3),
+            Self::Five => dec!(5),
+            Self::Ten => dec!(10),
+            Self::Twenty => dec!(20),
+            Self::Hundred => dec!(100),
+        }
+    }
+
+    /// Convert a rating in this scale into the scale that Ryot stores it in.
+    pub fn normalize(self, rating: Decimal) -> Decimal {
+        normalize_rating(rating, self, Self::Hundred)
+    }
+}
+
+/// Convert a rating from one scale into another. The result is rounded to two
+/// decimal places (with halves rounded up), and ratings outside of the scale are
+/// clamped to it.
+pub fn normalize_rating(
+    rating: Decimal,
+    from: ImportRatingScale,
+    to: ImportRatingScale,
+) -> Decimal {
+    (rating.saturating_mul(to.max()) / from.max())
+        .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+        .clamp(Decimal::ZERO, to.max())
 }
 
 #[derive(Debug)]
@@ -176,119 +1077,813 @@ pub struct ImportResult {
 pub struct ImportResultResponse {
     pub import: ImportDetails,
     pub failed_items: Vec<ImportFailedItem>,
+    /// The collections that could not be created. Items are still imported, but
+    /// are not added to these collections.
+    #[serde(default)]
+    pub failed_collections: Vec<ImportFailedCollection>,
+    /// The collections of the source whose names were changed because they
+    /// contained characters that can not be used in a collection name.
+    #[serde(default)]
+    pub renamed_collections: Vec<ImportRenamedCollection>,
+    /// The most recent log lines of the import.
+    #[serde(default)]
+    pub logs: Vec<String>,
+    #[graphql(skip)]
+    #[serde(default)]
+    pub settings: Option<ImportItemSettings>,
 }
 
-#[derive(Default)]
-pub struct ImporterQuery;
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ImportFailedCollection {
+    name: String,
+    error: String,
+}
 
-#[Object]
-impl ImporterQuery {
-    /// Get all the import jobs deployed by the user.
-    async fn import_reports(&self, gql_ctx: &Context<'_>) -> Result<Vec<import_report::Model>> {
-        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
-        let user_id = service.user_id_from_ctx(gql_ctx).await?;
-        service.import_reports(user_id).await
+#[derive(Debug, SimpleObject, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ImportRenamedCollection {
+    /// The name of the collection in the source.
+    source: String,
+    /// The name of the collection in Ryot.
+    target: String,
+}
+
+/// The most recent log lines of an import. Lines are logged as usual and also
+/// kept so that they can be saved in the report.
+#[derive(Debug, Default)]
+struct ImportLogs(Mutex<VecDeque<String>>);
+
+impl ImportLogs {
+    fn push(&self, level: Level, line: String) {
+        let mut lines = self.0.lock().unwrap();
+        if lines.len() == MAX_IMPORT_LOGS {
+            lines.pop_front();
+        }
+        lines.push_back(format!("{} {level} {line}", Utc::now().format("%H:%M:%S")));
+    }
+
+    fn debug(&self, line: String) {
+        tracing::debug!("{line}");
+        self.push(Level::DEBUG, line);
+    }
+
+    fn error(&self, line: String) {
+        tracing::error!("{line}");
+        self.push(Level::ERROR, line);
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.0.lock().unwrap().iter().cloned().collect()
     }
 }
 
-#[derive(Default)]
-pub struct ImporterMutation;
+/// The media that the items of an import were committed as, keyed by their lot,
+/// source and identifier. Items of media that appears more than once in the
+/// source (for eg: rewatches listed separately) reuse the media that was committed
+/// for the first one instead of contacting the provider again. It only lives for
+/// one import so that the media is never outdated.
+#[derive(Debug, Default)]
+struct ImportCommitCache(Mutex<HashMap<ImportCommitKey, Arc<OnceCell<i32>>>>);
 
-#[Object]
-impl ImporterMutation {
-    /// Add job to import data from various sources.
-    async fn deploy_import_job(
-        &self,
-        gql_ctx: &Context<'_>,
-        input: DeployImportJobInput,
-    ) -> Result<String> {
-        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
-        let user_id = service.user_id_from_ctx(gql_ctx).await?;
-        service.deploy_import_job(user_id, input).await
+type ImportCommitKey = (MetadataLot, MediaSource, String);
+
+impl ImportCommitCache {
+    /// The cell that the media is committed into. Items of the same media share
+    /// it, so that an item waits for a commit of the media that is in progress
+    /// instead of committing it again.
+    fn cell(&self, partial: &PartialMetadataWithoutId) -> Arc<OnceCell<i32>> {
+        let key = (partial.lot, partial.source, partial.identifier.clone());
+        self.0.lock().unwrap().entry(key).or_default().clone()
     }
 }
 
-pub struct ImporterService {
-    media_service: Arc<MiscellaneousService>,
-    exercise_service: Arc<ExerciseService>,
-    timezone: Arc<chrono_tz::Tz>,
+/// The time spent in requests to each provider during an import.
+#[derive(Debug, Default)]
+struct ImportProviderTimings(Mutex<HashMap<MediaSource, ImportProviderTiming>>);
+
+impl ImportProviderTimings {
+    fn record(&self, source: MediaSource, duration: StdDuration) {
+        let ms = duration.as_millis() as u64;
+        let mut timings = self.0.lock().unwrap();
+        let timing = timings.entry(source).or_insert(ImportProviderTiming {
+            source,
+            count: 0,
+            total_ms: 0,
+            max_ms: 0,
+        });
+        timing.count += 1;
+        timing.total_ms += ms;
+        timing.max_ms = timing.max_ms.max(ms);
+    }
+
+    /// The timings of the providers, starting with the one that took the longest.
+    fn slowest_first(&self) -> Vec<ImportProviderTiming> {
+        self.0
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .sorted_unstable_by_key(|t| std::cmp::Reverse(t.total_ms))
+            .collect()
+    }
 }
 
-impl AuthProvider for ImporterService {}
+/// The results of the media items of an import that have been processed so far.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+pub struct ImportedItems {
+    processed: usize,
+    skipped: usize,
+    media_imported: usize,
+    excluded_adult: usize,
+    reviews_imported: usize,
+    seen_entries_imported: usize,
+    /// The media that the items were committed as.
+    metadata_ids: Vec<i32>,
+    /// The failed items along with the index of the item that they belong to.
+    failed_items: Vec<(usize, ImportFailedItem)>,
+}
 
-impl ImporterService {
-    pub fn new(
-        media_service: Arc<MiscellaneousService>,
-        exercise_service: Arc<ExerciseService>,
-        timezone: Arc<chrono_tz::Tz>,
-    ) -> Self {
-        Self {
-            media_service,
-            exercise_service,
-            timezone,
-        }
+impl ImportedItems {
+    fn add(&mut self, idx: usize, result: ImportedMediaItem) {
+        self.processed += 1;
+        self.skipped += result.skipped;
+        self.media_imported += result.committed as usize;
+        self.excluded_adult += result.excluded_adult as usize;
+        self.reviews_imported += result.reviews;
+        self.seen_entries_imported += result.seen_entries;
+        self.metadata_ids.extend(result.metadata_id);
+        self.failed_items
+            .extend(result.failed_items.into_iter().map(|f| (idx, f)));
     }
+}
 
-    pub async fn deploy_import_job(
-        &self,
-        user_id: i32,
-        mut input: DeployImportJobInput,
-    ) -> Result<String> {
-        if let Some(s) = input.media_tracker.as_mut() {
-            s.api_url = s.api_url.trim_end_matches('/').to_owned()
+/// How far an interrupted media import got, so that it can be resumed without
+/// importing the same items again.
+#[derive(Debug, Serialize, Deserialize, FromJsonQueryResult, Eq, PartialEq, Clone)]
+pub struct ImportCursor {
+    /// The hash of the parsed items. The cursor is only used if the source returns
+    /// the same items when the import is resumed.
+    input_hash: String,
+    /// The number of items from the start that have all been processed.
+    position: usize,
+    /// The indices of the items after the position that have been processed.
+    completed: Vec<usize>,
+    /// The results of the processed items.
+    results: ImportedItems,
+}
+
+impl ImportCursor {
+    fn new(input_hash: &str, completed: &[bool], results: &ImportedItems) -> Self {
+        let position = completed.iter().take_while(|c| **c).count();
+        Self {
+            input_hash: input_hash.to_owned(),
+            position,
+            completed: (position..completed.len())
+                .filter(|i| completed[*i])
+                .collect(),
+            results: results.clone(),
         }
-        let job = self
-            .media_service
-            .perform_application_job
-            .clone()
-            .push(ApplicationJob::ImportFromExternalSource(
-                user_id,
-                Box::new(input),
-            ))
-            .await
-            .unwrap();
-        Ok(job.to_string())
     }
 
-    pub async fn invalidate_import_jobs(&self) -> Result<()> {
-        let all_jobs = ImportReport::find()
-            .filter(import_report::Column::Success.is_null())
-            .all(&self.media_service.db)
-            .await?;
-        for job in all_jobs {
-            if Utc::now() - job.started_on > Duration::try_hours(24).unwrap() {
-                tracing::debug!("Invalidating job with id = {id}", id = job.id);
-                let mut job: import_report::ActiveModel = job.into();
-                job.success = ActiveValue::Set(Some(false));
-                job.save(&self.media_service.db).await?;
+    /// Mark the items that were processed before the import was interrupted.
+    fn mark_completed(&self, completed: &mut [bool]) {
+        let position = self.position.min(completed.len());
+        completed[..position].fill(true);
+        for idx in self.completed.iter() {
+            if let Some(c) = completed.get_mut(*idx) {
+                *c = true;
             }
         }
-        Ok(())
     }
+}
 
-    pub async fn import_reports(&self, user_id: i32) -> Result<Vec<import_report::Model>> {
-        let reports = ImportReport::find()
-            .filter(import_report::Column::UserId.eq(user_id))
-            .order_by_desc(import_report::Column::StartedOn)
-            .all(&self.media_service.db)
-            .await
-            .unwrap();
-        Ok(reports)
+/// A hash of the media items of an import, including the identifiers that they are
+/// committed with, which are not part of their serialized form.
+fn hash_import_items(items: &[ImportOrExportMediaItem]) -> String {
+    let mut hasher = Sha256::new();
+    for item in items {
+        hasher.update(serde_json::to_vec(&(item, &item.internal_identifier)).unwrap_or_default());
     }
+    format!("{:x}", hasher.finalize())
+}
 
-    pub async fn start_importing(
-        &self,
-        user_id: i32,
-        input: Box<DeployImportJobInput>,
-    ) -> Result<()> {
-        match input.source {
-            ImportSource::StrongApp | ImportSource::WorkoutsJson => {
-                self.import_workouts(user_id, input).await?
-            }
+/// The settings and state shared by all the media items of an import.
+struct ImportContext<'a> {
+    user_id: i32,
+    report_id: i32,
+    logs: &'a ImportLogs,
+    commit_cache: &'a ImportCommitCache,
+    timings: &'a ImportProviderTimings,
+    preferences: &'a UserPreferences,
+    deduplicate: bool,
+    default_visibility: Option<Visibility>,
+    exclude_adult: bool,
+    refresh_details: bool,
+    timezone: Option<Tz>,
+}
+
+/// The outcome of importing a single media item.
+#[derive(Debug, Default)]
+struct ImportedMediaItem {
+    failed_items: Vec<ImportFailedItem>,
+    /// Whether the media item could be fetched from the provider.
+    committed: bool,
+    /// Whether the media item was left out because it is for adults.
+    excluded_adult: bool,
+    /// The media that the item was committed as.
+    metadata_id: Option<i32>,
+    skipped: usize,
+    seen_entries: usize,
+    reviews: usize,
+}
+
+/// The entities saved for an imported media item. The tasks that run after they
+/// are saved are performed once the transaction of the item has been committed.
+#[derive(Debug, Default)]
+struct SavedMediaItemEntities {
+    seen: Vec<seen::Model>,
+    reviews: Vec<review::ActiveModel>,
+    /// The number of seen entries that were created by the import.
+    seen_entries: usize,
+    skipped: usize,
+}
+
+/// The input of the source of an import, which is only optional because every
+/// source has its own field.
+fn source_input<T>(input: Option<T>, source: ImportSource) -> Result<T> {
+    input.ok_or_else(|| Error::new(format!("The input for the {source:?} import is missing")))
+}
+
+/// Attach the step at which saving an imported item failed to the error.
+fn failed_at<E: Into<Error>>(step: ImportFailStep) -> impl FnOnce(E) -> (ImportFailStep, Error) {
+    move |e| (step, e.into())
+}
+
+#[derive(Debug, SimpleObject, Clone)]
+pub struct ImportPreviewItem {
+    title: String,
+    lot: MetadataLot,
+    seen_history: usize,
+    reviews: usize,
+    collections: Vec<String>,
+}
+
+#[derive(Debug, SimpleObject, Clone)]
+pub struct ImportPreviewLot {
+    lot: MetadataLot,
+    count: usize,
+}
+
+/// The items that were found in the source of an import, without anything being
+/// imported.
+#[derive(Debug, SimpleObject, Clone)]
+pub struct ImportPreview {
+    /// The number of media items found for each lot.
+    lots: Vec<ImportPreviewLot>,
+    seen_history: usize,
+    reviews: usize,
+    /// The names of all the collections that items would be added to.
+    collections: Vec<String>,
+    items: Vec<ImportPreviewItem>,
+    failed_items: Vec<ImportFailedItem>,
+}
+
+#[derive(Debug, SimpleObject, Clone)]
+pub struct ImportProgressStarted {
+    pub total: usize,
+}
+
+#[derive(Debug, SimpleObject, Clone)]
+pub struct ImportProgressItemProcessed {
+    pub index: usize,
+    pub success: bool,
+}
+
+#[derive(Debug, SimpleObject, Clone)]
+pub struct ImportProgressFinished {
+    pub total: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Union, Clone)]
+pub enum ImportProgressEvent {
+    Started(ImportProgressStarted),
+    ItemProcessed(ImportProgressItemProcessed),
+    Finished(ImportProgressFinished),
+}
+
+#[derive(Default)]
+pub struct ImporterQuery;
+
+#[Object]
+impl ImporterQuery {
+    /// Get all the import jobs deployed by the user.
+    async fn import_reports(
+        &self,
+        gql_ctx: &Context<'_>,
+        bulk_id: Option<String>,
+    ) -> Result<Vec<import_report::Model>> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.import_reports(user_id, bulk_id).await
+    }
+
+    /// Get an import job deployed by the user, for eg: to follow its progress.
+    async fn import_report(
+        &self,
+        gql_ctx: &Context<'_>,
+        report_id: i32,
+    ) -> Result<import_report::Model> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.import_report(user_id, report_id).await
+    }
+
+    /// Get the number of imports that are waiting to be run, for eg: to find out
+    /// why an import has not started yet.
+    async fn import_queue_status(&self, gql_ctx: &Context<'_>) -> Result<ImportQueueStatus> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.import_queue_status(user_id).await
+    }
+
+    /// Get the imports of the user which are run periodically.
+    async fn scheduled_imports(&self, gql_ctx: &Context<'_>) -> Result<Vec<UserScheduledImport>> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.scheduled_imports(user_id).await
+    }
+
+    /// Read the items from the source of a media import and summarize them,
+    /// without importing anything. No provider is contacted, so items are not
+    /// matched and failures found while looking them up are not reported.
+    async fn preview_import(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: DeployImportJobInput,
+    ) -> Result<ImportPreview> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        service.user_id_from_ctx(gql_ctx).await?;
+        service.preview_import(input).await
+    }
+}
+
+#[derive(Default)]
+pub struct ImporterMutation;
+
+#[Object]
+impl ImporterMutation {
+    /// Add job to import data from various sources. Returns the id of the report
+    /// of the import, which can be used to follow its progress.
+    async fn deploy_import_job(
+        &self,
+        gql_ctx: &Context<'_>,
+        input: DeployImportJobInput,
+    ) -> Result<String> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.deploy_import_job(user_id, input).await
+    }
+
+    /// Deploy imports from several sources at once. Returns the ids of their
+    /// reports, which can be listed together using the id of the bulk import.
+    async fn deploy_bulk_import(
+        &self,
+        gql_ctx: &Context<'_>,
+        inputs: Vec<DeployImportJobInput>,
+    ) -> Result<BulkImportResponse> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.deploy_bulk_import(user_id, inputs).await
+    }
+
+    /// Import the items of a previous import that failed because of a problem that
+    /// can go away, for eg: a provider that could not be reached. Their seen history,
+    /// reviews and collections are imported along with them.
+    async fn retry_failed_import_items(
+        &self,
+        gql_ctx: &Context<'_>,
+        report_id: i32,
+    ) -> Result<ImportResultResponse> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.retry_failed_import_items(user_id, report_id).await
+    }
+
+    /// Delete an import report. If `delete_media` is set, the seen history, reviews
+    /// and collection entries created by the import are deleted too.
+    async fn delete_import_report(
+        &self,
+        gql_ctx: &Context<'_>,
+        report_id: i32,
+        delete_media: Option<bool>,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service
+            .delete_import_report(user_id, report_id, delete_media.unwrap_or_default())
+            .await
+    }
+
+    /// Check that the media items imported by a report are still known to their
+    /// providers. Items that are not are searched for by their title, and the
+    /// seen history, reviews and collections of the user are moved to the item
+    /// that is found.
+    async fn reconcile_import_report(
+        &self,
+        gql_ctx: &Context<'_>,
+        report_id: i32,
+    ) -> Result<ImportReconcileResult> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.reconcile_import_report(user_id, report_id).await
+    }
+
+    /// Stop running an import periodically. Reports of earlier runs are kept.
+    async fn cancel_scheduled_import(
+        &self,
+        gql_ctx: &Context<'_>,
+        scheduled_import_id: usize,
+    ) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service
+            .cancel_scheduled_import(user_id, scheduled_import_id)
+            .await
+    }
+
+    /// Stop an import job that is still running. Items that were already
+    /// imported are kept.
+    async fn cancel_import(&self, gql_ctx: &Context<'_>, report_id: i32) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.cancel_import(user_id, report_id).await
+    }
+
+    /// Pause or resume starting imports, for eg: while a provider is down. Imports
+    /// that are running are not stopped, and the others stay queued until the
+    /// worker is resumed. This is only available to admins.
+    async fn set_import_worker_paused(&self, gql_ctx: &Context<'_>, paused: bool) -> Result<bool> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.set_import_worker_paused(user_id, paused).await
+    }
+}
+
+#[derive(Default)]
+pub struct ImporterSubscription;
+
+#[Subscription]
+impl ImporterSubscription {
+    /// Get live progress events of an import job. The stream ends once the job
+    /// has finished.
+    async fn import_progress(
+        &self,
+        gql_ctx: &Context<'_>,
+        report_id: i32,
+    ) -> Result<BoxStream<'static, ImportProgressEvent>> {
+        let service = gql_ctx.data_unchecked::<Arc<ImporterService>>();
+        let user_id = service.user_id_from_ctx(gql_ctx).await?;
+        service.import_progress(user_id, report_id).await
+    }
+}
+
+pub struct ImporterService {
+    config: Arc<AppConfig>,
+    progress_events: broadcast::Sender<(i32, ImportProgressEvent)>,
+    media_service: Arc<MiscellaneousService>,
+    exercise_service: Arc<ExerciseService>,
+    perform_import_job: SqliteStorage<ImportJob>,
+    timezone: Arc<chrono_tz::Tz>,
+    /// Whether imports are left in the queue instead of being started. This is
+    /// not persisted, so the worker is running again after a restart.
+    worker_paused: AtomicBool,
+}
+
+impl AuthProvider for ImporterService {}
+
+impl ImporterService {
+    pub fn new(
+        config: Arc<AppConfig>,
+        media_service: Arc<MiscellaneousService>,
+        exercise_service: Arc<ExerciseService>,
+        perform_import_job: &SqliteStorage<ImportJob>,
+        timezone: Arc<chrono_tz::Tz>,
+    ) -> Self {
+        let (progress_events, _) = broadcast::channel(PROGRESS_EVENTS_CAPACITY);
+        Self {
+            config,
+            progress_events,
+            media_service,
+            exercise_service,
+            perform_import_job: perform_import_job.clone(),
+            timezone,
+            worker_paused: AtomicBool::new(false),
+        }
+    }
+
+    pub async fn preview_import(&self, input: DeployImportJobInput) -> Result<ImportPreview> {
+        if matches!(
+            input.source,
+            ImportSource::StrongApp
+                | ImportSource::WorkoutsJson
+                | ImportSource::PeopleJson
+                | ImportSource::MeasurementsJson
+        ) {
+            return Err(Error::new("Previews are only supported for media imports"));
+        }
+        let lots = input.lots.clone();
+        let matching = input.matching.unwrap_or_default();
+        let min_title_similarity = input.min_title_similarity;
+        let limit = input.limit;
+        let mut import = self.parse_media_import(input, true).await?;
+        reject_invalid_sources(&mut import);
+        filter_media_by_lots(&mut import, lots.as_deref());
+        reject_title_matches(&mut import, matching);
+        reject_dissimilar_matches(&mut import, min_title_similarity);
+        limit_media(&mut import, limit);
+        import.media.iter_mut().for_each(apply_item_status);
+        let lots = import
+            .media
+            .iter()
+            .counts_by(|m| m.lot)
+            .into_iter()
+            .map(|(lot, count)| ImportPreviewLot { lot, count })
+            .sorted_unstable_by_key(|l| std::cmp::Reverse(l.count))
+            .collect();
+        let collections = import
+            .collections
+            .iter()
+            .map(|c| c.name.clone())
+            .chain(import.media.iter().flat_map(|m| m.collections.clone()))
+            .unique()
+            .collect();
+        set_error_codes(&mut import.failed_items);
+        Ok(ImportPreview {
+            lots,
+            seen_history: import.media.iter().map(|m| m.seen_history.len()).sum(),
+            reviews: import.media.iter().map(|m| m.reviews.len()).sum(),
+            collections,
+            items: import
+                .media
+                .into_iter()
+                .map(|m| ImportPreviewItem {
+                    title: m.source_id,
+                    lot: m.lot,
+                    seen_history: m.seen_history.len(),
+                    reviews: m.reviews.len(),
+                    collections: m.collections,
+                })
+                .collect(),
+            failed_items: import.failed_items,
+        })
+    }
+
+    pub async fn retry_failed_import_items(
+        &self,
+        user_id: i32,
+        report_id: i32,
+    ) -> Result<ImportResultResponse> {
+        let Some(report) = ImportReport::find_by_id(report_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .one(&self.media_service.db)
+            .await?
+        else {
+            return Err(Error::new("Import report does not exist"));
+        };
+        let Some(mut details) = report.details.clone() else {
+            return Err(Error::new("Import report has not finished yet"));
+        };
+        let settings = details.settings.clone().unwrap_or_default();
+        let preferences =
+            partial_user_by_id::<UserWithOnlyPreferences>(&self.media_service.db, user_id)
+                .await?
+                .preferences;
+        let logs = ImportLogs::default();
+        let commit_cache = ImportCommitCache::default();
+        let timings = ImportProviderTimings::default();
+        let ctx = ImportContext {
+            user_id,
+            report_id,
+            logs: &logs,
+            commit_cache: &commit_cache,
+            timings: &timings,
+            preferences: &preferences,
+            deduplicate: settings.deduplicate,
+            default_visibility: settings.default_visibility,
+            exclude_adult: settings.exclude_adult,
+            refresh_details: false,
+            timezone: parse_timezone(settings.timezone.as_deref())?,
+        };
+        let (retried, mut failed_items): (Vec<_>, Vec<_>) = details
+            .failed_items
+            .into_iter()
+            .partition(|i| i.item.is_some() && ImportErrorCode::can_be_retried(i.error_code));
+        let total = retried.len();
+        let mut committed = 0;
+        // DEV: The whole item is imported again, since none of its seen history,
+        // reviews and collections were saved when it failed
+        for (idx, failed) in retried.into_iter().enumerate() {
+            let Some(item) = failed.item.as_ref().and_then(|i| i.item()) else {
+                failed_items.push(failed);
+                continue;
+            };
+            let result = match self.import_media_item(&ctx, idx, total, &item).await {
+                Ok(result) => result,
+                Err(e) => unexpected_item_failure(&logs, &item, e.message),
+            };
+            if !result.failed_items.is_empty() {
+                failed_items.extend(result.failed_items);
+                continue;
+            }
+            committed += 1;
+            details.import.media_failed = details.import.media_failed.saturating_sub(1);
+            if result.excluded_adult {
+                details.import.excluded_adult += 1;
+                continue;
+            }
+            details.import.media_imported += 1;
+            details.import.skipped += result.skipped;
+            details.import.seen_entries_imported += result.seen_entries;
+            details.import.reviews_imported += result.reviews;
+        }
+        set_error_codes(&mut failed_items);
+        details.failed_items = failed_items;
+        details.logs.extend(logs.lines());
+        let extra_logs = details.logs.len().saturating_sub(MAX_IMPORT_LOGS);
+        details.logs.drain(..extra_logs);
+        tracing::debug!("Imported {committed} previously failed items of report {report_id}");
+        let mut model: import_report::ActiveModel = report.into();
+        model.details = ActiveValue::Set(Some(details.clone()));
+        model.update(&self.media_service.db).await?;
+        if committed > 0 {
+            self.media_service
+                .deploy_background_job(user_id, BackgroundJob::CalculateSummary)
+                .await
+                .ok();
+        }
+        Ok(details)
+    }
+
+    /// Create the collections that items will be added to once, before any of the
+    /// items are imported. Collections that can not be created are returned so that
+    /// the rest of the import can continue.
+    async fn create_collections(
+        &self,
+        user_id: i32,
+        inputs: Vec<CreateOrUpdateCollectionInput>,
+    ) -> Vec<ImportFailedCollection> {
+        let mut failed = vec![];
+        for input in inputs {
+            let name = input.name.clone();
+            if let Err(e) = self
+                .media_service
+                .create_or_update_collection(user_id, input)
+                .await
+            {
+                tracing::debug!("Could not create collection {name:?}: {e:?}");
+                failed.push(ImportFailedCollection {
+                    name,
+                    error: e.message,
+                });
+            }
+        }
+        failed
+    }
+
+    async fn count_collections(&self, user_id: i32) -> Result<usize> {
+        let count = Collection::find()
+            .filter(collection::Column::UserId.eq(user_id))
+            .count(&self.media_service.db)
+            .await?;
+        Ok(count as usize)
+    }
+
+    /// Merge the collections that were touched by an import into the collections
+    /// of the user which have the same normalized name. Default collections, and
+    /// otherwise the oldest collection, are kept.
+    async fn merge_duplicate_collections(&self, user_id: i32, touched: Vec<String>) -> Result<()> {
+        let touched = touched
+            .iter()
+            .map(|n| normalize_collection_name(n))
+            .collect::<HashSet<_>>();
+        let defaults = DefaultCollection::iter()
+            .map(|c| c.to_string())
+            .collect_vec();
+        let groups = Collection::find()
+            .filter(collection::Column::UserId.eq(user_id))
+            .order_by_asc(collection::Column::CreatedOn)
+            .order_by_asc(collection::Column::Id)
+            .all(&self.media_service.db)
+            .await?
+            .into_iter()
+            .into_group_map_by(|c| normalize_collection_name(&c.name))
+            .into_iter()
+            .filter(|(name, group)| group.len() > 1 && touched.contains(name))
+            .map(|(_, group)| group)
+            .collect_vec();
+        for group in groups {
+            let target = group
+                .iter()
+                .find(|c| defaults.contains(&c.name))
+                .unwrap_or(&group[0]);
+            tracing::debug!(
+                "Merging collections {:?} into {:?}",
+                group.iter().map(|c| &c.name).collect_vec(),
+                target.name
+            );
+            self.media_service
+                .merge_collections(user_id, target.id, group.iter().map(|c| c.id).collect_vec())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Get the ids of the associations between the collections of a user and a
+    /// media item.
+    async fn user_collection_entities<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        user_id: i32,
+        metadata_id: i32,
+    ) -> Result<Vec<i32>> {
+        let ids = CollectionToEntity::find()
+            .select_only()
+            .column(collection_to_entity::Column::Id)
+            .inner_join(Collection)
+            .filter(collection::Column::UserId.eq(user_id))
+            .filter(collection_to_entity::Column::MetadataId.eq(metadata_id))
+            .into_tuple::<i32>()
+            .all(db)
+            .await?;
+        Ok(ids)
+    }
+
+    pub async fn import_progress(
+        &self,
+        user_id: i32,
+        report_id: i32,
+    ) -> Result<BoxStream<'static, ImportProgressEvent>> {
+        let Some(report) = ImportReport::find_by_id(report_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .one(&self.media_service.db)
+            .await?
+        else {
+            return Err(Error::new("Import report does not exist"));
+        };
+        if report.finished_on.is_some() {
+            return Ok(stream::empty().boxed());
+        }
+        let receiver = self.progress_events.subscribe();
+        let events = stream::unfold(Some(receiver), move |receiver| async move {
+            let mut receiver = receiver?;
+            loop {
+                match receiver.recv().await {
+                    Ok((id, event)) if id == report_id => {
+                        let finished = matches!(event, ImportProgressEvent::Finished(_));
+                        return Some((event, (!finished).then_some(receiver)));
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+        Ok(events.boxed())
+    }
+
+    /// Publish a progress event of an import job. Events are dropped if nobody is
+    /// listening, so this never affects the import itself.
+    fn send_progress_event(&self, job: &import_report::Model, event: ImportProgressEvent) {
+        self.progress_events.send((job.id, event)).ok();
+    }
+
+    async fn is_import_job_cancelled(&self, job: &import_report::Model) -> Result<bool> {
+        let cancelled = ImportReport::find_by_id(job.id)
+            .one(&self.media_service.db)
+            .await?
+            .and_then(|r| r.was_cancelled)
+            .unwrap_or_default();
+        Ok(cancelled)
+    }
+
+    pub async fn start_importing(
+        &self,
+        user_id: i32,
+        input: Box<DeployImportJobInput>,
+    ) -> Result<()> {
+        let dry_run = input.dry_run.unwrap_or_default();
+        let job_id = input.job_id.clone();
+        match input.source {
+            ImportSource::StrongApp | ImportSource::WorkoutsJson => {
+                self.import_workouts(user_id, input).await?
+            }
             ImportSource::PeopleJson => self.import_people(user_id, input).await?,
             ImportSource::MeasurementsJson => self.import_measurements(user_id, input).await?,
             _ => self.import_media(user_id, input).await?,
         };
+        if dry_run {
+            return Ok(());
+        }
+        self.send_import_summary(user_id, job_id.as_deref()).await;
         self.media_service
             .deploy_background_job(user_id, BackgroundJob::CalculateSummary)
             .await
@@ -302,21 +1897,47 @@ impl ImporterService {
 
     #[instrument(skip(self, input))]
     async fn import_people(&self, user_id: i32, input: Box<DeployImportJobInput>) -> Result<()> {
-        let db_import_job = self.start_import_job(user_id, input.source).await?;
+        let db_import_job = self
+            .resume_or_start_import_job(user_id, input.source, input.job_id.clone())
+            .await?;
         let mut import = match input.source {
             ImportSource::PeopleJson => json::people_import(input.json.unwrap()).await.unwrap(),
             _ => unreachable!(),
         };
-        let details = ImportResultResponse {
+        let mut details = ImportResultResponse {
             import: ImportDetails {
                 total: import.people.len(),
+                ..Default::default()
             },
             failed_items: vec![],
+            failed_collections: vec![],
+            renamed_collections: vec![],
+            logs: vec![],
+            settings: None,
         };
+        if input.dry_run.unwrap_or_default() {
+            self.finish_import_job(db_import_job, details, input.callback_url.as_deref())
+                .await?;
+            return Ok(());
+        }
+        if let Some(mapping) = input.collection_mapping.as_ref() {
+            map_collections(&mut import, mapping);
+        }
+        details.renamed_collections = sanitize_collections(&mut import);
         let preferences =
             partial_user_by_id::<UserWithOnlyPreferences>(&self.media_service.db, user_id)
                 .await?
                 .preferences;
+        let item_collections = import
+            .people
+            .iter()
+            .flat_map(|p| p.collections.iter().cloned())
+            .unique()
+            .collect_vec();
+        let touched_collections = item_collections.clone();
+        let failed_collections = self
+            .create_collections(user_id, collection_inputs(item_collections))
+            .await;
         for (idx, item) in import.people.iter().enumerate() {
             let person = self
                 .media_service
@@ -328,29 +1949,24 @@ impl ImporterService {
                 })
                 .await?;
             for review in item.reviews.iter() {
-                if let Some(input) =
-                    convert_review_into_input(review, &preferences, None, Some(person.id))
-                {
+                if let Some(input) = convert_review_into_input(
+                    review,
+                    &preferences,
+                    input.default_visibility,
+                    None,
+                    Some(person.id),
+                ) {
                     if let Err(e) = self.media_service.post_review(user_id, input).await {
-                        import.failed_items.push(ImportFailedItem {
-                            lot: None,
-                            step: ImportFailStep::ReviewConversion,
-                            identifier: item.name.to_owned(),
-                            error: Some(e.message),
-                        });
+                        import.failed_items.push(ImportFailedItem::new(
+                            None,
+                            ImportFailStep::ReviewConversion,
+                            item.name.to_owned(),
+                            Some(e.message),
+                        ));
                     };
                 }
             }
             for col in item.collections.iter() {
-                self.media_service
-                    .create_or_update_collection(
-                        user_id,
-                        CreateOrUpdateCollectionInput {
-                            name: col.to_string(),
-                            ..Default::default()
-                        },
-                    )
-                    .await?;
                 self.media_service
                     .add_entity_to_collection(
                         user_id,
@@ -380,7 +1996,13 @@ impl ImporterService {
                 name = item.name,
             );
         }
-        self.finish_import_job(db_import_job, details).await?;
+        if input.merge_collections_on_import.unwrap_or_default() {
+            self.merge_duplicate_collections(user_id, touched_collections)
+                .await?;
+        }
+        details.failed_collections = failed_collections;
+        self.finish_import_job(db_import_job, details, input.callback_url.as_deref())
+            .await?;
         Ok(())
     }
 
@@ -390,7 +2012,9 @@ impl ImporterService {
         user_id: i32,
         input: Box<DeployImportJobInput>,
     ) -> Result<()> {
-        let db_import_job = self.start_import_job(user_id, input.source).await?;
+        let db_import_job = self
+            .resume_or_start_import_job(user_id, input.source, input.job_id.clone())
+            .await?;
         let import = match input.source {
             ImportSource::MeasurementsJson => json::measurements_import(input.json.unwrap())
                 .await
@@ -400,22 +2024,35 @@ impl ImporterService {
         let details = ImportResultResponse {
             import: ImportDetails {
                 total: import.measurements.len(),
+                ..Default::default()
             },
             failed_items: vec![],
+            failed_collections: vec![],
+            renamed_collections: vec![],
+            logs: vec![],
+            settings: None,
         };
+        if input.dry_run.unwrap_or_default() {
+            self.finish_import_job(db_import_job, details, input.callback_url.as_deref())
+                .await?;
+            return Ok(());
+        }
         for measurement in import.measurements {
             self.exercise_service
                 .create_user_measurement(user_id, measurement)
                 .await
                 .ok();
         }
-        self.finish_import_job(db_import_job, details).await?;
+        self.finish_import_job(db_import_job, details, input.callback_url.as_deref())
+            .await?;
         Ok(())
     }
 
     #[instrument(skip(self, input))]
     async fn import_workouts(&self, user_id: i32, input: Box<DeployImportJobInput>) -> Result<()> {
-        let db_import_job = self.start_import_job(user_id, input.source).await?;
+        let db_import_job = self
+            .resume_or_start_import_job(user_id, input.source, input.job_id.clone())
+            .await?;
         let import = match input.source {
             ImportSource::StrongApp => {
                 strong_app::import(input.strong_app.unwrap(), self.timezone.clone())
@@ -432,266 +2069,2107 @@ impl ImporterService {
         let details = ImportResultResponse {
             import: ImportDetails {
                 total: import.workouts.len(),
+                ..Default::default()
             },
             failed_items: vec![],
+            failed_collections: vec![],
+            renamed_collections: vec![],
+            logs: vec![],
+            settings: None,
         };
+        if input.dry_run.unwrap_or_default() {
+            self.finish_import_job(db_import_job, details, input.callback_url.as_deref())
+                .await?;
+            return Ok(());
+        }
         for workout in import.workouts {
             self.exercise_service
                 .create_user_workout(user_id, workout)
                 .await
                 .ok();
         }
-        self.finish_import_job(db_import_job, details).await?;
+        self.finish_import_job(db_import_job, details, input.callback_url.as_deref())
+            .await?;
         Ok(())
     }
 
-    #[instrument(skip(self, input))]
-    async fn import_media(&self, user_id: i32, input: Box<DeployImportJobInput>) -> Result<()> {
-        let db_import_job = self.start_import_job(user_id, input.source).await?;
-        let mut import = match input.source {
-            ImportSource::MediaTracker => media_tracker::import(input.media_tracker.unwrap())
-                .await
-                .unwrap(),
-            ImportSource::MediaJson => json::media_import(input.json.unwrap()).await.unwrap(),
-            ImportSource::Mal => mal::import(input.mal.unwrap()).await.unwrap(),
-            ImportSource::Goodreads => goodreads::import(
-                input.goodreads.unwrap(),
-                &self.media_service.get_isbn_service().await.unwrap(),
-            )
-            .await
-            .unwrap(),
-            ImportSource::Trakt => trakt::import(input.trakt.unwrap()).await.unwrap(),
-            ImportSource::Movary => movary::import(input.movary.unwrap()).await.unwrap(),
-            ImportSource::StoryGraph => story_graph::import(
-                input.story_graph.unwrap(),
-                &self.media_service.get_isbn_service().await.unwrap(),
-            )
-            .await
-            .unwrap(),
-            ImportSource::Audiobookshelf => audiobookshelf::import(input.audiobookshelf.unwrap())
-                .await
-                .unwrap(),
-            _ => unreachable!(),
+    /// Read the items of a media import from the source. This is the part of an
+    /// import that does not change the library of the user.
+    async fn parse_media_import(
+        &self,
+        input: DeployImportJobInput,
+        preview: bool,
+    ) -> Result<ImportResult> {
+        let timezone = parse_timezone(input.timezone.as_deref())?;
+        let mut import = match input.source {
+            ImportSource::MediaTracker => {
+                media_tracker::import(source_input(input.media_tracker, input.source)?).await?
+            }
+            ImportSource::MediaJson => {
+                json::media_import(source_input(input.json, input.source)?).await?
+            }
+            ImportSource::Mal => mal::import(source_input(input.mal, input.source)?).await?,
+            ImportSource::GenericCsv => {
+                generic_csv::import(source_input(input.generic_csv, input.source)?).await?
+            }
+            ImportSource::Anilist => {
+                anilist::import(source_input(input.anilist, input.source)?).await?
+            }
+            ImportSource::Kitsu => {
+                kitsu::import(
+                    source_input(input.kitsu, input.source)?,
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::Anime, MediaSource::Anilist),
+                    )
+                    .await?
+                    .as_ref(),
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::Manga, MediaSource::Anilist),
+                    )
+                    .await?
+                    .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Calibre => {
+                calibre::import(
+                    source_input(input.calibre, input.source)?,
+                    lookup_service(preview, self.media_service.get_isbn_service())
+                        .await?
+                        .as_ref(),
+                    lookup_service(preview, self.media_service.get_openlibrary_service())
+                        .await?
+                        .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Goodreads => {
+                goodreads::import(
+                    source_input(input.goodreads, input.source)?,
+                    lookup_service(preview, self.media_service.get_isbn_service())
+                        .await?
+                        .as_ref(),
+                    lookup_service(preview, self.media_service.get_openlibrary_service())
+                        .await?
+                        .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Hardcover => {
+                hardcover::import(
+                    source_input(input.hardcover, input.source)?,
+                    lookup_service(preview, self.media_service.get_isbn_service())
+                        .await?
+                        .as_ref(),
+                    lookup_service(preview, self.media_service.get_openlibrary_service())
+                        .await?
+                        .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Trakt => trakt::import(source_input(input.trakt, input.source)?).await?,
+            ImportSource::TvTime => {
+                tv_time::import(
+                    source_input(input.tv_time, input.source)?,
+                    lookup_service(preview, self.media_service.get_tmdb_non_media_service())
+                        .await?
+                        .as_ref(),
+                    timezone,
+                )
+                .await?
+            }
+            ImportSource::Movary => {
+                movary::import(source_input(input.movary, input.source)?).await?
+            }
+            ImportSource::Plex => {
+                plex::import(
+                    source_input(input.plex, input.source)?,
+                    lookup_service(preview, self.media_service.get_tmdb_non_media_service())
+                        .await?
+                        .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Kodi => {
+                kodi::import(
+                    source_input(input.kodi, input.source)?,
+                    lookup_service(preview, self.media_service.get_tmdb_non_media_service())
+                        .await?
+                        .as_ref(),
+                    timezone,
+                )
+                .await?
+            }
+            ImportSource::Jellyfin => {
+                jellyfin::import(
+                    source_input(input.jellyfin, input.source)?,
+                    lookup_service(preview, self.media_service.get_tmdb_non_media_service())
+                        .await?
+                        .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Imdb => {
+                imdb::import(
+                    source_input(input.imdb, input.source)?,
+                    lookup_service(preview, self.media_service.get_tmdb_non_media_service())
+                        .await?
+                        .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Letterboxd => {
+                letterboxd::import(
+                    source_input(input.letterboxd, input.source)?,
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::Movie, MediaSource::Tmdb),
+                    )
+                    .await?
+                    .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::OpenlibraryLog => {
+                openlibrary_log::import(source_input(input.openlibrary_log, input.source)?).await?
+            }
+            ImportSource::StoryGraph => {
+                story_graph::import(
+                    source_input(input.story_graph, input.source)?,
+                    lookup_service(preview, self.media_service.get_isbn_service())
+                        .await?
+                        .as_ref(),
+                    lookup_service(preview, self.media_service.get_openlibrary_service())
+                        .await?
+                        .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Tmdb => tmdb::import(source_input(input.tmdb, input.source)?).await?,
+            ImportSource::Simkl => {
+                simkl::import(
+                    source_input(input.simkl, input.source)?,
+                    lookup_service(preview, self.media_service.get_tmdb_non_media_service())
+                        .await?
+                        .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::HowLongToBeat => {
+                hltb::import(
+                    source_input(input.hltb, input.source)?,
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::VideoGame, MediaSource::Igdb),
+                    )
+                    .await?
+                    .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Backloggd => {
+                backloggd::import(
+                    source_input(input.backloggd, input.source)?,
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::VideoGame, MediaSource::Igdb),
+                    )
+                    .await?
+                    .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Ryot => ryot::import(source_input(input.ryot, input.source)?).await?,
+            ImportSource::Serializd => {
+                serializd::import(
+                    source_input(input.serializd, input.source)?,
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::Show, MediaSource::Tmdb),
+                    )
+                    .await?
+                    .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::LastFm => {
+                lastfm::import(
+                    source_input(input.lastfm, input.source)?,
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::Podcast, MediaSource::Itunes),
+                    )
+                    .await?
+                    .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Spotify => {
+                spotify::import(
+                    source_input(input.spotify, input.source)?,
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::Podcast, MediaSource::Itunes),
+                    )
+                    .await?
+                    .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Steam => {
+                steam::import(
+                    source_input(input.steam, input.source)?,
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::VideoGame, MediaSource::Igdb),
+                    )
+                    .await?
+                    .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Netflix => {
+                streaming_csv::import(
+                    DeployStreamingCsvImportInput {
+                        csv: source_input(input.netflix, input.source)?.csv,
+                        service: StreamingService::Netflix,
+                    },
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::Movie, MediaSource::Tmdb),
+                    )
+                    .await?
+                    .as_ref(),
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::Show, MediaSource::Tmdb),
+                    )
+                    .await?
+                    .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::StreamingCsv => {
+                streaming_csv::import(
+                    source_input(input.streaming_csv, input.source)?,
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::Movie, MediaSource::Tmdb),
+                    )
+                    .await?
+                    .as_ref(),
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::Show, MediaSource::Tmdb),
+                    )
+                    .await?
+                    .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::PodcastOpml => {
+                opml::import(
+                    source_input(input.podcast_opml, input.source)?,
+                    lookup_service(preview, self.media_service.get_itunes_service())
+                        .await?
+                        .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Audible => {
+                audible::import(
+                    source_input(input.audible, input.source)?,
+                    lookup_service(
+                        preview,
+                        self.media_service
+                            .get_metadata_provider(MetadataLot::AudioBook, MediaSource::Audible),
+                    )
+                    .await?
+                    .as_ref(),
+                )
+                .await?
+            }
+            ImportSource::Audiobookshelf => {
+                audiobookshelf::import(source_input(input.audiobookshelf, input.source)?).await?
+            }
+            source => {
+                return Err(Error::new(format!(
+                    "Imports from {source:?} do not have media items"
+                )))
+            }
         };
+        for item in import.media.iter_mut() {
+            let tags = item.tags.drain(..).collect_vec();
+            item.collections = item.collections.drain(..).chain(tags).unique().collect();
+            expand_repeated_seen(item);
+        }
+        if let Some(mapping) = input.collection_mapping.as_ref() {
+            map_collections(&mut import, mapping);
+        }
+        Ok(import)
+    }
+
+    #[instrument(skip(self, input))]
+    async fn import_media(&self, user_id: i32, input: Box<DeployImportJobInput>) -> Result<()> {
+        let db_import_job = self
+            .resume_or_start_import_job(user_id, input.source, input.job_id.clone())
+            .await?;
+        let logs = ImportLogs::default();
+        let mut import = self.parse_media_import(*input.clone(), false).await?;
+        logs.debug(format!(
+            "Read {media} media items from {source}, {failed} items could not be read",
+            media = import.media.len(),
+            source = input.source,
+            failed = import.failed_items.len()
+        ));
+        let renamed_collections = sanitize_collections(&mut import);
+        for col in renamed_collections.iter() {
+            logs.debug(format!(
+                "Renamed collection {source:?} to {target:?}",
+                source = col.source,
+                target = col.target
+            ));
+        }
+        for item in import.failed_items.iter() {
+            logs.error(format!(
+                "Could not read {identifier:?} from the source: {error}",
+                identifier = item.identifier,
+                error = item.error.as_deref().unwrap_or_default()
+            ));
+        }
+        let invalid = reject_invalid_sources(&mut import);
+        if invalid > 0 {
+            logs.error(format!(
+                "Rejected {invalid} media items whose source does not have media of their type"
+            ));
+        }
+        let filtered = filter_media_by_lots(&mut import, input.lots.as_deref());
+        if filtered > 0 {
+            logs.debug(format!("Skipped {filtered} media items of other lots"));
+        }
+        let rejected = reject_title_matches(&mut import, input.matching.unwrap_or_default())
+            + reject_dissimilar_matches(&mut import, input.min_title_similarity);
+        if rejected > 0 {
+            logs.error(format!(
+                "Rejected {rejected} media items that were only matched by their title"
+            ));
+        }
+        let limited = limit_media(&mut import, input.limit);
+        if limited > 0 {
+            logs.debug(format!(
+                "Left out {limited} media items because of the limit of the import"
+            ));
+        }
+        import.media.iter_mut().for_each(apply_item_status);
         let preferences =
             partial_user_by_id::<UserWithOnlyPreferences>(&self.media_service.db, user_id)
                 .await?
                 .preferences;
+        // DEV: The order needs to be the same every time the import runs so that it
+        // can be resumed using the cursor
         import.media = import
             .media
             .into_iter()
-            .sorted_unstable_by_key(|m| {
-                m.seen_history.len() + m.reviews.len() + m.collections.len()
+            .sorted_by(|a, b| {
+                let count = |m: &ImportOrExportMediaItem| {
+                    m.seen_history.len() + m.reviews.len() + m.collections.len()
+                };
+                count(b)
+                    .cmp(&count(a))
+                    .then_with(|| a.source_id.cmp(&b.source_id))
             })
-            .rev()
             .collect_vec();
-        for col_details in import.collections.into_iter() {
-            self.media_service
-                .create_or_update_collection(user_id, col_details)
+        // DEV: This includes the items imported before the job was resumed
+        let last_seen_on = import
+            .media
+            .iter()
+            .flat_map(|m| m.seen_history.iter().filter_map(|s| s.ended_on))
+            .max();
+        if input.dry_run.unwrap_or_default() {
+            let details = ImportResultResponse {
+                import: ImportDetails {
+                    total: import.media.len(),
+                    filtered,
+                    limited,
+                    ..Default::default()
+                },
+                failed_items: import.failed_items,
+                failed_collections: vec![],
+                renamed_collections,
+                logs: logs.lines(),
+                settings: None,
+            };
+            self.finish_import_job(db_import_job, details, input.callback_url.as_deref())
                 .await?;
+            return Ok(());
         }
-        for (idx, item) in import.media.iter().enumerate() {
-            tracing::debug!(
-                "Importing media with identifier = {iden}",
-                iden = &item.source_id
-            );
-            let rev_length = item.reviews.len();
-            let identifier = item.internal_identifier.clone().unwrap();
-            let data = match identifier {
-                ImportOrExportItemIdentifier::NeedsDetails { identifier, title } => {
-                    let resp = self
-                        .media_service
-                        .create_partial_metadata(PartialMetadataWithoutId {
-                            identifier,
-                            title,
-                            image: None,
-                            lot: item.lot,
-                            source: item.source,
-                        })
-                        .await;
-                    resp.map(|r| IdObject { id: r.id })
+        let mut touched_collections = import
+            .collections
+            .iter()
+            .map(|c| c.name.clone())
+            .collect_vec();
+        let collections_before = self.count_collections(user_id).await?;
+        let mut failed_collections = self.create_collections(user_id, import.collections).await;
+        let total = import.media.len();
+        let item_collections = import
+            .media
+            .iter()
+            .flat_map(|m| m.collections.iter().cloned())
+            .unique()
+            .collect_vec();
+        touched_collections.extend(item_collections.iter().cloned());
+        failed_collections.extend(
+            self.create_collections(user_id, collection_inputs(item_collections))
+                .await,
+        );
+        let collections_created = self
+            .count_collections(user_id)
+            .await?
+            .saturating_sub(collections_before);
+        for col in failed_collections.iter() {
+            logs.error(format!(
+                "Could not create collection {name:?}: {error}",
+                name = col.name,
+                error = col.error
+            ));
+        }
+        self.send_progress_event(
+            &db_import_job,
+            ImportProgressEvent::Started(ImportProgressStarted { total }),
+        );
+        let deduplicate = input.deduplicate.unwrap_or_default();
+        let timezone = parse_timezone(input.timezone.as_deref())?;
+        let settings = ImportItemSettings {
+            deduplicate,
+            default_visibility: input.default_visibility,
+            exclude_adult: input.exclude_adult.unwrap_or_default(),
+            timezone: input.timezone.clone(),
+        };
+        let input_hash = hash_import_items(&import.media);
+        let mut completed = vec![false; total];
+        let mut results = ImportedItems::default();
+        match db_import_job.cursor.as_ref() {
+            Some(cursor) if cursor.input_hash == input_hash => {
+                cursor.mark_completed(&mut completed);
+                results = cursor.results.clone();
+                logs.debug(format!(
+                    "Resuming import job with id = {id} after {processed} items",
+                    id = db_import_job.id,
+                    processed = results.processed
+                ));
+            }
+            Some(_) => logs.error(format!(
+                "The items of the source changed since import job with id = {id} was interrupted, so it is started again",
+                id = db_import_job.id
+            )),
+            None => {}
+        }
+        let deadline = input
+            .max_runtime_minutes
+            .map(|m| Instant::now() + StdDuration::from_secs(u64::from(m) * 60));
+        let mut timed_out = false;
+        let mut error = None;
+        let commit_cache = ImportCommitCache::default();
+        let timings = ImportProviderTimings::default();
+        let ctx = ImportContext {
+            user_id,
+            report_id: db_import_job.id,
+            logs: &logs,
+            commit_cache: &commit_cache,
+            timings: &timings,
+            preferences: &preferences,
+            deduplicate,
+            default_visibility: settings.default_visibility,
+            exclude_adult: settings.exclude_adult,
+            refresh_details: input.refresh_details.unwrap_or_default(),
+            timezone,
+        };
+        // DEV: Boxing the futures is needed for the compiler to prove that they are `Send`.
+        // Panics are caught so that an item that can not be handled does not stop
+        // the whole import.
+        let items = import
+            .media
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !completed[*idx])
+            .map(|(idx, item)| {
+                AssertUnwindSafe(self.import_media_item(&ctx, idx, total, item))
+                    .catch_unwind()
+                    .map(move |result| (idx, result))
+                    .boxed()
+            })
+            .collect_vec();
+        let mut items =
+            stream::iter(items).buffer_unordered(self.config.importer.concurrency.max(1));
+        let mut processed_now = 0;
+        loop {
+            // DEV: The deadline is also checked while waiting, since a provider
+            // that does not respond would otherwise keep the import running
+            let next = match deadline {
+                Some(deadline) => {
+                    match tokio::time::timeout_at(deadline.into(), items.next()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            timed_out = true;
+                            None
+                        }
+                    }
                 }
-                ImportOrExportItemIdentifier::AlreadyFilled(a) => {
-                    self.media_service
-                        .commit_media_internal(*a.clone(), None)
-                        .await
+                None => items.next().await,
+            };
+            let Some((idx, result)) = next else {
+                break;
+            };
+            let result = match result {
+                Ok(Ok(result)) => result,
+                Ok(Err(e)) => unexpected_item_failure(&logs, &import.media[idx], e.message),
+                Err(panic) => {
+                    unexpected_item_failure(&logs, &import.media[idx], panic_message(panic))
                 }
             };
-            let metadata = match data {
-                Ok(r) => r,
+            self.send_progress_event(
+                &db_import_job,
+                ImportProgressEvent::ItemProcessed(ImportProgressItemProcessed {
+                    index: idx,
+                    success: result.failed_items.is_empty(),
+                }),
+            );
+            results.add(idx, result);
+            completed[idx] = true;
+            processed_now += 1;
+            // DEV: Errors after this point are not caused by the item, so the import
+            // is stopped and finished with the items imported so far
+            match self.is_import_job_cancelled(&db_import_job).await {
+                Ok(true) => {
+                    logs.debug(format!(
+                        "Import job with id = {id} was cancelled",
+                        id = db_import_job.id
+                    ));
+                    break;
+                }
+                Ok(false) => {}
                 Err(e) => {
-                    tracing::error!("{e:?}");
-                    import.failed_items.push(ImportFailedItem {
-                        lot: Some(item.lot),
-                        step: ImportFailStep::MediaDetailsFromProvider,
-                        identifier: item.source_id.to_owned(),
-                        error: Some(e.message),
-                    });
-                    continue;
+                    error = Some(e.message);
+                    break;
                 }
-            };
-            for seen in item.seen_history.iter() {
-                let progress = if seen.progress.is_some() {
-                    seen.progress
-                } else {
-                    Some(100)
-                };
+            }
+            if processed_now % PROGRESS_UPDATE_INTERVAL == 0 {
+                let cursor = ImportCursor::new(&input_hash, &completed, &results);
                 if let Err(e) = self
-                    .media_service
-                    .progress_update(
-                        ProgressUpdateInput {
-                            metadata_id: metadata.id,
-                            progress,
-                            date: seen.ended_on.map(|d| d.date_naive()),
-                            show_season_number: seen.show_season_number,
-                            show_episode_number: seen.show_episode_number,
-                            podcast_episode_number: seen.podcast_episode_number,
-                            anime_episode_number: seen.anime_episode_number,
-                            manga_chapter_number: seen.manga_chapter_number,
-                            change_state: None,
-                        },
-                        user_id,
-                        false,
+                    .update_import_job_progress(
+                        &db_import_job,
+                        results.processed,
+                        total,
+                        Some(cursor),
                     )
                     .await
                 {
-                    import.failed_items.push(ImportFailedItem {
-                        lot: Some(item.lot),
-                        step: ImportFailStep::SeenHistoryConversion,
-                        identifier: item.source_id.to_owned(),
-                        error: Some(e.message),
-                    });
-                };
-            }
-            for review in item.reviews.iter() {
-                if let Some(input) =
-                    convert_review_into_input(review, &preferences, Some(metadata.id), None)
-                {
-                    if let Err(e) = self.media_service.post_review(user_id, input).await {
-                        import.failed_items.push(ImportFailedItem {
-                            lot: Some(item.lot),
-                            step: ImportFailStep::ReviewConversion,
-                            identifier: item.source_id.to_owned(),
-                            error: Some(e.message),
-                        });
-                    };
+                    error = Some(e.message);
+                    break;
                 }
             }
-            for col in item.collections.iter() {
-                self.media_service
-                    .create_or_update_collection(
-                        user_id,
-                        CreateOrUpdateCollectionInput {
-                            name: col.to_string(),
-                            ..Default::default()
-                        },
-                    )
-                    .await?;
-                self.media_service
-                    .add_entity_to_collection(
-                        user_id,
-                        ChangeCollectionToEntityInput {
-                            collection_name: col.to_string(),
-                            metadata_id: Some(metadata.id),
-                            ..Default::default()
-                        },
-                    )
-                    .await
-                    .ok();
-            }
-            self.media_service
-                .toggle_media_monitor(
-                    user_id,
-                    ToggleMediaMonitorInput {
-                        metadata_id: Some(metadata.id),
-                        force_value: item.monitored,
-                        ..Default::default()
-                    },
+        }
+        let mut not_processed = 0;
+        if timed_out {
+            logs.error(format!(
+                "Import job with id = {id} ran for longer than {minutes} minutes",
+                id = db_import_job.id,
+                minutes = input.max_runtime_minutes.unwrap_or_default()
+            ));
+        }
+        if let Some(error) = error.as_ref() {
+            logs.error(format!(
+                "Import job with id = {id} was stopped because of an error: {error}",
+                id = db_import_job.id
+            ));
+        }
+        let not_processed_failure = if timed_out {
+            Some((
+                "The import reached its maximum runtime before importing this item",
+                Some(ImportErrorCode::Timeout),
+            ))
+        } else {
+            error.as_ref().map(|_| {
+                (
+                    "The import was stopped by an error before importing this item",
+                    None,
                 )
-                .await?;
-            tracing::debug!(
-                "Imported item: {idx}/{total}, lot: {lot}, history count: {hist}, review count: {rev}, collection count: {col}",
-                idx = idx + 1,
-                total = import.media.len(),
-                lot = item.lot,
-                hist = item.seen_history.len(),
-                rev = rev_length,
-                col = item.collections.len(),
-            );
+            })
+        };
+        if let Some((message, error_code)) = not_processed_failure {
+            for (item, _) in import
+                .media
+                .iter()
+                .zip(completed.iter())
+                .filter(|(_, c)| !**c)
+            {
+                not_processed += 1;
+                import.failed_items.push(ImportFailedItem {
+                    lot: Some(item.lot),
+                    step: ImportFailStep::MediaDetailsFromProvider,
+                    identifier: item.source_id.to_owned(),
+                    error: Some(message.to_owned()),
+                    item: ImportRetryItem::new(item),
+                    candidates: vec![],
+                    error_code,
+                });
+            }
         }
-        tracing::debug!(
-            "Imported {total} media items from {source}",
-            total = import.media.len(),
-            source = db_import_job.source
+        let ImportedItems {
+            processed,
+            skipped,
+            media_imported,
+            excluded_adult,
+            reviews_imported,
+            seen_entries_imported,
+            metadata_ids,
+            failed_items,
+        } = results;
+        let media_failed = import.failed_items.len() + processed - media_imported - excluded_adult;
+        let series_linked = if input.link_series.unwrap_or_default() {
+            match self.link_imported_series(&ctx, metadata_ids).await {
+                Ok(linked) => linked,
+                Err(e) => {
+                    logs.error(format!(
+                        "Could not link the series: {error}",
+                        error = e.message
+                    ));
+                    0
+                }
+            }
+        } else {
+            0
+        };
+        // DEV: Items finish out of order, so sort them to report failures like a
+        // sequential import would
+        import.failed_items.extend(
+            failed_items
+                .into_iter()
+                .sorted_by_key(|(idx, _)| *idx)
+                .map(|(_, f)| f),
         );
+        logs.debug(format!(
+            "Imported {processed} media items from {source}",
+            source = db_import_job.source
+        ));
+        if input.merge_collections_on_import.unwrap_or_default() {
+            if let Err(e) = self
+                .merge_duplicate_collections(user_id, touched_collections)
+                .await
+            {
+                logs.error(format!(
+                    "Could not merge the collections: {error}",
+                    error = e.message
+                ));
+            }
+        }
         let details = ImportResultResponse {
             import: ImportDetails {
-                total: import.media.len(),
+                total: processed + not_processed,
+                skipped,
+                filtered,
+                limited,
+                excluded_adult,
+                media_imported,
+                media_failed,
+                collections_created,
+                reviews_imported,
+                seen_entries_imported,
+                series_linked,
+                timed_out,
+                last_seen_on: last_seen_on
+                    .filter(|_| limited == 0 && !timed_out && error.is_none()),
+                error,
+                provider_timings: timings.slowest_first(),
             },
             failed_items: import.failed_items,
+            failed_collections,
+            renamed_collections,
+            logs: logs.lines(),
+            settings: Some(settings),
         };
-        self.finish_import_job(db_import_job, details).await?;
+        self.finish_import_job(db_import_job, details, input.callback_url.as_deref())
+            .await?;
         Ok(())
     }
 
-    async fn start_import_job(
+    /// Link the imported media to the series that their provider reports them to
+    /// be a part of. Media that is already part of a series is left as it is, and
+    /// every series is only fetched once per import. Returns the number of series
+    /// that were linked.
+    async fn link_imported_series(
         &self,
-        user_id: i32,
-        source: ImportSource,
-    ) -> Result<import_report::Model> {
-        let model = import_report::ActiveModel {
-            user_id: ActiveValue::Set(user_id),
-            source: ActiveValue::Set(source),
-            ..Default::default()
+        ctx: &ImportContext<'_>,
+        metadata_ids: Vec<i32>,
+    ) -> Result<usize> {
+        let logs = ctx.logs;
+        let already_linked: HashSet<i32> = MetadataToMetadataGroup::find()
+            .filter(metadata_to_metadata_group::Column::MetadataId.is_in(metadata_ids.clone()))
+            .all(&self.media_service.db)
+            .await?
+            .into_iter()
+            .map(|m| m.metadata_id)
+            .collect();
+        let mut seen_groups = HashSet::new();
+        let mut linked = 0;
+        for metadata_id in metadata_ids.into_iter().unique() {
+            if already_linked.contains(&metadata_id) {
+                continue;
+            }
+            let Some(metadata) = Metadata::find_by_id(metadata_id)
+                .one(&self.media_service.db)
+                .await?
+            else {
+                continue;
+            };
+            let details = match self
+                .provider_details(ctx, metadata.lot, metadata.source, &metadata.identifier)
+                .await
+            {
+                Ok(details) => details,
+                Err(e) => {
+                    logs.error(format!(
+                        "Could not get the series of {title:?}: {error}",
+                        title = metadata.title,
+                        error = e.message
+                    ));
+                    continue;
+                }
+            };
+            for group in details.group_identifiers {
+                if !seen_groups.insert((metadata.lot, metadata.source, group.clone())) {
+                    continue;
+                }
+                match self
+                    .media_service
+                    .associate_group_with_metadata(metadata.lot, metadata.source, group.clone())
+                    .await
+                {
+                    Ok(()) => {
+                        linked += 1;
+                        logs.debug(format!(
+                            "Linked {title:?} to the series {group:?}",
+                            title = metadata.title
+                        ));
+                    }
+                    Err(e) => logs.error(format!(
+                        "Could not link {title:?} to the series {group:?}: {error}",
+                        title = metadata.title,
+                        error = e.message
+                    )),
+                }
+            }
+        }
+        Ok(linked)
+    }
+
+    /// Replace the details with the ones from the provider, keeping them as they
+    /// are if the provider can not be reached.
+    async fn refresh_media_details(&self, ctx: &ImportContext<'_>, details: &mut MediaDetails) {
+        let fresh = self
+            .provider_details(ctx, details.lot, details.source, &details.identifier)
+            .await;
+        match fresh {
+            Ok(fresh) => *details = fresh,
+            Err(e) => ctx.logs.debug(format!(
+                "Could not refresh the details of {title:?}, using the ones from the source: {error}",
+                title = details.title,
+                error = e.message
+            )),
+        }
+    }
+
+    async fn import_media_item(
+        &self,
+        ctx: &ImportContext<'_>,
+        idx: usize,
+        total: usize,
+        item: &ImportOrExportMediaItem,
+    ) -> Result<ImportedMediaItem> {
+        let logs = ctx.logs;
+        logs.debug(format!(
+            "Importing media with identifier = {iden}",
+            iden = &item.source_id
+        ));
+        let mut result = ImportedMediaItem::default();
+        let rev_length = item.reviews.len();
+        let mut identifier = item.internal_identifier.clone().unwrap();
+        let partial = partial_metadata(item, &identifier);
+        // DEV: A failed commit leaves the cell empty, so the next item of the same
+        // media tries again
+        let mut committed_here = false;
+        let data = ctx
+            .commit_cache
+            .cell(&partial)
+            .get_or_try_init(|| {
+                committed_here = true;
+                async {
+                    match &mut identifier {
+                        ImportOrExportItemIdentifier::NeedsDetails { .. } => self
+                            .media_service
+                            .create_partial_metadata(partial.clone())
+                            .await
+                            .map(|r| r.id),
+                        ImportOrExportItemIdentifier::AlreadyFilled(a) => {
+                            if ctx.refresh_details {
+                                self.refresh_media_details(ctx, a).await;
+                            }
+                            self.media_service
+                                .commit_media_internal(*a.clone(), None)
+                                .await
+                                .map(|r| r.id)
+                        }
+                    }
+                }
+            })
+            .await
+            .map(|id| IdObject { id: *id });
+        if !committed_here {
+            logs.debug(format!(
+                "Reusing the media committed earlier in the import for {iden:?}",
+                iden = item.source_id
+            ));
+        }
+        let metadata = match data {
+            Ok(r) => r,
+            Err(e) => {
+                logs.error(format!(
+                    "Could not get {iden:?} from the provider: {error}",
+                    iden = item.source_id,
+                    error = e.message
+                ));
+                let candidates = match self
+                    .media_service
+                    .get_metadata_provider(item.lot, item.source)
+                    .await
+                {
+                    Ok(provider) => {
+                        let started = Instant::now();
+                        let search = provider.metadata_search(&partial.title, None, true).await;
+                        ctx.timings.record(item.source, started.elapsed());
+                        search
+                            .map(|s| get_import_candidates(&s.items))
+                            .unwrap_or_default()
+                    }
+                    Err(_) => vec![],
+                };
+                result.failed_items.push(ImportFailedItem {
+                    lot: Some(item.lot),
+                    step: ImportFailStep::MediaDetailsFromProvider,
+                    identifier: item.source_id.to_owned(),
+                    error: Some(e.message),
+                    item: ImportRetryItem::new(item),
+                    candidates,
+                    error_code: None,
+                });
+                return Ok(result);
+            }
+        };
+        if ctx.exclude_adult {
+            match self.is_adult_media(ctx, metadata.id).await {
+                Ok(false) => {}
+                Ok(true) => {
+                    logs.debug(format!(
+                        "Left out {iden:?} since it is for adults",
+                        iden = item.source_id
+                    ));
+                    result.excluded_adult = true;
+                    return Ok(result);
+                }
+                Err(e) => {
+                    logs.error(format!(
+                        "Could not find out if {iden:?} is for adults: {error}",
+                        iden = item.source_id,
+                        error = e.message
+                    ));
+                    result.failed_items.push(ImportFailedItem {
+                        lot: Some(item.lot),
+                        step: ImportFailStep::MediaDetailsFromProvider,
+                        identifier: item.source_id.to_owned(),
+                        error: Some(e.message),
+                        item: ImportRetryItem::new(item),
+                        candidates: vec![],
+                        error_code: None,
+                    });
+                    return Ok(result);
+                }
+            }
+        }
+        if !item.images.is_empty() {
+            if let Err(e) = self.fill_missing_images(metadata.id, &item.images).await {
+                logs.error(format!(
+                    "Could not add the images of {iden:?} from the source: {error}",
+                    iden = item.source_id,
+                    error = e.message
+                ));
+            }
+        }
+        let expanded;
+        let item = if item.lot == MetadataLot::Show
+            && item
+                .seen_history
+                .iter()
+                .any(|s| s.watched_through == Some(true))
+        {
+            expanded = self
+                .expand_show_watched_through(ctx, metadata.id, item)
+                .await;
+            &expanded
+        } else {
+            item
+        };
+        // DEV: Everything that is saved for an item is done in a single transaction
+        // so that a failure does not leave it half imported
+        let txn = self.media_service.db.begin().await?;
+        let saved = match self
+            .save_media_item_entities(&txn, ctx, item, metadata.id)
+            .await
+        {
+            Ok(saved) => {
+                txn.commit().await?;
+                saved
+            }
+            Err((step, e)) => {
+                txn.rollback().await?;
+                logs.error(format!(
+                    "Could not import {iden:?}, none of its entries were saved: {error}",
+                    iden = item.source_id,
+                    error = e.message
+                ));
+                result.failed_items.push(ImportFailedItem {
+                    lot: Some(item.lot),
+                    step,
+                    identifier: item.source_id.to_owned(),
+                    error: Some(e.message),
+                    item: ImportRetryItem::new(item),
+                    candidates: vec![],
+                    error_code: None,
+                });
+                return Ok(result);
+            }
         };
-        let model = model.insert(&self.media_service.db).await.unwrap();
-        tracing::debug!("Started import job with id = {id}", id = model.id);
-        Ok(model)
+        result.committed = true;
+        result.metadata_id = Some(metadata.id);
+        result.skipped = saved.skipped;
+        result.seen_entries = saved.seen_entries;
+        result.reviews = saved.reviews.len();
+        // DEV: The item is saved by now, so these tasks failing does not make it
+        // fail to import
+        let post_commit_error = |task: &str, error: Error| {
+            logs.error(format!(
+                "Could not {task} for {iden:?}: {error}",
+                iden = item.source_id,
+                error = error.message
+            ))
+        };
+        for seen in saved.seen {
+            if let Err(e) = self.media_service.after_media_seen_tasks(seen).await {
+                post_commit_error("run the tasks after a seen entry", e);
+            }
+        }
+        for review in saved.reviews {
+            if let Err(e) = self
+                .media_service
+                .after_review_posted_tasks(ctx.user_id, review, true)
+                .await
+            {
+                post_commit_error("run the tasks after a review", e);
+            }
+        }
+        if let Err(e) = self
+            .media_service
+            .toggle_media_monitor(
+                ctx.user_id,
+                ToggleMediaMonitorInput {
+                    metadata_id: Some(metadata.id),
+                    force_value: item.monitored,
+                    ..Default::default()
+                },
+            )
+            .await
+        {
+            post_commit_error("update the monitoring", e);
+        }
+        logs.debug(format!(
+            "Imported item: {idx}/{total}, lot: {lot}, history count: {hist}, review count: {rev}, collection count: {col}",
+            idx = idx + 1,
+            total = total,
+            lot = item.lot,
+            hist = item.seen_history.len(),
+            rev = rev_length,
+            col = item.collections.len(),
+        ));
+        Ok(result)
     }
 
-    async fn finish_import_job(
+    /// Replace the seen entries of a show that was watched through an episode with
+    /// an entry for every episode up to it. Only the entry of the episode itself is
+    /// kept if the episodes of the show can not be found.
+    async fn expand_show_watched_through(
         &self,
-        job: import_report::Model,
-        details: ImportResultResponse,
-    ) -> Result<import_report::Model> {
-        let mut model: import_report::ActiveModel = job.into();
-        model.finished_on = ActiveValue::Set(Some(Utc::now()));
-        model.details = ActiveValue::Set(Some(details));
-        model.success = ActiveValue::Set(Some(true));
-        let model = model.update(&self.media_service.db).await.unwrap();
-        Ok(model)
+        ctx: &ImportContext<'_>,
+        metadata_id: i32,
+        item: &ImportOrExportMediaItem,
+    ) -> ImportOrExportMediaItem {
+        let mut item = item.clone();
+        match self.show_episodes(ctx, metadata_id).await {
+            Ok(episodes) => expand_watched_through(&mut item, &episodes),
+            Err(e) => ctx.logs.error(format!(
+                "Could not get the episodes of {iden:?}, only the last watched episode is imported: {error}",
+                iden = item.source_id,
+                error = e.message
+            )),
+        }
+        item.seen_history
+            .iter_mut()
+            .for_each(|s| s.watched_through = None);
+        item
+    }
+
+    /// The season and episode numbers of the episodes of a show, in the order in
+    /// which they aired. Media that was created by the import only has its title,
+    /// so its details are fetched from the provider.
+    async fn show_episodes(
+        &self,
+        ctx: &ImportContext<'_>,
+        metadata_id: i32,
+    ) -> Result<Vec<(i32, i32)>> {
+        let metadata = Metadata::find_by_id(metadata_id)
+            .one(&self.media_service.db)
+            .await?
+            .ok_or_else(|| Error::new("Media does not exist"))?;
+        let specifics = match metadata.show_specifics {
+            Some(specifics) => specifics,
+            None => self
+                .provider_details(ctx, metadata.lot, metadata.source, &metadata.identifier)
+                .await?
+                .show_specifics
+                .ok_or_else(|| Error::new("The provider does not have the episodes of the show"))?,
+        };
+        Ok(specifics
+            .seasons
+            .iter()
+            .flat_map(|s| {
+                s.episodes
+                    .iter()
+                    .map(|e| (s.season_number, e.episode_number))
+            })
+            .sorted()
+            .collect())
+    }
+
+    /// Whether the media is for adults. Media that was created by the import only
+    /// has its title, so its details are fetched from the provider.
+    async fn is_adult_media(&self, ctx: &ImportContext<'_>, metadata_id: i32) -> Result<bool> {
+        let metadata = Metadata::find_by_id(metadata_id)
+            .one(&self.media_service.db)
+            .await?
+            .ok_or_else(|| Error::new("Media does not exist"))?;
+        if let Some(is_nsfw) = metadata.is_nsfw {
+            return Ok(is_nsfw);
+        }
+        if metadata.is_partial != Some(true) {
+            return Ok(false);
+        }
+        let details = self
+            .provider_details(ctx, metadata.lot, metadata.source, &metadata.identifier)
+            .await?;
+        Ok(details.is_nsfw.unwrap_or_default())
+    }
+
+    /// Fetch the details of media from its provider. Requests that fail because
+    /// the provider is limiting requests or did not respond are retried. The time
+    /// taken by every request is recorded in the timings of the import.
+    async fn provider_details(
+        &self,
+        ctx: &ImportContext<'_>,
+        lot: MetadataLot,
+        source: MediaSource,
+        identifier: &str,
+    ) -> Result<MediaDetails> {
+        let provider = self
+            .media_service
+            .get_metadata_provider(lot, source)
+            .await?;
+        retry_with_backoff(
+            self.config.importer.commit_retries,
+            is_transient_error,
+            || async {
+                let started = Instant::now();
+                let details = provider.metadata_details(identifier).await;
+                ctx.timings.record(source, started.elapsed());
+                details.map_err(|e| Error::new(e.to_string()))
+            },
+        )
+        .await
+    }
+
+    /// Use the images from the source for media that does not have any. Images
+    /// from the provider are never replaced.
+    async fn fill_missing_images(&self, metadata_id: i32, images: &[String]) -> Result<()> {
+        let metadata = Metadata::find_by_id(metadata_id)
+            .one(&self.media_service.db)
+            .await?
+            .ok_or_else(|| Error::new("Media does not exist"))?;
+        if metadata.images.as_ref().is_some_and(|i| !i.is_empty()) {
+            return Ok(());
+        }
+        let images = images
+            .iter()
+            .map(|url| MetadataImage {
+                url: StoredUrl::Url(url.to_owned()),
+                lot: MetadataImageLot::Poster,
+            })
+            .collect();
+        let mut model: metadata::ActiveModel = metadata.into();
+        model.images = ActiveValue::Set(Some(images));
+        model.update(&self.media_service.db).await?;
+        Ok(())
+    }
+
+    /// Save the seen history, reviews and collections of an imported media item
+    /// using the transaction of the item. On failure, the step at which it failed
+    /// is returned so that the item can be reported.
+    async fn save_media_item_entities(
+        &self,
+        txn: &DatabaseTransaction,
+        ctx: &ImportContext<'_>,
+        item: &ImportOrExportMediaItem,
+        metadata_id: i32,
+    ) -> std::result::Result<SavedMediaItemEntities, (ImportFailStep, Error)> {
+        let ImportContext {
+            user_id,
+            report_id,
+            preferences,
+            deduplicate,
+            default_visibility,
+            timezone,
+            ..
+        } = *ctx;
+        let mut saved = SavedMediaItemEntities::default();
+        // DEV: Entities that existed before the import must not be tagged with the
+        // report, otherwise deleting the report would remove them
+        let existing_seen = Seen::find()
+            .filter(seen::Column::UserId.eq(user_id))
+            .filter(seen::Column::MetadataId.eq(metadata_id))
+            .all(txn)
+            .await
+            .map_err(failed_at(ImportFailStep::SeenHistoryConversion))?;
+        let current_state = match item.status {
+            Some(ImportOrExportItemStatus::Dropped) => Some(SeenState::Dropped),
+            Some(ImportOrExportItemStatus::OnHold) => Some(SeenState::OnAHold),
+            _ => None,
+        };
+        let mut imported_seen = vec![];
+        for (idx, seen) in item.seen_history.iter().enumerate() {
+            if deduplicate
+                && existing_seen
+                    .iter()
+                    .any(|e| is_same_seen(e, seen, timezone))
+            {
+                saved.skipped += 1;
+                continue;
+            }
+            let progress = if seen.progress.is_some() {
+                seen.progress
+            } else {
+                Some(100)
+            };
+            let input = ProgressUpdateInput {
+                metadata_id,
+                progress,
+                date: seen.ended_on.map(|d| seen_date(d, timezone)),
+                show_season_number: seen.show_season_number,
+                show_episode_number: seen.show_episode_number,
+                podcast_episode_number: seen.podcast_episode_number,
+                anime_episode_number: seen.anime_episode_number,
+                manga_chapter_number: seen.manga_chapter_number,
+                change_state: None,
+            };
+            if let Ok(mut saved_seen) = self
+                .media_service
+                .progress_update_seen(txn, input, user_id, false)
+                .await
+                .map_err(failed_at(ImportFailStep::SeenHistoryConversion))?
+            {
+                // DEV: Progress updates start the media from scratch, so the progress
+                // of the current entry is set along with its state
+                if let (Some(state), true) = (current_state, idx + 1 == item.seen_history.len()) {
+                    let mut current: seen::ActiveModel = saved_seen.into();
+                    current.state = ActiveValue::Set(state);
+                    current.progress = ActiveValue::Set(seen.progress.unwrap_or_default());
+                    saved_seen = current
+                        .update(txn)
+                        .await
+                        .map_err(failed_at(ImportFailStep::SeenHistoryConversion))?;
+                }
+                if existing_seen.iter().all(|e| e.id != saved_seen.id) {
+                    imported_seen.push(saved_seen.id);
+                }
+                saved.seen.push(saved_seen);
+            }
+        }
+        saved.seen_entries = imported_seen.len();
+        if !imported_seen.is_empty() {
+            Seen::update_many()
+                .col_expr(seen::Column::ImportReportId, Expr::value(report_id))
+                .filter(seen::Column::Id.is_in(imported_seen))
+                .exec(txn)
+                .await
+                .map_err(failed_at(ImportFailStep::SeenHistoryConversion))?;
+        }
+        let existing_reviews = if deduplicate {
+            Review::find()
+                .filter(review::Column::UserId.eq(user_id))
+                .filter(review::Column::MetadataId.eq(metadata_id))
+                .all(txn)
+                .await
+                .map_err(failed_at(ImportFailStep::ReviewConversion))?
+        } else {
+            vec![]
+        };
+        for review in item.reviews.iter() {
+            let Some(input) = convert_review_into_input(
+                review,
+                preferences,
+                default_visibility,
+                Some(metadata_id),
+                None,
+            ) else {
+                continue;
+            };
+            if existing_reviews
+                .iter()
+                .any(|e| is_same_review(e, &input, &preferences.general.review_scale))
+            {
+                saved.skipped += 1;
+                continue;
+            }
+            let review = self
+                .media_service
+                .post_review_in(txn, user_id, input)
+                .await
+                .map_err(failed_at(ImportFailStep::ReviewConversion))?;
+            Review::update_many()
+                .col_expr(review::Column::ImportReportId, Expr::value(report_id))
+                .filter(review::Column::Id.eq(review.id.clone().unwrap()))
+                .exec(txn)
+                .await
+                .map_err(failed_at(ImportFailStep::ReviewConversion))?;
+            saved.reviews.push(review);
+        }
+        let existing_collections = self
+            .user_collection_entities(txn, user_id, metadata_id)
+            .await
+            .map_err(failed_at(ImportFailStep::CollectionConversion))?;
+        for col in item.collections.iter() {
+            let added = add_entity_to_collection(
+                txn,
+                user_id,
+                ChangeCollectionToEntityInput {
+                    collection_name: col.to_string(),
+                    metadata_id: Some(metadata_id),
+                    added_on: item.added_on,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(failed_at(ImportFailStep::CollectionConversion))?;
+            if !added {
+                return Err((
+                    ImportFailStep::CollectionConversion,
+                    Error::new(format!("Could not add the item to the collection {col:?}")),
+                ));
+            }
+        }
+        let added_collections = self
+            .user_collection_entities(txn, user_id, metadata_id)
+            .await
+            .map_err(failed_at(ImportFailStep::CollectionConversion))?
+            .into_iter()
+            .filter(|id| !existing_collections.contains(id))
+            .collect_vec();
+        if !added_collections.is_empty() {
+            CollectionToEntity::update_many()
+                .col_expr(
+                    collection_to_entity::Column::ImportReportId,
+                    Expr::value(report_id),
+                )
+                .filter(collection_to_entity::Column::Id.is_in(added_collections))
+                .exec(txn)
+                .await
+                .map_err(failed_at(ImportFailStep::CollectionConversion))?;
+        }
+        Ok(saved)
+    }
+}
+
+/// Whether an error from a provider can go away by itself, which is the case when
+/// the provider is limiting requests or did not respond in time.
+fn is_transient_error(error: &Error) -> bool {
+    matches!(
+        ImportErrorCode::from_failure(ImportFailStep::MediaDetailsFromProvider, &error.message),
+        Some(ImportErrorCode::RateLimited) | Some(ImportErrorCode::Timeout)
+    )
+}
+
+/// Retry an operation with an exponential backoff (1s, 2s, 4s...) until it succeeds,
+/// fails with an error that `should_retry` rejects or has been retried `retries` times.
+async fn retry_with_backoff<F, Fut, T>(
+    retries: usize,
+    should_retry: fn(&Error) -> bool,
+    operation: F,
+) -> Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Ok(r) => return Ok(r),
+            Err(e) if attempt < retries && should_retry(&e) => {
+                let delay = StdDuration::from_secs(2_u64.pow(attempt as u32));
+                tracing::debug!("Operation failed with {e:?}, retrying after {delay:?}");
+                sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Mark the current read or watch of an item as in progress. An entry without a
+/// finish date is taken as the current one, otherwise a new entry is added.
+fn mark_current_seen_in_progress(seen_history: &mut Vec<ImportOrExportMediaItemSeen>) {
+    let mut current = match seen_history.iter().rposition(|s| s.ended_on.is_none()) {
+        Some(idx) => seen_history.remove(idx),
+        None => ImportOrExportMediaItemSeen::default(),
+    };
+    current.progress = Some(current.progress.unwrap_or(0).min(99));
+    seen_history.push(current);
+}
+
+/// Translate the status of an item in the source into its seen history and
+/// collections. The state of the current seen entry of items that were dropped
+/// or put on hold is set when it is saved.
+fn apply_item_status(item: &mut ImportOrExportMediaItem) {
+    let collection = match item.status {
+        Some(ImportOrExportItemStatus::Planned) => {
+            item.seen_history.clear();
+            DefaultCollection::Watchlist.to_string()
+        }
+        Some(ImportOrExportItemStatus::Dropped) => {
+            mark_current_seen_in_progress(&mut item.seen_history);
+            DROPPED_COLLECTION.to_owned()
+        }
+        Some(ImportOrExportItemStatus::OnHold) => {
+            mark_current_seen_in_progress(&mut item.seen_history);
+            ON_HOLD_COLLECTION.to_owned()
+        }
+        Some(ImportOrExportItemStatus::Completed) => {
+            if item.seen_history.is_empty() {
+                item.seen_history
+                    .push(ImportOrExportMediaItemSeen::default());
+            }
+            return;
+        }
+        None => return,
+    };
+    if !item.collections.contains(&collection) {
+        item.collections.push(collection);
+    }
+}
+
+/// Replace the seen entries that were seen more than once with one entry for each
+/// time. The first one keeps the dates, since the others are not known.
+fn expand_repeated_seen(item: &mut ImportOrExportMediaItem) {
+    if item.seen_history.iter().all(|s| s.times.unwrap_or(1) <= 1) {
+        return;
+    }
+    item.seen_history = item
+        .seen_history
+        .drain(..)
+        .flat_map(|seen| {
+            let times = seen.times.unwrap_or(1).max(1) as usize;
+            let repeated = ImportOrExportMediaItemSeen {
+                started_on: None,
+                ended_on: None,
+                times: None,
+                ..seen.clone()
+            };
+            iter::once(ImportOrExportMediaItemSeen {
+                times: None,
+                ..seen
+            })
+            .chain(iter::repeat(repeated).take(times - 1))
+        })
+        .collect();
+}
+
+/// The outcome of an item whose import failed unexpectedly, for eg: because of an
+/// error while saving it or a panic.
+fn unexpected_item_failure(
+    logs: &ImportLogs,
+    item: &ImportOrExportMediaItem,
+    error: String,
+) -> ImportedMediaItem {
+    logs.error(format!(
+        "Could not import {iden:?} because of an unexpected error: {error}",
+        iden = item.source_id
+    ));
+    ImportedMediaItem {
+        failed_items: vec![ImportFailedItem::new(
+            Some(item.lot),
+            ImportFailStep::ItemImport,
+            item.source_id.to_owned(),
+            Some(error),
+        )],
+        ..Default::default()
+    }
+}
+
+/// The message that a panic was started with.
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => match panic.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "The import of the item panicked".to_owned(),
+        },
+    }
+}
+
+/// Replace the seen entries of a show that were watched through an episode with an
+/// entry for each of the episodes up to it. Specials are only included for entries
+/// of season 0, and episodes that have their own entry are left out.
+fn expand_watched_through(item: &mut ImportOrExportMediaItem, episodes: &[(i32, i32)]) {
+    let mut seen_episodes: HashSet<(i32, i32)> = item
+        .seen_history
+        .iter()
+        .filter(|s| s.watched_through != Some(true))
+        .filter_map(|s| s.show_season_number.zip(s.show_episode_number))
+        .collect();
+    let mut seen_history = vec![];
+    for seen in item.seen_history.drain(..) {
+        let last = seen
+            .show_season_number
+            .zip(seen.show_episode_number)
+            .filter(|_| seen.watched_through == Some(true));
+        let Some(last) = last else {
+            seen_history.push(seen);
+            continue;
+        };
+        let watched = episodes
+            .iter()
+            .copied()
+            .filter(|(season, _)| (*season > 0) == (last.0 > 0))
+            .filter(|episode| *episode <= last)
+            .chain(iter::once(last))
+            .unique()
+            .collect_vec();
+        for (season, episode) in watched {
+            if !seen_episodes.insert((season, episode)) {
+                continue;
+            }
+            seen_history.push(ImportOrExportMediaItemSeen {
+                show_season_number: Some(season),
+                show_episode_number: Some(episode),
+                watched_through: None,
+                ..seen.clone()
+            });
+        }
+    }
+    item.seen_history = seen_history;
+}
+
+/// Move the media items that were matched by their title to the failed items when
+/// only items with an id should be imported, returning the number of items that
+/// were moved.
+fn reject_title_matches(import: &mut ImportResult, matching: MatchStrategy) -> usize {
+    if matching != MatchStrategy::StrictId {
+        return 0;
+    }
+    let (matched, media): (Vec<_>, Vec<_>) =
+        import.media.drain(..).partition(|m| m.matched_by_title);
+    import.media = media;
+    let rejected = matched.len();
+    import.failed_items.extend(matched.into_iter().map(|m| {
+        let candidates = match m.internal_identifier {
+            Some(ImportOrExportItemIdentifier::NeedsDetails { identifier, title }) => {
+                vec![ImportCandidate {
+                    identifier,
+                    title,
+                    publish_year: None,
+                }]
+            }
+            _ => vec![],
+        };
+        ImportFailedItem {
+            lot: Some(m.lot),
+            step: ImportFailStep::MediaDetailsFromProvider,
+            identifier: m.source_id,
+            error: Some("Item was only matched by its title".to_owned()),
+            item: None,
+            candidates,
+            error_code: None,
+        }
+    }));
+    rejected
+}
+
+/// Move the media items that were matched by their title to the failed items when
+/// the title that was found is not similar enough to the title in the source, for
+/// eg: a sequel or a remake. Returns the number of items that were moved.
+fn reject_dissimilar_matches(import: &mut ImportResult, min_similarity: Option<u8>) -> usize {
+    let min_similarity = f64::from(min_similarity.unwrap_or(DEFAULT_MIN_TITLE_SIMILARITY));
+    let similarity = |m: &ImportOrExportMediaItem| match &m.internal_identifier {
+        Some(ImportOrExportItemIdentifier::NeedsDetails { title, .. }) if m.matched_by_title => {
+            strsim::normalized_levenshtein(&normalize_title(&m.source_id), &normalize_title(title))
+                * 100.0
+        }
+        _ => 100.0,
+    };
+    let (media, dissimilar): (Vec<_>, Vec<_>) = import
+        .media
+        .drain(..)
+        .partition(|m| similarity(m) >= min_similarity);
+    import.media = media;
+    let rejected = dissimilar.len();
+    import.failed_items.extend(dissimilar.into_iter().map(|m| {
+        let score = similarity(&m).round();
+        let candidates = match m.internal_identifier {
+            Some(ImportOrExportItemIdentifier::NeedsDetails { identifier, title }) => {
+                vec![ImportCandidate {
+                    identifier,
+                    title,
+                    publish_year: None,
+                }]
+            }
+            _ => vec![],
+        };
+        ImportFailedItem {
+            lot: Some(m.lot),
+            step: ImportFailStep::MediaDetailsFromProvider,
+            identifier: m.source_id,
+            error: Some(format!(
+                "The title found on the provider is only {score}% similar"
+            )),
+            item: None,
+            candidates,
+            error_code: Some(ImportErrorCode::AmbiguousMatch),
+        }
+    }));
+    rejected
+}
+
+/// Whether the provider has media of this lot. Custom media can be of any lot.
+fn is_valid_lot_source(lot: MetadataLot, source: MediaSource) -> bool {
+    match source {
+        MediaSource::Custom => true,
+        MediaSource::Audible => lot == MetadataLot::AudioBook,
+        MediaSource::Openlibrary | MediaSource::GoogleBooks => lot == MetadataLot::Book,
+        MediaSource::Itunes | MediaSource::Listennotes => lot == MetadataLot::Podcast,
+        MediaSource::Tmdb => matches!(lot, MetadataLot::Movie | MetadataLot::Show),
+        MediaSource::Igdb => lot == MetadataLot::VideoGame,
+        MediaSource::Anilist | MediaSource::Mal => {
+            matches!(lot, MetadataLot::Anime | MetadataLot::Manga)
+        }
+        MediaSource::MangaUpdates => lot == MetadataLot::Manga,
+        MediaSource::Vndb => lot == MetadataLot::VisualNovel,
+    }
+}
+
+/// Move the media items whose source does not have media of their lot to the
+/// failed items, before any provider is asked for their details. Returns the
+/// number of items that were moved.
+fn reject_invalid_sources(import: &mut ImportResult) -> usize {
+    let (media, invalid): (Vec<_>, Vec<_>) = import
+        .media
+        .drain(..)
+        .partition(|m| is_valid_lot_source(m.lot, m.source));
+    import.media = media;
+    let rejected = invalid.len();
+    import.failed_items.extend(invalid.into_iter().map(|m| {
+        ImportFailedItem::new(
+            Some(m.lot),
+            ImportFailStep::InputTransformation,
+            m.source_id,
+            Some(format!(
+                "{:?} does not have media of type {:?}",
+                m.source, m.lot
+            )),
+        )
+    }));
+    rejected
+}
+
+/// Remove the media items whose lot is not in the allowed lots, returning the
+/// number of items that were removed.
+fn filter_media_by_lots(import: &mut ImportResult, lots: Option<&[MetadataLot]>) -> usize {
+    let Some(lots) = lots else {
+        return 0;
+    };
+    let total = import.media.len();
+    import.media.retain(|m| lots.contains(&m.lot));
+    total - import.media.len()
+}
+
+/// Keep only the first media items of the import, returning the number of items
+/// that were removed.
+fn limit_media(import: &mut ImportResult, limit: Option<usize>) -> usize {
+    let Some(limit) = limit else {
+        return 0;
+    };
+    let total = import.media.len();
+    import.media.truncate(limit);
+    total - import.media.len()
+}
+
+/// The inputs to create collections with the given names.
+fn collection_inputs(names: Vec<String>) -> Vec<CreateOrUpdateCollectionInput> {
+    names
+        .into_iter()
+        .map(|name| CreateOrUpdateCollectionInput {
+            name,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Rename the collections of an import according to the mapping in the input.
+fn map_collections(import: &mut ImportResult, mapping: &[ImportCollectionMapping]) {
+    let map_name = |name: &mut String| {
+        if let Some(m) = mapping
+            .iter()
+            .find(|m| m.source.trim().eq_ignore_ascii_case(name.trim()))
+        {
+            *name = m.target.trim().to_owned();
+        }
+    };
+    for col in import.collections.iter_mut() {
+        map_name(&mut col.name);
+    }
+    for item in import.media.iter_mut() {
+        item.collections.iter_mut().for_each(map_name);
+        item.collections = item.collections.drain(..).unique().collect();
+    }
+}
+
+/// Make the name of a collection from the source usable in Ryot. Text in any
+/// script is kept, path separators are replaced with dashes, and emoji and
+/// control characters are removed.
+fn sanitize_collection_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .filter_map(|c| match c {
+            '/' | '\\' | '|' => Some('-'),
+            // DEV: Emoji, the joiners and variation selectors used to combine them,
+            // and other pictographs
+            '\u{200D}' | '\u{FE0E}' | '\u{FE0F}' => None,
+            '\u{2600}'..='\u{27BF}' | '\u{2B00}'..='\u{2BFF}' | '\u{1F000}'..='\u{1FAFF}' => None,
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect::<String>();
+    name.split_whitespace()
+        .join(" ")
+        .chars()
+        .take(MAX_COLLECTION_NAME_LENGTH)
+        .collect::<String>()
+        .trim_end()
+        .to_owned()
+}
+
+/// Sanitize the names of all the collections of an import. Names that are empty
+/// once sanitized are given a numbered name. Returns the collections that were
+/// renamed.
+fn sanitize_collections(import: &mut ImportResult) -> Vec<ImportRenamedCollection> {
+    let names = import
+        .collections
+        .iter()
+        .map(|c| c.name.clone())
+        .chain(import.media.iter().flat_map(|m| m.collections.clone()))
+        .chain(import.people.iter().flat_map(|p| p.collections.clone()))
+        .unique()
+        .collect_vec();
+    let mut renamed = vec![];
+    let mut unnamed = 0;
+    for source in names {
+        let mut target = sanitize_collection_name(&source);
+        if target.is_empty() {
+            unnamed += 1;
+            target = format!("{UNNAMED_COLLECTION} {unnamed}");
+        }
+        if target != source {
+            renamed.push(ImportRenamedCollection { source, target });
+        }
     }
+    if renamed.is_empty() {
+        return renamed;
+    }
+    let map_name = |name: &mut String| {
+        if let Some(r) = renamed.iter().find(|r| &r.source == name) {
+            *name = r.target.clone();
+        }
+    };
+    for col in import.collections.iter_mut() {
+        map_name(&mut col.name);
+    }
+    for item in import.media.iter_mut() {
+        item.collections.iter_mut().for_each(map_name);
+        item.collections = item.collections.drain(..).unique().collect();
+    }
+    for item in import.people.iter_mut() {
+        item.collections.iter_mut().for_each(map_name);
+        item.collections = item.collections.drain(..).unique().collect();
+    }
+    renamed
+}
+
+/// The name used to find collections that only differ in case or punctuation.
+fn normalize_collection_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Whether an existing review is the same as the one being imported, including
+/// the episode or chapter that it was posted for. The rating of the input is in
+/// the scale of the user, while the stored one is always out of 100.
+fn is_same_review(
+    existing: &review::Model,
+    input: &PostReviewInput,
+    review_scale: &UserReviewScale,
+) -> bool {
+    let rating = input.rating.map(|r| match review_scale {
+        UserReviewScale::OutOfFive => r * dec!(20),
+        UserReviewScale::OutOfHundred => r,
+    });
+    existing.rating == rating
+        && existing.text == input.text
+        && existing
+            .show_extra_information
+            .as_ref()
+            .map(|s| (s.season, s.episode))
+            == input.show_season_number.zip(input.show_episode_number)
+        && existing
+            .podcast_extra_information
+            .as_ref()
+            .map(|p| p.episode)
+            == input.podcast_episode_number
+        && existing
+            .anime_extra_information
+            .as_ref()
+            .and_then(|a| a.episode)
+            == input.anime_episode_number
+        && existing
+            .manga_extra_information
+            .as_ref()
+            .and_then(|m| m.chapter)
+            == input.manga_chapter_number
+}
+
+/// Whether an existing seen entry is the same as the one being imported.
+fn is_same_seen(
+    existing: &seen::Model,
+    seen: &ImportOrExportMediaItemSeen,
+    timezone: Option<Tz>,
+) -> bool {
+    existing.finished_on == seen.ended_on.map(|d| seen_date(d, timezone))
+        && existing
+            .show_extra_information
+            .as_ref()
+            .map(|s| (s.season, s.episode))
+            == seen.show_season_number.zip(seen.show_episode_number)
+        && existing
+            .podcast_extra_information
+            .as_ref()
+            .map(|p| p.episode)
+            == seen.podcast_episode_number
+        && existing
+            .anime_extra_information
+            .as_ref()
+            .and_then(|a| a.episode)
+            == seen.anime_episode_number
+        && existing
+            .manga_extra_information
+            .as_ref()
+            .and_then(|m| m.chapter)
+            == seen.manga_chapter_number
 }
 
 fn convert_review_into_input(
     review: &ImportOrExportItemRating,
     preferences: &UserPreferences,
+    default_visibility: Option<Visibility>,
     metadata_id: Option<i32>,
     person_id: Option<i32>,
 ) -> Option<PostReviewInput> {
-    if review.review.is_none() && review.rating.is_none() {
+    // DEV: Sources often export a rating with an empty review, which should not
+    // be saved as a blank review
+    let text = review
+        .review
+        .as_ref()
+        .and_then(|r| r.text.clone())
+        .filter(|t| !t.trim().is_empty());
+    if text.is_none() && review.rating.is_none() {
         tracing::debug!("Skipping review since it has no content");
         return None;
     }
+    let rating = review.rating.map(|rating| {
+        let clamped = rating.clamp(dec!(0), dec!(100));
+        if clamped != rating {
+            tracing::warn!("Rating {rating} is out of range, clamping it to {clamped}");
+        }
+        clamped
+    });
     let rating = match preferences.general.review_scale {
-        UserReviewScale::OutOfFive => review.rating.map(|rating| rating / dec!(20)),
-        UserReviewScale::OutOfHundred => review.rating,
+        UserReviewScale::OutOfFive => rating.map(|rating| rating / dec!(20)),
+        UserReviewScale::OutOfHundred => rating,
     };
-    let text = review.review.clone().and_then(|r| r.text);
-    let spoiler = review.review.clone().map(|r| r.spoiler.unwrap_or(false));
-    let date = review.review.clone().map(|r| r.date);
+    let details = review.review.as_ref();
     Some(PostReviewInput {
         rating,
+        spoiler: text
+            .as_ref()
+            .and(details.map(|r| r.spoiler.unwrap_or(false))),
+        visibility: details.and_then(|r| r.visibility).or(default_visibility),
+        date: details.and_then(|r| r.date),
         text,
-        spoiler,
-        visibility: review.review.clone().and_then(|r| r.visibility),
-        date: date.flatten(),
         metadata_id,
         person_id,
         show_season_number: review.show_season_number,
         show_episode_number: review.show_episode_number,
         podcast_episode_number: review.podcast_episode_number,
+        anime_episode_number: review.anime_episode_number,
         manga_chapter_number: review.manga_chapter_number,
         ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Error;
+    use chrono::Utc;
+    use database::Visibility;
+    use rust_decimal_macros::dec;
+
+    use super::{
+        convert_review_into_input, is_same_review, is_transient_error, normalize_rating,
+        ImportCommitCache, ImportCursor, ImportRatingScale, ImportRetryItem, ImportedItems,
+    };
+    use crate::{
+        entities::review,
+        models::media::{
+            ImportOrExportItemIdentifier, ImportOrExportItemRating, ImportOrExportMediaItem,
+            ImportOrExportMediaItemSeen,
+        },
+        users::{UserPreferences, UserReviewScale},
+    };
+
+    fn stored_review(rating: rust_decimal::Decimal) -> review::Model {
+        review::Model {
+            id: 1,
+            posted_on: Utc::now(),
+            rating: Some(rating),
+            text: None,
+            visibility: Visibility::Public,
+            spoiler: false,
+            user_id: 1,
+            metadata_id: Some(1),
+            person_id: None,
+            metadata_group_id: None,
+            collection_id: None,
+            show_extra_information: None,
+            podcast_extra_information: None,
+            anime_extra_information: None,
+            manga_extra_information: None,
+            comments: vec![],
+            import_report_id: None,
+        }
+    }
+
+    #[test]
+    fn test_normalize_rating_boundaries() {
+        for scale in [
+            ImportRatingScale::Three,
+            ImportRatingScale::Five,
+            ImportRatingScale::Ten,
+            ImportRatingScale::Twenty,
+            ImportRatingScale::Hundred,
+        ] {
+            assert_eq!(scale.normalize(dec!(0)), dec!(0));
+            assert_eq!(scale.normalize(scale.max()), dec!(100));
+        }
+        assert_eq!(
+            normalize_rating(
+                dec!(100),
+                ImportRatingScale::Hundred,
+                ImportRatingScale::Five
+            ),
+            dec!(5)
+        );
+    }
+
+    #[test]
+    fn test_normalize_rating_scales() {
+        assert_eq!(ImportRatingScale::Five.normalize(dec!(4)), dec!(80));
+        assert_eq!(ImportRatingScale::Five.normalize(dec!(3.5)), dec!(70));
+        assert_eq!(ImportRatingScale::Ten.normalize(dec!(7)), dec!(70));
+        assert_eq!(ImportRatingScale::Twenty.normalize(dec!(15)), dec!(75));
+        assert_eq!(ImportRatingScale::Hundred.normalize(dec!(42)), dec!(42));
+        assert_eq!(
+            normalize_rating(dec!(8), ImportRatingScale::Ten, ImportRatingScale::Five),
+            dec!(4)
+        );
+    }
+
+    #[test]
+    fn test_normalize_rating_rounding() {
+        assert_eq!(ImportRatingScale::Three.normalize(dec!(1)), dec!(33.33));
+        assert_eq!(ImportRatingScale::Three.normalize(dec!(2)), dec!(66.67));
+        assert_eq!(
+            normalize_rating(dec!(1), ImportRatingScale::Twenty, ImportRatingScale::Three),
+            dec!(0.15)
+        );
+    }
+
+    #[test]
+    fn test_normalize_rating_out_of_scale() {
+        assert_eq!(ImportRatingScale::Five.normalize(dec!(6)), dec!(100));
+        assert_eq!(ImportRatingScale::Ten.normalize(dec!(-1)), dec!(0));
+    }
+
+    #[test]
+    fn test_is_same_review_out_of_five() {
+        let mut preferences = UserPreferences::default();
+        preferences.general.review_scale = UserReviewScale::OutOfFive;
+        let rating = ImportOrExportItemRating {
+            rating: Some(dec!(80)),
+            ..Default::default()
+        };
+        let input = convert_review_into_input(&rating, &preferences, None, Some(1), None).unwrap();
+        assert_eq!(input.rating, Some(dec!(4)));
+        let scale = &preferences.general.review_scale;
+        assert!(is_same_review(&stored_review(dec!(80)), &input, scale));
+        assert!(!is_same_review(&stored_review(dec!(4)), &input, scale));
+        assert!(!is_same_review(&stored_review(dec!(60)), &input, scale));
+    }
+
+    #[test]
+    fn test_is_same_review_out_of_hundred() {
+        let preferences = UserPreferences::default();
+        let rating = ImportOrExportItemRating {
+            rating: Some(dec!(66.67)),
+            ..Default::default()
+        };
+        let input = convert_review_into_input(&rating, &preferences, None, Some(1), None).unwrap();
+        let scale = &preferences.general.review_scale;
+        assert!(is_same_review(&stored_review(dec!(66.67)), &input, scale));
+    }
+
+    #[test]
+    fn test_retry_item_keeps_identifier() {
+        let item = ImportOrExportMediaItem {
+            source_id: "The Hobbit".to_owned(),
+            lot: database::MetadataLot::Book,
+            source: database::MediaSource::Openlibrary,
+            identifier: "".to_owned(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier: "OL27482W".to_owned(),
+                title: "The Hobbit".to_owned(),
+            }),
+            seen_history: vec![ImportOrExportMediaItemSeen::default()],
+            reviews: vec![],
+            collections: vec!["Fantasy".to_owned()],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        };
+        let retried = ImportRetryItem::new(&item).unwrap().item().unwrap();
+        assert_eq!(retried.source_id, item.source_id);
+        assert_eq!(retried.seen_history.len(), 1);
+        assert_eq!(retried.collections, item.collections);
+        assert!(matches!(
+            retried.internal_identifier,
+            Some(ImportOrExportItemIdentifier::NeedsDetails { identifier, .. }) if identifier == "OL27482W"
+        ));
+    }
+
+    #[test]
+    fn test_is_transient_error() {
+        assert!(is_transient_error(&Error::new("429 Too Many Requests")));
+        assert!(is_transient_error(&Error::new("Request timed out")));
+        assert!(!is_transient_error(&Error::new("404 Not Found")));
+        assert!(!is_transient_error(&Error::new(
+            "duplicate key value violates unique constraint"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_commit_cache_commits_duplicates_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::models::media::PartialMetadataWithoutId;
+
+        let cache = ImportCommitCache::default();
+        let partial = PartialMetadataWithoutId {
+            identifier: "OL27482W".to_owned(),
+            title: "The Hobbit".to_owned(),
+            image: None,
+            lot: database::MetadataLot::Book,
+            source: database::MediaSource::Openlibrary,
+        };
+        let commits = AtomicUsize::new(0);
+        let commit = || async {
+            let cell = cache.cell(&partial);
+            cell.get_or_try_init(|| async {
+                commits.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                Ok::<_, ()>(1)
+            })
+            .await
+            .copied()
+        };
+        let (first, second) = tokio::join!(commit(), commit());
+        assert_eq!((first, second), (Ok(1), Ok(1)));
+        assert_eq!(commits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_cursor_marks_processed_items() {
+        let completed = [true, true, false, true, false, true];
+        let results = ImportedItems {
+            processed: 4,
+            media_imported: 3,
+            ..Default::default()
+        };
+        let cursor = ImportCursor::new("hash", &completed, &results);
+        assert_eq!(cursor.position, 2);
+        assert_eq!(cursor.completed, vec![3, 5]);
+        let mut resumed = vec![false; completed.len()];
+        cursor.mark_completed(&mut resumed);
+        assert_eq!(resumed, completed);
+        assert_eq!(cursor.results, results);
+    }
+}
@@ -6,13 +6,12 @@ use csv::Reader;
 use database::{MediaSource, MetadataLot};
 use rs_utils::convert_naive_to_utc;
 use rust_decimal::Decimal;
-use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     importer::{
         DeployMovaryImportInput, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
-        ImportOrExportMediaItem, ImportResult,
+        ImportOrExportMediaItem, ImportRatingScale, ImportResult,
     },
     miscellaneous::DefaultCollection,
     models::media::{
@@ -20,6 +19,8 @@ use crate::{
     },
 };
 
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Ten;
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Common {
@@ -41,11 +42,31 @@ struct History {
     #[serde(flatten)]
     common: Common,
     watched_at: NaiveDate,
+    /// The number of times the movie was watched on that date. Older exports do
+    /// not have this column.
+    #[serde(default)]
+    plays: Option<usize>,
     comment: Option<String>,
 }
 
+fn find_movie(
+    media: &mut [ImportOrExportMediaItem],
+    tmdb_id: i32,
+) -> Option<&mut ImportOrExportMediaItem> {
+    let tmdb_id = tmdb_id.to_string();
+    media.iter_mut().find(|m| {
+        matches!(
+            &m.internal_identifier,
+            Some(ImportOrExportItemIdentifier::NeedsDetails { identifier, .. }) if *identifier == tmdb_id
+        )
+    })
+}
+
 pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
-    let ratings = fs::read_to_string(&input.ratings)?;
+    let ratings = match &input.ratings {
+        Some(ratings) => fs::read_to_string(ratings)?,
+        None => String::new(),
+    };
     let history = fs::read_to_string(&input.history)?;
     let watchlist = fs::read_to_string(&input.watchlist)?;
     let lot = MetadataLot::Movie;
@@ -57,12 +78,12 @@ pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
         let record: Rating = match result {
             Ok(r) => r,
             Err(e) => {
-                failed_items.push(ImportFailedItem {
-                    lot: Some(lot),
-                    step: ImportFailStep::InputTransformation,
-                    identifier: idx.to_string(),
-                    error: Some(format!("Ratings file: {:#?}", e)),
-                });
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(format!("Ratings file: {:#?}", e)),
+                ));
                 continue;
             }
         };
@@ -77,12 +98,16 @@ pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
             identifier: "".to_string(),
             seen_history: vec![],
             reviews: vec![ImportOrExportItemRating {
-                // DEV: Rates items out of 10
-                rating: Some(record.user_rating.saturating_mul(dec!(10))),
+                rating: Some(RATING_SCALE.normalize(record.user_rating)),
                 ..Default::default()
             }],
             collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
             monitored: None,
+            matched_by_title: false,
+            status: None,
         })
     }
     let mut watchlist_reader = Reader::from_reader(watchlist.as_bytes());
@@ -90,12 +115,12 @@ pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
         let record: Common = match result {
             Ok(r) => r,
             Err(e) => {
-                failed_items.push(ImportFailedItem {
-                    lot: Some(lot),
-                    step: ImportFailStep::InputTransformation,
-                    identifier: idx.to_string(),
-                    error: Some(format!("Watchlist file: {:#?}", e)),
-                });
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(format!("Watchlist file: {:#?}", e)),
+                ));
                 continue;
             }
         };
@@ -111,7 +136,12 @@ pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
             seen_history: vec![],
             reviews: vec![],
             collections: vec![DefaultCollection::Watchlist.to_string()],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
             monitored: None,
+            matched_by_title: false,
+            status: None,
         })
     }
     let mut history_reader = Reader::from_reader(history.as_bytes());
@@ -119,33 +149,35 @@ pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
         let record: History = match result {
             Ok(r) => r,
             Err(e) => {
-                failed_items.push(ImportFailedItem {
-                    lot: Some(lot),
-                    step: ImportFailStep::InputTransformation,
-                    identifier: idx.to_string(),
-                    error: Some(format!("History file: {:#?}", e)),
-                });
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(format!("History file: {:#?}", e)),
+                ));
                 continue;
             }
         };
         let watched_at = Some(convert_naive_to_utc(record.watched_at));
-        let seen_item = ImportOrExportMediaItemSeen {
-            started_on: None,
-            ended_on: watched_at,
-            ..Default::default()
-        };
+        // DEV: Every play is a separate entry in the seen history
+        let seen_items = vec![
+            ImportOrExportMediaItemSeen {
+                started_on: None,
+                ended_on: watched_at,
+                ..Default::default()
+            };
+            record.plays.unwrap_or(1).max(1)
+        ];
         let review = record.comment.map(|c| ImportOrExportItemReview {
             spoiler: Some(false),
             text: Some(c),
             date: watched_at,
             visibility: None,
         });
-        if let Some(media) = media
-            .iter_mut()
-            .find(|m| m.source_id == record.common.title)
-        {
+        if let Some(media) = find_movie(&mut media, record.common.tmdb_id) {
             if review.is_some() {
-                if let Some(rating) = media.reviews.last_mut() {
+                // DEV: The comments of other plays must not be overwritten
+                if let Some(rating) = media.reviews.iter_mut().find(|r| r.review.is_none()) {
                     rating.review = review;
                 } else {
                     media.reviews.push(ImportOrExportItemRating {
@@ -154,7 +186,7 @@ pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
                     })
                 }
             }
-            media.seen_history.push(seen_item);
+            media.seen_history.extend(seen_items);
         } else {
             let mut reviews = vec![];
             if review.is_some() {
@@ -172,10 +204,15 @@ pub async fn import(input: DeployMovaryImportInput) -> Result<ImportResult> {
                     identifier: record.common.tmdb_id.to_string(),
                     title: record.common.title,
                 }),
-                seen_history: vec![seen_item],
+                seen_history: seen_items,
                 reviews,
                 collections: vec![],
+                added_on: None,
+                tags: vec![],
+                images: vec![],
                 monitored: None,
+                matched_by_title: false,
+                status: None,
             })
         }
     }
@@ -0,0 +1,154 @@
+use std::fs;
+
+use async_graphql::Result;
+use chrono::{NaiveDate, NaiveDateTime};
+use csv::Reader;
+use database::{MediaSource, MetadataLot};
+use itertools::Itertools;
+use rs_utils::convert_naive_to_utc;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sea_orm::prelude::DateTimeUtc;
+use serde::Deserialize;
+
+use crate::{
+    importer::{
+        mark_current_seen_in_progress, DeployOpenlibraryLogImportInput, ImportFailStep,
+        ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportRatingScale,
+        ImportResult,
+    },
+    miscellaneous::DefaultCollection,
+    models::media::{ImportOrExportItemRating, ImportOrExportMediaItemSeen},
+};
+
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Five;
+
+#[derive(Debug, Deserialize)]
+struct Entry {
+    #[serde(rename = "Work ID")]
+    work_id: String,
+    #[serde(rename = "Title", default)]
+    title: Option<String>,
+    #[serde(rename = "Bookshelf")]
+    bookshelf: String,
+    #[serde(rename = "My Ratings", default)]
+    rating: Option<Decimal>,
+    #[serde(rename = "Date Added", default)]
+    date_added: Option<String>,
+}
+
+// DEV: The id is also accepted as the full key of the work, for eg: "/works/OL45804W"
+fn work_key(id: &str) -> Option<String> {
+    let key = id.trim().rsplit('/').next()?;
+    (key.starts_with("OL") && key.ends_with('W')).then(|| key.to_owned())
+}
+
+fn parse_date(date: &str) -> Option<DateTimeUtc> {
+    let date = date.trim();
+    ["%Y-%m-%d %H:%M:%S", "%Y/%m/%d, %H:%M:%S"]
+        .into_iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(date, f).ok())
+        .map(|d| d.date())
+        .or_else(|| {
+            ["%Y-%m-%d", "%Y/%m/%d"]
+                .into_iter()
+                .find_map(|f| NaiveDate::parse_from_str(date, f).ok())
+        })
+        .map(convert_naive_to_utc)
+}
+
+pub async fn import(input: DeployOpenlibraryLogImportInput) -> Result<ImportResult> {
+    let lot = MetadataLot::Book;
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let export = fs::read_to_string(input.csv_path)?;
+    let entries = Reader::from_reader(export.as_bytes())
+        .deserialize::<Entry>()
+        .collect_vec();
+    for (idx, result) in entries.into_iter().enumerate() {
+        let entry = match result {
+            Ok(e) => e,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(e.to_string()),
+                ));
+                continue;
+            }
+        };
+        let title = entry
+            .title
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or_else(|| entry.work_id.clone());
+        let Some(identifier) = work_key(&entry.work_id) else {
+            failed_items.push(ImportFailedItem::new(
+                Some(lot),
+                ImportFailStep::InputTransformation,
+                title,
+                Some(format!("Invalid Openlibrary work id: {}", entry.work_id)),
+            ));
+            continue;
+        };
+        let added_on = entry.date_added.as_deref().and_then(parse_date);
+        let mut seen_history = vec![];
+        let mut collections = vec![];
+        match entry.bookshelf.trim() {
+            "Want to Read" => collections.push(DefaultCollection::Watchlist.to_string()),
+            "Currently Reading" => {
+                collections.push(DefaultCollection::InProgress.to_string());
+                mark_current_seen_in_progress(&mut seen_history);
+            }
+            "Already Read" => seen_history.push(ImportOrExportMediaItemSeen {
+                ended_on: added_on,
+                ..Default::default()
+            }),
+            shelf => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    title,
+                    Some(format!("Unknown bookshelf: {shelf}")),
+                ));
+                continue;
+            }
+        }
+        let reviews = entry
+            .rating
+            .filter(|r| *r > dec!(0))
+            .map(|r| ImportOrExportItemRating {
+                rating: Some(RATING_SCALE.normalize(r)),
+                ..Default::default()
+            })
+            .into_iter()
+            .collect();
+        media.push(ImportOrExportMediaItem {
+            source_id: title.clone(),
+            lot,
+            source: MediaSource::Openlibrary,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title,
+            }),
+            seen_history,
+            reviews,
+            collections,
+            added_on,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        });
+    }
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
@@ -0,0 +1,178 @@
+use std::fs;
+
+use async_graphql::Result;
+use database::{MediaSource, MetadataLot};
+use serde::Deserialize;
+
+use crate::{
+    importer::{
+        get_import_candidates, DeployPodcastOpmlImportInput, ImportFailStep, ImportFailedItem,
+        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+    },
+    miscellaneous::resolver::RateLimited,
+    providers::itunes::ITunesService,
+    traits::MediaProvider,
+};
+
+const COLLECTION: &str = "Subscribed Podcasts";
+
+#[derive(Debug, Deserialize)]
+struct Opml {
+    body: Body,
+}
+
+#[derive(Debug, Deserialize)]
+struct Body {
+    #[serde(rename = "outline", default)]
+    outlines: Vec<Outline>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Outline {
+    text: Option<String>,
+    title: Option<String>,
+    #[serde(rename = "xmlUrl")]
+    xml_url: Option<String>,
+    #[serde(rename = "htmlUrl")]
+    html_url: Option<String>,
+    #[serde(rename = "outline", default)]
+    outlines: Vec<Outline>,
+}
+
+/// A podcast from the subscriptions, with the title that is used to search it.
+#[derive(Debug)]
+struct Subscription {
+    title: String,
+    feed_url: String,
+    html_url: Option<String>,
+}
+
+/// Collect the podcasts from the outlines. Outlines containing other outlines are
+/// folders (for eg: categories) and are flattened.
+fn collect_subscriptions(
+    outlines: Vec<Outline>,
+    subscriptions: &mut Vec<Subscription>,
+    failed_items: &mut Vec<ImportFailedItem>,
+) {
+    for outline in outlines {
+        if !outline.outlines.is_empty() {
+            collect_subscriptions(outline.outlines, subscriptions, failed_items);
+            continue;
+        }
+        let title = outline
+            .title
+            .or(outline.text)
+            .filter(|t| !t.trim().is_empty());
+        let feed_url = outline.xml_url.filter(|u| !u.trim().is_empty());
+        match (title, feed_url) {
+            (Some(title), Some(feed_url)) => subscriptions.push(Subscription {
+                title,
+                feed_url,
+                html_url: outline.html_url,
+            }),
+            (title, feed_url) => failed_items.push(ImportFailedItem::new(
+                Some(MetadataLot::Podcast),
+                ImportFailStep::InputTransformation,
+                title.or(feed_url).unwrap_or_default(),
+                Some("Outline does not have a title and a feed URL".to_owned()),
+            )),
+        }
+    }
+}
+
+// DEV: Apps that use iTunes link to the page of the podcast, which ends with its
+// id, for eg: "https://podcasts.apple.com/us/podcast/the-daily/id1200361736"
+fn itunes_id_from_url(url: &str) -> Option<String> {
+    if !url.contains("apple.com") {
+        return None;
+    }
+    url.split(['/', '?'])
+        .filter_map(|p| p.strip_prefix("id"))
+        .find(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+        .map(String::from)
+}
+
+pub async fn import(
+    input: DeployPodcastOpmlImportInput,
+    itunes_service: Option<&RateLimited<ITunesService>>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::Podcast;
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let opml: Opml = serde_xml_rs::from_str(&fs::read_to_string(&input.opml)?)?;
+    let mut subscriptions = vec![];
+    collect_subscriptions(opml.body.outlines, &mut subscriptions, &mut failed_items);
+    let total = subscriptions.len();
+    for (idx, subscription) in subscriptions.into_iter().enumerate() {
+        tracing::debug!(
+            "Getting details for {title:?} ({idx}/{total})",
+            title = subscription.title
+        );
+        let found = match (
+            subscription
+                .html_url
+                .as_deref()
+                .and_then(itunes_id_from_url),
+            itunes_service,
+        ) {
+            (Some(id), _) => Some(id),
+            (None, Some(itunes_service)) => {
+                itunes_service
+                    .request(|s| s.id_from_feed_url(&subscription.title, &subscription.feed_url))
+                    .await
+            }
+            // DEV: Previews do not look up the feed, so the podcast only has its title
+            (None, None) => Some(String::new()),
+        };
+        let Some(identifier) = found else {
+            let candidates = match itunes_service {
+                Some(itunes_service) => itunes_service
+                    .request(|s| s.metadata_search(&subscription.title, None, true))
+                    .await
+                    .map(|s| get_import_candidates(&s.items))
+                    .unwrap_or_default(),
+                None => vec![],
+            };
+            failed_items.push(ImportFailedItem {
+                candidates,
+                ..ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::MediaDetailsFromProvider,
+                    subscription.title,
+                    Some(format!(
+                        "No podcast found on iTunes with the feed URL: {}",
+                        subscription.feed_url
+                    )),
+                )
+            });
+            continue;
+        };
+        media.push(ImportOrExportMediaItem {
+            source_id: subscription.feed_url,
+            lot,
+            source: MediaSource::Itunes,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title: subscription.title,
+            }),
+            seen_history: vec![],
+            reviews: vec![],
+            collections: vec![COLLECTION.to_owned()],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        });
+    }
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use async_graphql::Result;
+use chrono::DateTime;
+use database::{MediaSource, MetadataLot};
+use http_types::mime;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use surf::{http::headers::ACCEPT, Client};
+
+use crate::{
+    importer::{
+        DeployPlexImportInput, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportResult,
+    },
+    miscellaneous::resolver::RateLimited,
+    models::media::ImportOrExportMediaItemSeen,
+    providers::tmdb::NonMediaTmdbService,
+    utils::get_base_http_client,
+};
+
+const PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct Guid {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directory {
+    key: String,
+    title: String,
+    #[serde(rename = "type")]
+    section_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Metadata {
+    rating_key: Option<String>,
+    title: Option<String>,
+    #[serde(rename = "type")]
+    item_type: String,
+    #[serde(rename = "Guid", default)]
+    guids: Vec<Guid>,
+    viewed_at: Option<i64>,
+    grandparent_key: Option<String>,
+    grandparent_title: Option<String>,
+    parent_index: Option<i32>,
+    index: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MediaContainer {
+    #[serde(rename = "Directory", default)]
+    directories: Vec<Directory>,
+    #[serde(rename = "Metadata", default)]
+    metadata: Vec<Metadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    #[serde(rename = "MediaContainer")]
+    media_container: MediaContainer,
+}
+
+async fn get<T: DeserializeOwned>(
+    client: &Client,
+    path: &str,
+    query: serde_json::Value,
+) -> Result<T> {
+    let data = client
+        .get(path)
+        .query(&query)
+        .unwrap()
+        .await
+        .map_err(|e| anyhow!(e))?
+        .body_json::<T>()
+        .await
+        .map_err(|e| anyhow!(e))?;
+    Ok(data)
+}
+
+/// Get the TMDB identifier of a library item from the external ids embedded in
+/// its GUIDs.
+async fn get_tmdb_identifier(
+    guids: &[Guid],
+    lot: MetadataLot,
+    tmdb_service: Option<&RateLimited<NonMediaTmdbService>>,
+) -> std::result::Result<String, String> {
+    let mut external_ids = vec![];
+    for guid in guids {
+        match guid.id.split_once("://") {
+            Some(("tmdb", id)) => return Ok(id.to_owned()),
+            Some(("imdb", id)) => external_ids.push((id, "imdb_id")),
+            Some(("tvdb", id)) => external_ids.push((id, "tvdb_id")),
+            _ => {}
+        }
+    }
+    if external_ids.is_empty() {
+        return Err("Item does not have a TMDB, IMDb or TVDB id".to_owned());
+    }
+    // DEV: Previews do not look up the external ids, so the item only has its title
+    let Some(tmdb_service) = tmdb_service else {
+        return Ok(String::new());
+    };
+    let mut error = String::new();
+    for (id, source) in external_ids {
+        match tmdb_service
+            .request(|s| s.find_by_external_id(id, source))
+            .await
+        {
+            Ok((found_lot, identifier)) if found_lot == lot => return Ok(identifier),
+            Ok(_) => error = format!("No {lot} found on TMDB for {source} {id}"),
+            Err(e) => error = e.to_string(),
+        }
+    }
+    Err(error)
+}
+
+pub async fn import(
+    input: DeployPlexImportInput,
+    tmdb_service: Option<&RateLimited<NonMediaTmdbService>>,
+) -> Result<ImportResult> {
+    let client = get_base_http_client(
+        &format!("{}/", input.base_url),
+        vec![
+            (ACCEPT, mime::JSON.to_string()),
+            ("X-Plex-Token".into(), input.token),
+        ],
+    );
+    let mut failed_items = vec![];
+
+    let sections: Response = get(&client, "library/sections", json!({})).await?;
+    let mut library_items = HashMap::new();
+    for section in sections.media_container.directories {
+        let lot = match section.section_type.as_str() {
+            "movie" => MetadataLot::Movie,
+            "show" => MetadataLot::Show,
+            _ => continue,
+        };
+        tracing::debug!("Getting items in library section {:?}", section.title);
+        let items: Response = get(
+            &client,
+            &format!("library/sections/{}/all", section.key),
+            json!({ "includeGuids": 1 }),
+        )
+        .await?;
+        for item in items.media_container.metadata {
+            if let Some(key) = item.rating_key.clone() {
+                library_items.insert(key, (lot, item));
+            }
+        }
+    }
+
+    let mut history = vec![];
+    loop {
+        let page: Response = get(
+            &client,
+            "status/sessions/history/all",
+            json!({
+                "X-Plex-Container-Start": history.len(),
+                "X-Plex-Container-Size": PAGE_SIZE,
+            }),
+        )
+        .await?;
+        let count = page.media_container.metadata.len();
+        history.extend(page.media_container.metadata);
+        if count < PAGE_SIZE {
+            break;
+        }
+    }
+    tracing::debug!("Got {} history entries from Plex", history.len());
+
+    let mut seen_items: HashMap<String, Vec<ImportOrExportMediaItemSeen>> = HashMap::new();
+    for entry in history {
+        let ended_on = entry.viewed_at.and_then(|v| DateTime::from_timestamp(v, 0));
+        let (key, seen) = match entry.item_type.as_str() {
+            "movie" => (
+                entry.rating_key,
+                ImportOrExportMediaItemSeen {
+                    ended_on,
+                    ..Default::default()
+                },
+            ),
+            "episode" => (
+                entry
+                    .grandparent_key
+                    .and_then(|k| k.rsplit('/').next().map(String::from)),
+                ImportOrExportMediaItemSeen {
+                    ended_on,
+                    show_season_number: entry.parent_index,
+                    show_episode_number: entry.index,
+                    ..Default::default()
+                },
+            ),
+            _ => continue,
+        };
+        let Some(key) = key else {
+            failed_items.push(ImportFailedItem::new(
+                None,
+                ImportFailStep::ItemDetailsFromSource,
+                entry.grandparent_title.or(entry.title).unwrap_or_default(),
+                Some("History entry is not linked to a library item".to_owned()),
+            ));
+            continue;
+        };
+        seen_items.entry(key).or_default().push(seen);
+    }
+
+    let total = seen_items.len();
+    let mut media = vec![];
+    for (idx, (key, seen_history)) in seen_items.into_iter().enumerate() {
+        let Some((lot, item)) = library_items.remove(&key) else {
+            failed_items.push(ImportFailedItem::new(
+                None,
+                ImportFailStep::ItemDetailsFromSource,
+                key,
+                Some("Item is no longer present in the library".to_owned()),
+            ));
+            continue;
+        };
+        let title = item.title.unwrap_or_default();
+        tracing::debug!("Getting details for {title:?} ({idx}/{total})");
+        let identifier = match get_tmdb_identifier(&item.guids, lot, tmdb_service).await {
+            Ok(i) => i,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::ItemDetailsFromSource,
+                    title,
+                    Some(e),
+                ));
+                continue;
+            }
+        };
+        media.push(ImportOrExportMediaItem {
+            source_id: title.clone(),
+            lot,
+            source: MediaSource::Tmdb,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title,
+            }),
+            seen_history,
+            reviews: vec![],
+            collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        });
+    }
+
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
@@ -0,0 +1,404 @@
+use std::{str::FromStr, sync::atomic::Ordering, time::Duration as StdDuration};
+
+use apalis::prelude::{Job, JobId, JobState, Storage};
+use async_graphql::{Error, Result, SimpleObject};
+use chrono::{Duration, Utc};
+use database::{ImportSource, UserLot};
+use nanoid::nanoid;
+use sea_orm::{
+    prelude::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder,
+};
+
+use crate::{
+    background::ImportJob,
+    entities::{import_report, prelude::ImportReport},
+    importer::{
+        kodi, media_tracker, parse_timezone, report::validate_callback_url,
+        schedule::ensure_can_be_scheduled, DeployImportJobInput, ImporterService,
+    },
+    utils::user_by_id,
+};
+
+/// The time after which an import that was not started because the import
+/// worker is paused is tried again.
+const PAUSED_IMPORT_RETRY_DELAY: StdDuration = StdDuration::from_secs(60);
+
+/// The state of the background jobs that run imports, which are shared by all
+/// the users of the instance.
+#[derive(Debug, SimpleObject, Clone, Default)]
+pub struct ImportQueueStatus {
+    /// The number of imports that are waiting for a worker, including the ones
+    /// that are waiting to be retried.
+    pending: usize,
+    /// The number of imports that are being run.
+    running: usize,
+    /// The number of imports that have finished in the last day.
+    completed: usize,
+    /// Whether all the workers are running jobs, in which case new imports wait
+    /// until one of them is free.
+    workers_busy: bool,
+    /// Whether an admin has paused the import worker, in which case imports
+    /// wait until it is resumed.
+    worker_paused: bool,
+    /// The reports of the imports of the user that are waiting for a worker.
+    queued_reports: Vec<i32>,
+}
+
+#[derive(Debug, SimpleObject, Clone)]
+pub struct BulkImportResponse {
+    /// The id to list the reports of these imports with in `importReports`.
+    bulk_id: String,
+    /// The ids of the reports, in the same order as the inputs.
+    report_ids: Vec<String>,
+}
+
+impl ImporterService {
+    pub async fn set_import_worker_paused(&self, user_id: i32, paused: bool) -> Result<bool> {
+        let user = user_by_id(&self.media_service.db, user_id).await?;
+        if user.lot != UserLot::Admin {
+            return Err(Error::new("Only admins can perform this operation."));
+        }
+        self.worker_paused.store(paused, Ordering::SeqCst);
+        tracing::info!(
+            "Import worker was {state} by user with id = {user_id}",
+            state = if paused { "paused" } else { "resumed" }
+        );
+        Ok(paused)
+    }
+
+    pub fn is_worker_paused(&self) -> bool {
+        self.worker_paused.load(Ordering::SeqCst)
+    }
+
+    /// Queue an import that was picked up while the worker is paused again, to
+    /// be tried later. The job that picked it up is finished, and the new one
+    /// continues with the same report since the input keeps its job id. The
+    /// report is started again so that a long pause does not make it time out.
+    pub async fn postpone_import_job(
+        &self,
+        user_id: i32,
+        input: Box<DeployImportJobInput>,
+    ) -> Result<()> {
+        let run_at = Utc::now() + Duration::from_std(PAUSED_IMPORT_RETRY_DELAY).unwrap();
+        let job_id = input.job_id.clone();
+        let application_job_id = self
+            .perform_import_job
+            .clone()
+            .schedule(ImportJob::ImportFromExternalSource(user_id, input), run_at)
+            .await?;
+        ImportReport::update_many()
+            .col_expr(
+                import_report::Column::ApplicationJobId,
+                Expr::value(application_job_id.to_string()),
+            )
+            .col_expr(import_report::Column::StartedOn, Expr::value(Utc::now()))
+            .filter(import_report::Column::UserId.eq(user_id))
+            .filter(import_report::Column::JobId.eq(job_id))
+            .filter(import_report::Column::FinishedOn.is_null())
+            .exec(&self.media_service.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn deploy_import_job(
+        &self,
+        user_id: i32,
+        mut input: DeployImportJobInput,
+    ) -> Result<String> {
+        self.validate_import_job(&mut input).await?;
+        if let Some(report_id) = self.import_with_same_key(user_id, &input).await? {
+            return Ok(report_id.to_string());
+        }
+        self.ensure_free_import_slots(user_id, 1).await?;
+        self.enqueue_import_job(user_id, input).await
+    }
+
+    /// Check the input of an import before it is deployed, so that it fails when
+    /// it is deployed instead of once it runs.
+    async fn validate_import_job(&self, input: &mut DeployImportJobInput) -> Result<()> {
+        if let Some(s) = input.media_tracker.as_mut() {
+            s.api_url = s.api_url.trim_end_matches('/').to_owned()
+        }
+        if let Some(s) = input.plex.as_mut() {
+            s.base_url = s.base_url.trim_end_matches('/').to_owned()
+        }
+        if let Some(s) = input.jellyfin.as_mut() {
+            s.base_url = s.base_url.trim_end_matches('/').to_owned()
+        }
+        if let Some(url) = input.callback_url.as_deref() {
+            validate_callback_url(url, &self.config.importer.callback_allowed_hosts).await?;
+        }
+        parse_timezone(input.timezone.as_deref())?;
+        if input.schedule.is_some() {
+            ensure_can_be_scheduled(input.source)?;
+        }
+        match input.source {
+            ImportSource::MediaTracker => {
+                if let Some(s) = input.media_tracker.as_ref() {
+                    media_tracker::validate(s).await?;
+                }
+            }
+            ImportSource::Kodi => {
+                if let Some(s) = input.kodi.as_ref() {
+                    kodi::validate(s).await?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The report of the import with the same idempotency key that has not
+    /// finished yet, which is returned instead of deploying the import again.
+    async fn import_with_same_key(
+        &self,
+        user_id: i32,
+        input: &DeployImportJobInput,
+    ) -> Result<Option<i32>> {
+        let Some(key) = input.idempotency_key.as_deref() else {
+            return Ok(None);
+        };
+        let existing = ImportReport::find()
+            .filter(import_report::Column::UserId.eq(user_id))
+            .filter(import_report::Column::IdempotencyKey.eq(key))
+            .filter(import_report::Column::FinishedOn.is_null())
+            .filter(import_report::Column::Success.is_null())
+            .one(&self.media_service.db)
+            .await?;
+        if let Some(report) = existing.as_ref() {
+            tracing::debug!(
+                "Import with the same key is running with id = {id}",
+                id = report.id
+            );
+        }
+        Ok(existing.map(|r| r.id))
+    }
+
+    /// Save the schedule of a validated import and queue it.
+    async fn enqueue_import_job(
+        &self,
+        user_id: i32,
+        mut input: DeployImportJobInput,
+    ) -> Result<String> {
+        input.job_id = Some(nanoid!());
+        if let Some(schedule) = input.schedule {
+            self.schedule_import(user_id, schedule, &input).await?;
+        }
+        let report_id = self.push_import_job(user_id, input).await?;
+        Ok(report_id.to_string())
+    }
+
+    /// Create the report of an import and queue the job that runs it.
+    pub async fn push_import_job(&self, user_id: i32, input: DeployImportJobInput) -> Result<i32> {
+        // DEV: The report is created before the job runs so that its id can be
+        // returned, so that an import deployed again with the same key finds it,
+        // and so that the job can check that it imports for the owner of the report.
+        // It does not have a progress until the job is picked up by a worker.
+        let report = import_report::ActiveModel {
+            user_id: ActiveValue::Set(user_id),
+            source: ActiveValue::Set(input.source),
+            job_id: ActiveValue::Set(input.job_id.clone()),
+            idempotency_key: ActiveValue::Set(input.idempotency_key.clone()),
+            bulk_id: ActiveValue::Set(input.bulk_id.clone()),
+            ..Default::default()
+        }
+        .insert(&self.media_service.db)
+        .await?;
+        let job = self
+            .perform_import_job
+            .clone()
+            .push(ImportJob::ImportFromExternalSource(
+                user_id,
+                Box::new(input),
+            ))
+            .await
+            .unwrap();
+        import_report::ActiveModel {
+            id: ActiveValue::Unchanged(report.id),
+            application_job_id: ActiveValue::Set(Some(job.to_string())),
+            ..Default::default()
+        }
+        .update(&self.media_service.db)
+        .await?;
+        Ok(report.id)
+    }
+
+    pub async fn deploy_bulk_import(
+        &self,
+        user_id: i32,
+        inputs: Vec<DeployImportJobInput>,
+    ) -> Result<BulkImportResponse> {
+        if inputs.is_empty() {
+            return Err(Error::new("At least one import is required"));
+        }
+        // DEV: All the inputs are checked before any of them is queued, so that an
+        // invalid one does not leave the others queued without a bulk id
+        let mut inputs = inputs;
+        for input in inputs.iter_mut() {
+            self.validate_import_job(input).await?;
+        }
+        self.ensure_free_import_slots(user_id, inputs.len()).await?;
+        let bulk_id = nanoid!();
+        let mut report_ids = vec![];
+        for input in inputs {
+            if let Some(report_id) = self.import_with_same_key(user_id, &input).await? {
+                report_ids.push(report_id.to_string());
+                continue;
+            }
+            let input = DeployImportJobInput {
+                bulk_id: Some(bulk_id.clone()),
+                ..input
+            };
+            report_ids.push(self.enqueue_import_job(user_id, input).await?);
+        }
+        Ok(BulkImportResponse {
+            bulk_id,
+            report_ids,
+        })
+    }
+
+    /// Whether the user can deploy this many imports without going over the
+    /// number of imports that can be waiting or running at the same time.
+    pub async fn has_free_import_slots(&self, user_id: i32, count: usize) -> Result<bool> {
+        let limit = self.config.importer.max_imports_per_user;
+        if limit == 0 {
+            return Ok(true);
+        }
+        let unfinished = ImportReport::find()
+            .filter(import_report::Column::UserId.eq(user_id))
+            .filter(import_report::Column::FinishedOn.is_null())
+            .count(&self.media_service.db)
+            .await?;
+        Ok(unfinished as usize + count <= limit)
+    }
+
+    async fn ensure_free_import_slots(&self, user_id: i32, count: usize) -> Result<()> {
+        if !self.has_free_import_slots(user_id, count).await? {
+            return Err(Error::new(format!(
+                "Only {limit} imports can be waiting or running at the same time, wait for the others to finish",
+                limit = self.config.importer.max_imports_per_user
+            )));
+        }
+        Ok(())
+    }
+
+    /// Mark the imports that did not finish in time as failed. Imports that are
+    /// still running are stopped, so that they do not keep changing the library.
+    pub async fn invalidate_import_jobs(&self) -> Result<()> {
+        let hours = self.config.importer.job_timeout_hours;
+        let timeout = Duration::try_hours(hours).unwrap();
+        let all_jobs = ImportReport::find()
+            .filter(import_report::Column::Success.is_null())
+            .all(&self.media_service.db)
+            .await?;
+        for job in all_jobs {
+            if Utc::now() - job.started_on <= timeout {
+                continue;
+            }
+            let was_running = self
+                .kill_application_job(job.application_job_id.as_deref())
+                .await;
+            if was_running {
+                tracing::warn!(
+                    "Import job with id = {id} timed out after {hours} hours, stopping it",
+                    id = job.id
+                );
+            } else {
+                tracing::debug!(
+                    "Invalidating import job with id = {id} which failed",
+                    id = job.id
+                );
+            }
+            // DEV: The report is finished so that it no longer counts as an import
+            // that is waiting or running
+            let mut job: import_report::ActiveModel = job.into();
+            job.success = ActiveValue::Set(Some(false));
+            job.finished_on = ActiveValue::Set(Some(Utc::now()));
+            if was_running {
+                job.was_cancelled = ActiveValue::Set(Some(true));
+            }
+            job.save(&self.media_service.db).await?;
+        }
+        Ok(())
+    }
+
+    /// Mark the background job of an import as killed so that it is not run
+    /// again, returning whether it was still pending or running.
+    async fn kill_application_job(&self, job_id: Option<&str>) -> bool {
+        let Some(job_id) = job_id.and_then(|id| JobId::from_str(id).ok()) else {
+            return false;
+        };
+        let storage = &self.perform_import_job;
+        let Ok(Some(mut job)) = storage.fetch_by_id(&job_id).await else {
+            return false;
+        };
+        if !matches!(
+            job.status(),
+            JobState::Pending | JobState::Running | JobState::Retry
+        ) {
+            return false;
+        }
+        job.set_status(JobState::Killed);
+        if let Err(e) = storage.update_by_id(&job_id, &job).await {
+            tracing::error!("Could not kill job with id = {job_id}: {e:?}");
+        }
+        true
+    }
+
+    pub async fn import_queue_status(&self, user_id: i32) -> Result<ImportQueueStatus> {
+        // DEV: The counts are read from the apalis storage since a report does not
+        // tell whether its job is still known to a worker, for eg: when it crashed
+        let pool = self.perform_import_job.pool();
+        let (pending, running, completed): (i64, i64, i64) = sqlx::query_as(
+            "SELECT
+                COUNT(1) FILTER (WHERE status IN ('Pending', 'Retry')),
+                COUNT(1) FILTER (WHERE status = 'Running'),
+                COUNT(1) FILTER (WHERE status IN ('Done', 'Failed', 'Killed') AND done_at >= ?)
+            FROM Jobs WHERE job_type = ?",
+        )
+        .bind((Utc::now() - Duration::try_days(1).unwrap()).timestamp())
+        .bind(ImportJob::NAME)
+        .fetch_one(pool)
+        .await?;
+        let pending_jobs: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM Jobs WHERE job_type = ? AND status IN ('Pending', 'Retry')",
+        )
+        .bind(ImportJob::NAME)
+        .fetch_all(pool)
+        .await?;
+        let queued_reports = ImportReport::find()
+            .filter(import_report::Column::UserId.eq(user_id))
+            .filter(import_report::Column::FinishedOn.is_null())
+            .filter(import_report::Column::ApplicationJobId.is_in(pending_jobs))
+            .order_by_asc(import_report::Column::StartedOn)
+            .all(&self.media_service.db)
+            .await?
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        Ok(ImportQueueStatus {
+            pending: pending.try_into()?,
+            running: running.try_into()?,
+            completed: completed.try_into()?,
+            workers_busy: running >= i64::from(self.config.importer.workers),
+            worker_paused: self.is_worker_paused(),
+            queued_reports,
+        })
+    }
+
+    pub async fn cancel_import(&self, user_id: i32, report_id: i32) -> Result<bool> {
+        let Some(report) = ImportReport::find_by_id(report_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .filter(import_report::Column::FinishedOn.is_null())
+            .one(&self.media_service.db)
+            .await?
+        else {
+            return Err(Error::new("Import report does not exist or has finished"));
+        };
+        let mut model: import_report::ActiveModel = report.into();
+        model.was_cancelled = ActiveValue::Set(Some(true));
+        model.update(&self.media_service.db).await?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,542 @@
+use std::{collections::HashSet, net::IpAddr, time::Duration as StdDuration};
+
+use async_graphql::{Error, Result, SimpleObject};
+use chrono::Utc;
+use database::ImportSource;
+use itertools::Itertools;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder,
+    QuerySelect, QueryTrait,
+};
+use serde::Serialize;
+use surf::Url;
+use tokio::net::lookup_host;
+
+use crate::{
+    entities::{
+        collection_to_entity, import_report, metadata,
+        prelude::{CollectionToEntity, ImportReport, Metadata, Review, Seen},
+        review, seen,
+    },
+    importer::{
+        find_by_exact_title, set_error_codes, ImportCursor, ImportFailedItem, ImportProgressEvent,
+        ImportProgressFinished, ImportResultResponse, ImporterService,
+    },
+    miscellaneous::resolver::CommitMetadataInput,
+    models::BackgroundJob,
+};
+
+/// The time after which a request to the callback URL of an import is abandoned.
+const CALLBACK_TIMEOUT: StdDuration = StdDuration::from_secs(10);
+/// The number of times the callback URL of an import is tried.
+const CALLBACK_ATTEMPTS: usize = 2;
+
+/// The body that is sent to the callback URL of an import once it finishes.
+#[derive(Debug, Serialize)]
+struct ImportCallbackPayload<'a> {
+    report_id: i32,
+    #[serde(flatten)]
+    details: &'a ImportResultResponse,
+}
+
+/// The result of checking the media items of an import against their providers.
+#[derive(Debug, SimpleObject, Clone, Default)]
+pub struct ImportReconcileResult {
+    /// The number of media items of the import that were checked.
+    checked: usize,
+    /// The number of media items that are no longer on their provider and were
+    /// replaced by the item with the same title.
+    reconciled: usize,
+    /// The media items that are no longer on their provider and could not be
+    /// replaced. The entries of the user still belong to the old items.
+    failed_items: Vec<ImportFailedItem>,
+}
+
+impl ImporterService {
+    pub async fn import_reports(
+        &self,
+        user_id: i32,
+        bulk_id: Option<String>,
+    ) -> Result<Vec<import_report::Model>> {
+        let reports = ImportReport::find()
+            .filter(import_report::Column::UserId.eq(user_id))
+            .apply_if(bulk_id, |query, v| {
+                query.filter(import_report::Column::BulkId.eq(v))
+            })
+            .order_by_desc(import_report::Column::StartedOn)
+            .all(&self.media_service.db)
+            .await
+            .unwrap();
+        Ok(reports)
+    }
+
+    pub async fn import_report(
+        &self,
+        user_id: i32,
+        report_id: i32,
+    ) -> Result<import_report::Model> {
+        ImportReport::find_by_id(report_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .one(&self.media_service.db)
+            .await?
+            .ok_or_else(|| Error::new("Import report does not exist"))
+    }
+
+    pub async fn delete_import_report(
+        &self,
+        user_id: i32,
+        report_id: i32,
+        delete_media: bool,
+    ) -> Result<bool> {
+        let Some(report) = ImportReport::find_by_id(report_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .one(&self.media_service.db)
+            .await?
+        else {
+            return Err(Error::new("Import report does not exist"));
+        };
+        if report.finished_on.is_none() {
+            return Err(Error::new("Import report has not finished yet"));
+        }
+        if delete_media {
+            let seen = Seen::delete_many()
+                .filter(seen::Column::UserId.eq(user_id))
+                .filter(seen::Column::ImportReportId.eq(report_id))
+                .exec(&self.media_service.db)
+                .await?;
+            let reviews = Review::delete_many()
+                .filter(review::Column::UserId.eq(user_id))
+                .filter(review::Column::ImportReportId.eq(report_id))
+                .exec(&self.media_service.db)
+                .await?;
+            let collections = CollectionToEntity::delete_many()
+                .filter(collection_to_entity::Column::ImportReportId.eq(report_id))
+                .exec(&self.media_service.db)
+                .await?;
+            tracing::debug!(
+                "Deleted {seen} seen items, {reviews} reviews and {collections} collection entries imported by report {report_id}",
+                seen = seen.rows_affected,
+                reviews = reviews.rows_affected,
+                collections = collections.rows_affected,
+            );
+        }
+        report.delete(&self.media_service.db).await?;
+        if delete_media {
+            self.media_service
+                .deploy_background_job(user_id, BackgroundJob::CalculateSummary)
+                .await
+                .ok();
+        }
+        Ok(true)
+    }
+
+    pub async fn reconcile_import_report(
+        &self,
+        user_id: i32,
+        report_id: i32,
+    ) -> Result<ImportReconcileResult> {
+        let db = &self.media_service.db;
+        let Some(report) = ImportReport::find_by_id(report_id)
+            .filter(import_report::Column::UserId.eq(user_id))
+            .one(db)
+            .await?
+        else {
+            return Err(Error::new("Import report does not exist"));
+        };
+        if report.finished_on.is_none() {
+            return Err(Error::new("Import report has not finished yet"));
+        }
+        let mut metadata_ids: HashSet<i32> = Seen::find()
+            .select_only()
+            .column(seen::Column::MetadataId)
+            .filter(seen::Column::UserId.eq(user_id))
+            .filter(seen::Column::ImportReportId.eq(report_id))
+            .into_tuple::<i32>()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect();
+        metadata_ids.extend(
+            Review::find()
+                .select_only()
+                .column(review::Column::MetadataId)
+                .filter(review::Column::UserId.eq(user_id))
+                .filter(review::Column::ImportReportId.eq(report_id))
+                .into_tuple::<Option<i32>>()
+                .all(db)
+                .await?
+                .into_iter()
+                .flatten(),
+        );
+        metadata_ids.extend(
+            CollectionToEntity::find()
+                .select_only()
+                .column(collection_to_entity::Column::MetadataId)
+                .filter(collection_to_entity::Column::ImportReportId.eq(report_id))
+                .into_tuple::<Option<i32>>()
+                .all(db)
+                .await?
+                .into_iter()
+                .flatten(),
+        );
+        let items = Metadata::find()
+            .filter(metadata::Column::Id.is_in(metadata_ids))
+            .order_by_asc(metadata::Column::Id)
+            .all(db)
+            .await?;
+        let mut result = ImportReconcileResult {
+            checked: items.len(),
+            ..Default::default()
+        };
+        for item in items {
+            // DEV: Custom media items do not have a provider
+            let Ok(provider) = self
+                .media_service
+                .get_metadata_provider(item.lot, item.source)
+                .await
+            else {
+                continue;
+            };
+            if provider.metadata_details(&item.identifier).await.is_ok() {
+                continue;
+            }
+            let identifier = match find_by_exact_title(Some(&provider), item.lot, &item.title).await
+            {
+                Ok(identifier) if identifier != item.identifier => identifier,
+                // DEV: The provider still has the item, so its details could not be
+                // fetched because of some other error
+                Ok(_) => continue,
+                Err(mut failed) => {
+                    failed.error = Some(format!(
+                        "{identifier:?} is no longer on the provider: {error}",
+                        identifier = item.identifier,
+                        error = failed.error.unwrap_or_default()
+                    ));
+                    result.failed_items.push(failed);
+                    continue;
+                }
+            };
+            let replacement = self
+                .media_service
+                .commit_metadata(CommitMetadataInput {
+                    lot: item.lot,
+                    source: item.source,
+                    identifier: identifier.clone(),
+                })
+                .await?;
+            self.media_service
+                .merge_metadata(user_id, item.id, replacement.id)
+                .await?;
+            tracing::debug!(
+                "Replaced {old:?} with {identifier:?} for {title:?}",
+                old = item.identifier,
+                title = item.title
+            );
+            result.reconciled += 1;
+        }
+        if result.reconciled > 0 {
+            self.media_service
+                .deploy_background_job(user_id, BackgroundJob::CalculateSummary)
+                .await
+                .ok();
+        }
+        Ok(result)
+    }
+
+    async fn start_import_job(
+        &self,
+        user_id: i32,
+        source: ImportSource,
+    ) -> Result<import_report::Model> {
+        let model = import_report::ActiveModel {
+            user_id: ActiveValue::Set(user_id),
+            source: ActiveValue::Set(source),
+            progress: ActiveValue::Set(Some(0)),
+            ..Default::default()
+        };
+        let model = model.insert(&self.media_service.db).await.unwrap();
+        tracing::debug!("Started import job with id = {id}", id = model.id);
+        Ok(model)
+    }
+
+    /// Find the report that was created when the job was deployed, which also
+    /// lets an interrupted job continue from its cursor, or start a new one. The
+    /// job is rejected if the report belongs to another user.
+    pub async fn resume_or_start_import_job(
+        &self,
+        user_id: i32,
+        source: ImportSource,
+        job_id: Option<String>,
+    ) -> Result<import_report::Model> {
+        let Some(job_id) = job_id else {
+            return Err(Error::new("The import job does not have an id"));
+        };
+        let Some(report) = ImportReport::find()
+            .filter(import_report::Column::JobId.eq(&job_id))
+            .order_by_desc(import_report::Column::Id)
+            .one(&self.media_service.db)
+            .await?
+        else {
+            return Err(Error::new("No import report was created for this job"));
+        };
+        // DEV: The job is stored outside of the database, so it is only trusted
+        // to import into the library of the user who deployed it
+        if report.user_id != user_id || report.source != source {
+            tracing::error!(
+                "Import job with id = {job_id} for user = {user_id} does not match its report with id = {id}",
+                id = report.id
+            );
+            return Err(Error::new("The import report belongs to another import"));
+        }
+        if report.finished_on.is_none() && report.success.is_none() {
+            tracing::debug!("Found report of import job with id = {id}", id = report.id);
+            if report.progress.is_some() {
+                return Ok(report);
+            }
+            let mut model: import_report::ActiveModel = report.into();
+            model.started_on = ActiveValue::Set(Utc::now());
+            model.progress = ActiveValue::Set(Some(0));
+            let report = model.update(&self.media_service.db).await?;
+            return Ok(report);
+        }
+        let report = self.start_import_job(user_id, source).await?;
+        let mut model: import_report::ActiveModel = report.into();
+        model.job_id = ActiveValue::Set(Some(job_id));
+        let report = model.update(&self.media_service.db).await?;
+        Ok(report)
+    }
+
+    /// Send a summary of the finished import to the notification platforms of the
+    /// user. Sending it is best effort, so errors are only logged and never fail
+    /// the import.
+    pub async fn send_import_summary(&self, user_id: i32, job_id: Option<&str>) {
+        let Some(job_id) = job_id else {
+            return;
+        };
+        let report = ImportReport::find()
+            .filter(import_report::Column::JobId.eq(job_id))
+            .filter(import_report::Column::UserId.eq(user_id))
+            .order_by_desc(import_report::Column::Id)
+            .one(&self.media_service.db)
+            .await;
+        let Ok(Some(report)) = report else {
+            return;
+        };
+        let Some(details) = report.details.as_ref() else {
+            return;
+        };
+        let msg = format!(
+            "{summary} See the failed items in import report {id}: {url}/settings/imports-and-exports",
+            summary = details.summary(report.source, report.success == Some(false)),
+            id = report.id,
+            url = self.config.frontend.url
+        );
+        if let Err(e) = self
+            .media_service
+            .send_notifications_to_user_platforms(user_id, &msg)
+            .await
+        {
+            tracing::warn!(
+                "Could not send the summary of import report with id = {id}: {error}",
+                id = report.id,
+                error = e.message
+            );
+        }
+    }
+
+    pub async fn update_import_job_progress(
+        &self,
+        job: &import_report::Model,
+        processed: usize,
+        total: usize,
+        cursor: Option<ImportCursor>,
+    ) -> Result<()> {
+        // DEV: Progress only reaches 100 once the job is finished
+        let progress = (processed * 100 / total.max(1)).min(99);
+        let mut model: import_report::ActiveModel = job.clone().into();
+        model.progress = ActiveValue::Set(Some(progress.try_into().unwrap()));
+        if cursor.is_some() {
+            model.cursor = ActiveValue::Set(cursor);
+        }
+        model.update(&self.media_service.db).await?;
+        Ok(())
+    }
+
+    pub async fn finish_import_job(
+        &self,
+        job: import_report::Model,
+        mut details: ImportResultResponse,
+        callback_url: Option<&str>,
+    ) -> Result<import_report::Model> {
+        self.send_progress_event(
+            &job,
+            ImportProgressEvent::Finished(ImportProgressFinished {
+                total: details.import.total,
+                failed: details.failed_items.len(),
+            }),
+        );
+        // DEV: An import that timed out is stopped, but it should still be shown
+        // as failed once it finishes
+        let invalidated = details.import.timed_out
+            || details.import.error.is_some()
+            || ImportReport::find_by_id(job.id)
+                .one(&self.media_service.db)
+                .await?
+                .and_then(|r| r.success)
+                == Some(false);
+        set_error_codes(&mut details.failed_items);
+        let mut model: import_report::ActiveModel = job.into();
+        model.finished_on = ActiveValue::Set(Some(Utc::now()));
+        model.details = ActiveValue::Set(Some(details));
+        model.success = ActiveValue::Set(Some(!invalidated));
+        model.progress = ActiveValue::Set(Some(100));
+        model.cursor = ActiveValue::Set(None);
+        let model = model.update(&self.media_service.db).await.unwrap();
+        if let (Some(url), Some(details)) = (callback_url, model.details.as_ref()) {
+            send_import_callback(
+                url,
+                &self.config.importer.callback_allowed_hosts,
+                model.id,
+                details,
+            )
+            .await;
+        }
+        Ok(model)
+    }
+}
+
+/// Whether an address belongs to the server or its network, which callbacks must
+/// not be able to reach.
+fn is_internal_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                // DEV: The shared address space (100.64.0.0/10) used by carrier-grade NAT
+                || (a == 100 && (b & 0xc0) == 64)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_internal_address(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // DEV: Unique local (fc00::/7) and link-local (fe80::/10) addresses
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80
+            }
+        },
+    }
+}
+
+/// Check that a callback URL uses HTTP(S) and that its host does not resolve to an
+/// internal address, unless the host has been allowed by the admin.
+pub async fn validate_callback_url(url: &str, allowed_hosts: &[String]) -> Result<Url> {
+    let url = Url::parse(url).map_err(|_| Error::new("The callback URL is not valid"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::new("The callback URL must use HTTP or HTTPS"));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| Error::new("The callback URL does not have a host"))?;
+    if allowed_hosts.iter().any(|h| h.eq_ignore_ascii_case(host)) {
+        return Ok(url);
+    }
+    // DEV: The host is resolved so that names which point to internal addresses
+    // are rejected too
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or_default();
+    let addresses = lookup_host((host, port))
+        .await
+        .map_err(|_| Error::new("The host of the callback URL could not be resolved"))?
+        .collect_vec();
+    if addresses.is_empty() || addresses.iter().any(|a| is_internal_address(a.ip())) {
+        return Err(Error::new(
+            "The callback URL can not point to a loopback, private or link-local address",
+        ));
+    }
+    Ok(url)
+}
+
+/// Send the result of an import to its callback URL. Delivery is best effort, so
+/// errors are only logged and never fail the import.
+async fn send_import_callback(
+    url: &str,
+    allowed_hosts: &[String],
+    report_id: i32,
+    details: &ImportResultResponse,
+) {
+    // DEV: Checked again since the host could resolve to another address by now
+    let url = match validate_callback_url(url, allowed_hosts).await {
+        Ok(url) => url,
+        Err(e) => {
+            tracing::warn!("Not sending import callback to {url}: {}", e.message);
+            return;
+        }
+    };
+    let payload = ImportCallbackPayload { report_id, details };
+    for attempt in 1..=CALLBACK_ATTEMPTS {
+        let request = surf::post(url.clone()).body_json(&payload).unwrap();
+        let error = match tokio::time::timeout(CALLBACK_TIMEOUT, request).await {
+            Ok(Ok(rsp)) if rsp.status().is_success() => return,
+            Ok(Ok(rsp)) => format!("status {}", rsp.status()),
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "timed out".to_owned(),
+        };
+        tracing::warn!("Could not send import callback to {url} (attempt {attempt}): {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_internal_address, validate_callback_url};
+
+    #[test]
+    fn test_internal_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(is_internal_address(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in ["1.1.1.1", "100.128.0.1", "2606:4700::1111"] {
+            assert!(!is_internal_address(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_callback_url_validation() {
+        assert!(validate_callback_url("file:///etc/passwd", &[])
+            .await
+            .is_err());
+        assert!(validate_callback_url("ftp://1.1.1.1/", &[]).await.is_err());
+        assert!(validate_callback_url("http://127.0.0.1:8000/", &[])
+            .await
+            .is_err());
+        assert!(validate_callback_url("http://[::1]/hook", &[])
+            .await
+            .is_err());
+        assert!(validate_callback_url("https://1.1.1.1/hook", &[])
+            .await
+            .is_ok());
+        let allowed = ["n8n.local".to_owned(), "127.0.0.1".to_owned()];
+        assert!(validate_callback_url("http://127.0.0.1:8000/", &allowed)
+            .await
+            .is_ok());
+    }
+}
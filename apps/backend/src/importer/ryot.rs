@@ -0,0 +1,48 @@
+use std::fs;
+
+use async_graphql::{Error, Result};
+
+use crate::{
+    importer::{DeployRyotImportInput, ImportResult},
+    models::{media::ImportOrExportItemIdentifier, CompleteExport},
+    utils::VERSION,
+};
+
+fn major_version(version: &str) -> &str {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .next()
+        .unwrap_or_default()
+}
+
+pub async fn import(input: DeployRyotImportInput) -> Result<ImportResult> {
+    let export = fs::read_to_string(input.export)?;
+    let export = serde_json::from_str::<CompleteExport>(&export)
+        .map_err(|e| Error::new(format!("The file is not a Ryot export: {e}")))?;
+    // DEV: Exports that were created before the version was recorded have the
+    // same format as the current ones
+    if let Some(version) = export.version {
+        if major_version(&version) != major_version(VERSION) {
+            return Err(Error::new(format!(
+                "The export was created by Ryot v{version}, which can not be imported by \
+                 Ryot v{VERSION}. Import it into an instance with the same major version."
+            )));
+        }
+    }
+    let mut media = export.media.unwrap_or_default();
+    media.iter_mut().for_each(|m| {
+        m.internal_identifier = Some(ImportOrExportItemIdentifier::NeedsDetails {
+            identifier: m.identifier.clone(),
+            title: m.source_id.clone(),
+        })
+    });
+    Ok(ImportResult {
+        media,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        failed_items: vec![],
+        measurements: vec![],
+    })
+}
@@ -0,0 +1,331 @@
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, NewAead},
+    Aes256Gcm,
+};
+use async_graphql::{Enum, Error, Result, SimpleObject};
+use chrono::{Duration, Utc};
+use data_encoding::BASE64;
+use database::ImportSource;
+use itertools::Itertools;
+use nanoid::nanoid;
+use sea_orm::{
+    prelude::DateTimeUtc, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait,
+    FromJsonQueryResult, QueryFilter, QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    entities::{
+        import_report,
+        prelude::{ImportReport, User},
+        user,
+    },
+    importer::{DeployImportJobInput, ImporterService},
+    utils::user_by_id,
+};
+
+/// Mixed into the key that the inputs of scheduled imports are encrypted with, so
+/// that it is not the JWT secret itself.
+const SCHEDULED_IMPORT_KEY_CONTEXT: &str = "ryot-scheduled-imports";
+const SCHEDULED_IMPORT_NONCE_LENGTH: usize = 12;
+
+/// How often a scheduled import is run again.
+#[derive(Debug, Enum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSchedule {
+    Daily,
+    Weekly,
+}
+
+impl ImportSchedule {
+    fn interval(self) -> Duration {
+        match self {
+            Self::Daily => Duration::try_days(1).unwrap(),
+            Self::Weekly => Duration::try_weeks(1).unwrap(),
+        }
+    }
+}
+
+#[derive(
+    Debug, SimpleObject, Serialize, Deserialize, Clone, PartialEq, Eq, FromJsonQueryResult,
+)]
+pub struct UserScheduledImport {
+    pub id: usize,
+    pub source: ImportSource,
+    pub schedule: ImportSchedule,
+    /// The input that every run is deployed with. It contains the credentials of
+    /// the source, so it is never exposed.
+    #[graphql(skip)]
+    pub input: EncryptedImportInput,
+    pub created_on: DateTimeUtc,
+    pub last_run_on: Option<DateTimeUtc>,
+}
+
+/// The input of a scheduled import, which is stored encrypted since it contains
+/// the credentials of the source. The key is derived from the JWT secret, so the
+/// scheduled imports can not be run anymore if it is changed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct EncryptedImportInput(String);
+
+impl EncryptedImportInput {
+    fn cipher(secret: &str) -> Aes256Gcm {
+        let key = Sha256::new()
+            .chain_update(SCHEDULED_IMPORT_KEY_CONTEXT)
+            .chain_update(secret)
+            .finalize();
+        Aes256Gcm::new(GenericArray::from_slice(&key))
+    }
+
+    fn encrypt(input: &DeployImportJobInput, secret: &str) -> Result<Self> {
+        let nonce: [u8; SCHEDULED_IMPORT_NONCE_LENGTH] = rand::random();
+        let ciphertext = Self::cipher(secret)
+            .encrypt(
+                GenericArray::from_slice(&nonce),
+                serde_json::to_vec(input)?.as_slice(),
+            )
+            .map_err(|_| Error::new("Could not encrypt the input of the scheduled import"))?;
+        Ok(Self(
+            BASE64.encode(&[nonce.as_slice(), &ciphertext].concat()),
+        ))
+    }
+
+    fn decrypt(&self, secret: &str) -> Result<DeployImportJobInput> {
+        let error = || Error::new("Could not decrypt the input of the scheduled import");
+        let data = BASE64.decode(self.0.as_bytes()).map_err(|_| error())?;
+        if data.len() < SCHEDULED_IMPORT_NONCE_LENGTH {
+            return Err(error());
+        }
+        let (nonce, ciphertext) = data.split_at(SCHEDULED_IMPORT_NONCE_LENGTH);
+        let input = Self::cipher(secret)
+            .decrypt(GenericArray::from_slice(nonce), ciphertext)
+            .map_err(|_| error())?;
+        Ok(serde_json::from_slice(&input)?)
+    }
+}
+
+/// Check that the source is read from a server, which is needed to run the import
+/// again. Imports that use an uploaded file can not be scheduled.
+pub fn ensure_can_be_scheduled(source: ImportSource) -> Result<()> {
+    let can_be_scheduled = matches!(
+        source,
+        ImportSource::MediaTracker
+            | ImportSource::Trakt
+            | ImportSource::Anilist
+            | ImportSource::Kitsu
+            | ImportSource::Steam
+            | ImportSource::LastFm
+            | ImportSource::Plex
+            | ImportSource::Jellyfin
+            | ImportSource::Kodi
+            | ImportSource::Tmdb
+            | ImportSource::Simkl
+            | ImportSource::Hardcover
+            | ImportSource::Audiobookshelf
+    );
+    if !can_be_scheduled {
+        return Err(Error::new(format!(
+            "Imports from {source:?} use an uploaded file and can not be scheduled"
+        )));
+    }
+    Ok(())
+}
+
+impl ImporterService {
+    /// Save an import so that it is run again periodically. The first run is the
+    /// one that is being deployed.
+    pub async fn schedule_import(
+        &self,
+        user_id: i32,
+        schedule: ImportSchedule,
+        input: &DeployImportJobInput,
+    ) -> Result<()> {
+        ensure_can_be_scheduled(input.source)?;
+        let user = user_by_id(&self.media_service.db, user_id).await?;
+        let mut imports = user.scheduled_imports.clone().unwrap_or_default();
+        let now = Utc::now();
+        imports.push(UserScheduledImport {
+            id: imports.iter().map(|i| i.id).max().unwrap_or_default() + 1,
+            source: input.source,
+            schedule,
+            input: EncryptedImportInput::encrypt(
+                &DeployImportJobInput {
+                    dry_run: None,
+                    deduplicate: Some(true),
+                    schedule: None,
+                    idempotency_key: None,
+                    job_id: None,
+                    bulk_id: None,
+                    ..input.clone()
+                },
+                &self.config.users.jwt_secret,
+            )?,
+            created_on: now,
+            last_run_on: Some(now),
+        });
+        let mut user: user::ActiveModel = user.into();
+        user.scheduled_imports = ActiveValue::Set(Some(imports));
+        user.update(&self.media_service.db).await?;
+        Ok(())
+    }
+
+    pub async fn scheduled_imports(&self, user_id: i32) -> Result<Vec<UserScheduledImport>> {
+        let user = user_by_id(&self.media_service.db, user_id).await?;
+        Ok(user.scheduled_imports.unwrap_or_default())
+    }
+
+    pub async fn cancel_scheduled_import(
+        &self,
+        user_id: i32,
+        scheduled_import_id: usize,
+    ) -> Result<bool> {
+        let user = user_by_id(&self.media_service.db, user_id).await?;
+        let imports = user.scheduled_imports.clone().unwrap_or_default();
+        if !imports.iter().any(|i| i.id == scheduled_import_id) {
+            return Err(Error::new("Scheduled import does not exist"));
+        }
+        let remaining = imports
+            .into_iter()
+            .filter(|i| i.id != scheduled_import_id)
+            .collect_vec();
+        let mut user: user::ActiveModel = user.into();
+        user.scheduled_imports = ActiveValue::Set((!remaining.is_empty()).then_some(remaining));
+        user.update(&self.media_service.db).await?;
+        Ok(true)
+    }
+
+    /// Deploy the scheduled imports of all users which are due. An import that can
+    /// not be deployed is logged and tried again the next time.
+    pub async fn run_scheduled_imports(&self) -> Result<()> {
+        let users = User::find()
+            .filter(user::Column::ScheduledImports.is_not_null())
+            .all(&self.media_service.db)
+            .await?;
+        let now = Utc::now();
+        for user in users {
+            let mut imports = user.scheduled_imports.clone().unwrap_or_default();
+            for import in user.scheduled_imports.unwrap_or_default() {
+                // DEV: This runs once a day, so imports that are due within the next
+                // hour are run now instead of a day late
+                let due = import.last_run_on.map_or(true, |l| {
+                    l + import.schedule.interval() <= now + Duration::try_hours(1).unwrap()
+                });
+                if !due {
+                    continue;
+                }
+                match self.run_scheduled_import(user.id, &import).await {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(e) => {
+                        tracing::error!(
+                            "Could not run scheduled import with id = {id} for user = {user_id}: {e:?}",
+                            id = import.id,
+                            user_id = user.id
+                        );
+                        continue;
+                    }
+                }
+                if let Some(i) = imports.iter_mut().find(|i| i.id == import.id) {
+                    i.last_run_on = Some(now);
+                }
+                // DEV: Saved after every run so that the imports which were deployed
+                // are not deployed again if a later one fails
+                let model = user::ActiveModel {
+                    id: ActiveValue::Unchanged(user.id),
+                    scheduled_imports: ActiveValue::Set(Some(imports.clone())),
+                    ..Default::default()
+                };
+                if let Err(e) = model.update(&self.media_service.db).await {
+                    tracing::error!(
+                        "Could not save the last run of scheduled import with id = {id} for user = {user_id}: {e:?}",
+                        id = import.id,
+                        user_id = user.id
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Deploy a scheduled import, returning whether it was deployed. It is not if
+    /// the user has too many imports running, in which case it is run the next
+    /// time the scheduled imports are checked.
+    async fn run_scheduled_import(
+        &self,
+        user_id: i32,
+        import: &UserScheduledImport,
+    ) -> Result<bool> {
+        if !self.has_free_import_slots(user_id, 1).await? {
+            tracing::debug!(
+                "Delaying scheduled import with id = {id} for user = {user_id} since they have too many imports running",
+                id = import.id,
+            );
+            return Ok(false);
+        }
+        tracing::debug!(
+            "Running scheduled import with id = {id} for user = {user_id}",
+            id = import.id,
+        );
+        let mut input = DeployImportJobInput {
+            job_id: Some(nanoid!()),
+            ..import.input.decrypt(&self.config.users.jwt_secret)?
+        };
+        if let Some(media_tracker) = input.media_tracker.as_mut() {
+            if let Some(since) = self.last_seen_on(user_id, import.source).await? {
+                media_tracker.since = Some(since);
+            }
+        }
+        if let Some(lastfm) = input.lastfm.as_mut() {
+            if let Some(since) = self.last_seen_on(user_id, import.source).await? {
+                lastfm.from = Some(since);
+            }
+        }
+        self.push_import_job(user_id, input).await?;
+        Ok(true)
+    }
+
+    /// The date of the most recent seen entry of the last successful import from
+    /// the source, which is where incremental imports continue from.
+    async fn last_seen_on(
+        &self,
+        user_id: i32,
+        source: ImportSource,
+    ) -> Result<Option<DateTimeUtc>> {
+        let reports = ImportReport::find()
+            .filter(import_report::Column::UserId.eq(user_id))
+            .filter(import_report::Column::Source.eq(source))
+            .filter(import_report::Column::Success.eq(true))
+            .order_by_desc(import_report::Column::FinishedOn)
+            .all(&self.media_service.db)
+            .await?;
+        // DEV: Runs that did not import any seen entries do not have a date, so
+        // the most recent run that did is used
+        Ok(reports
+            .into_iter()
+            .find_map(|r| r.details.and_then(|d| d.import.last_seen_on)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use database::ImportSource;
+    use serde_json::json;
+
+    use super::EncryptedImportInput;
+    use crate::importer::DeployImportJobInput;
+
+    #[test]
+    fn test_scheduled_import_input_is_encrypted() {
+        let input: DeployImportJobInput = serde_json::from_value(json!({
+            "source": ImportSource::Steam,
+            "steam": { "api_key": "secret-api-key", "steam_id": "76561197960287930" },
+        }))
+        .unwrap();
+        let encrypted = EncryptedImportInput::encrypt(&input, "jwt-secret").unwrap();
+        assert!(!serde_json::to_string(&encrypted)
+            .unwrap()
+            .contains("secret-api-key"));
+        assert_eq!(encrypted.decrypt("jwt-secret").unwrap(), input);
+        assert!(encrypted.decrypt("another-secret").is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs::File,
+    io::Read,
+};
+
+use async_graphql::Result;
+use database::{MediaSource, MetadataLot};
+use itertools::Itertools;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use sea_orm::prelude::DateTimeUtc;
+use serde::{de::DeserializeOwned, Deserialize};
+use zip::ZipArchive;
+
+use crate::{
+    importer::{
+        DeploySerializdImportInput, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportRatingScale, ImportResult,
+    },
+    miscellaneous::resolver::Provider,
+    models::media::{
+        ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportMediaItemSeen,
+        ShowSpecifics,
+    },
+};
+
+// DEV: Ratings are given in half stars out of 5 and exported as a number out of 10
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Ten;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Watched {
+    show_id: i32,
+    show_name: String,
+    /// Not set when the whole show was marked as watched.
+    season_number: Option<i32>,
+    /// Not set when the whole season was marked as watched.
+    episode_number: Option<i32>,
+    watched_at: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Review {
+    show_id: i32,
+    show_name: String,
+    season_number: Option<i32>,
+    episode_number: Option<i32>,
+    rating: Option<Decimal>,
+    review_text: Option<String>,
+    #[serde(default)]
+    contains_spoiler: bool,
+    created_at: Option<DateTimeUtc>,
+}
+
+fn read_file<T: DeserializeOwned>(archive: &mut ZipArchive<File>, name: &str) -> Result<Vec<T>> {
+    // DEV: The files are inside a directory named after the export in some archives
+    let Some(path) = archive
+        .file_names()
+        .find(|f| f.rsplit('/').next() == Some(name))
+        .map(String::from)
+    else {
+        return Ok(vec![]);
+    };
+    let mut contents = String::new();
+    archive.by_name(&path)?.read_to_string(&mut contents)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+/// The episodes of a show that were marked as watched all at once, either the
+/// whole show (without the specials) or a single season.
+fn expand_episodes(specifics: &ShowSpecifics, season: Option<i32>) -> Vec<(i32, i32)> {
+    specifics
+        .seasons
+        .iter()
+        .filter(|s| match season {
+            Some(season) => s.season_number == season,
+            None => s.season_number > 0,
+        })
+        .flat_map(|s| {
+            s.episodes
+                .iter()
+                .map(|e| (s.season_number, e.episode_number))
+        })
+        .collect()
+}
+
+fn media_entry(
+    media: &mut HashMap<i32, ImportOrExportMediaItem>,
+    id: i32,
+    title: String,
+) -> &mut ImportOrExportMediaItem {
+    media.entry(id).or_insert_with(|| ImportOrExportMediaItem {
+        source_id: title.clone(),
+        lot: MetadataLot::Show,
+        source: MediaSource::Tmdb,
+        identifier: "".to_string(),
+        internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+            identifier: id.to_string(),
+            title,
+        }),
+        seen_history: vec![],
+        reviews: vec![],
+        collections: vec![],
+        added_on: None,
+        tags: vec![],
+        images: vec![],
+        monitored: None,
+        matched_by_title: false,
+        status: None,
+    })
+}
+
+pub async fn import(
+    input: DeploySerializdImportInput,
+    tmdb_service: Option<&Provider>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::Show;
+    let mut archive = ZipArchive::new(File::open(&input.export_archive)?)?;
+    let watched: Vec<Watched> = read_file(&mut archive, "watched.json")?;
+    let reviews: Vec<Review> = read_file(&mut archive, "reviews.json")?;
+
+    let mut failed_items = vec![];
+    let mut media: HashMap<i32, ImportOrExportMediaItem> = HashMap::new();
+    // DEV: The details are only fetched for shows that have seasons (or the whole
+    // show) marked as watched, and only once per show
+    let mut specifics: HashMap<i32, Option<ShowSpecifics>> = HashMap::new();
+    let total = watched.len();
+    for (idx, record) in watched.into_iter().enumerate() {
+        let episodes = match (record.season_number, record.episode_number) {
+            (Some(season), Some(episode)) => vec![(season, episode)],
+            (season, _) => {
+                let Some(tmdb_service) = tmdb_service else {
+                    // DEV: Episodes can only be expanded using the provider, so a
+                    // preview has one entry for the whole season (or show)
+                    media_entry(&mut media, record.show_id, record.show_name)
+                        .seen_history
+                        .push(ImportOrExportMediaItemSeen {
+                            ended_on: record.watched_at,
+                            show_season_number: season,
+                            ..Default::default()
+                        });
+                    continue;
+                };
+                if let Entry::Vacant(vacant) = specifics.entry(record.show_id) {
+                    tracing::debug!(
+                        "Getting episodes of {name:?} ({idx}/{total})",
+                        name = record.show_name
+                    );
+                    let details = match tmdb_service
+                        .metadata_details(&record.show_id.to_string())
+                        .await
+                    {
+                        Ok(details) => details.show_specifics,
+                        Err(e) => {
+                            failed_items.push(ImportFailedItem::new(
+                                Some(lot),
+                                ImportFailStep::MediaDetailsFromProvider,
+                                record.show_name.clone(),
+                                Some(e.to_string()),
+                            ));
+                            None
+                        }
+                    };
+                    vacant.insert(details);
+                }
+                match specifics.get(&record.show_id) {
+                    Some(Some(s)) => expand_episodes(s, season),
+                    _ => continue,
+                }
+            }
+        };
+        media_entry(&mut media, record.show_id, record.show_name)
+            .seen_history
+            .extend(
+                episodes
+                    .into_iter()
+                    .map(|(season, episode)| ImportOrExportMediaItemSeen {
+                        ended_on: record.watched_at,
+                        show_season_number: Some(season),
+                        show_episode_number: Some(episode),
+                        ..Default::default()
+                    }),
+            );
+    }
+
+    for record in reviews {
+        let review = record
+            .review_text
+            .filter(|r| !r.trim().is_empty())
+            .map(|r| ImportOrExportItemReview {
+                date: record.created_at,
+                spoiler: Some(record.contains_spoiler),
+                text: Some(r),
+                visibility: None,
+            });
+        let rating = record
+            .rating
+            .filter(|r| *r > dec!(0))
+            .map(|r| RATING_SCALE.normalize(r));
+        if review.is_none() && rating.is_none() {
+            continue;
+        }
+        media_entry(&mut media, record.show_id, record.show_name)
+            .reviews
+            .push(ImportOrExportItemRating {
+                review,
+                rating,
+                show_season_number: record.season_number,
+                show_episode_number: record.episode_number,
+                ..Default::default()
+            });
+    }
+
+    Ok(ImportResult {
+        media: media
+            .into_values()
+            .sorted_by(|a, b| a.source_id.cmp(&b.source_id))
+            .collect(),
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
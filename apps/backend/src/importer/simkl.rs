@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use async_graphql::Result;
+use database::{MediaSource, MetadataLot};
+use http_types::mime;
+use rust_decimal::Decimal;
+use sea_orm::prelude::DateTimeUtc;
+use serde::Deserialize;
+use serde_json::json;
+use surf::http::headers::{ACCEPT, AUTHORIZATION};
+
+use crate::{
+    importer::{
+        DeploySimklImportInput, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportRatingScale, ImportResult,
+    },
+    miscellaneous::resolver::RateLimited,
+    miscellaneous::DefaultCollection,
+    models::media::{ImportOrExportItemRating, ImportOrExportMediaItemSeen},
+    providers::tmdb::NonMediaTmdbService,
+    utils::get_base_http_client,
+};
+
+static URL: &str = "https://api.simkl.com/";
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Ten;
+
+#[derive(Debug, Deserialize)]
+struct Details {
+    title: String,
+    // DEV: Simkl returns some ids as numbers and others as strings
+    #[serde(default)]
+    ids: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Episode {
+    number: i32,
+    watched_at: Option<DateTimeUtc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Season {
+    number: i32,
+    #[serde(default)]
+    episodes: Vec<Episode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Item {
+    status: String,
+    user_rating: Option<Decimal>,
+    last_watched_at: Option<DateTimeUtc>,
+    #[serde(alias = "movie", alias = "show")]
+    details: Details,
+    anime_type: Option<String>,
+    #[serde(default)]
+    seasons: Vec<Season>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AllItems {
+    #[serde(default)]
+    movies: Vec<Item>,
+    #[serde(default)]
+    shows: Vec<Item>,
+    #[serde(default)]
+    anime: Vec<Item>,
+}
+
+fn get_id(details: &Details, name: &str) -> Option<String> {
+    match details.ids.get(name)? {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s.to_owned()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+async fn get_identifier(
+    details: &Details,
+    lot: MetadataLot,
+    tmdb_service: Option<&RateLimited<NonMediaTmdbService>>,
+) -> std::result::Result<(MetadataLot, MediaSource, String), String> {
+    if let Some(id) = get_id(details, "tmdb") {
+        return Ok((lot, MediaSource::Tmdb, id));
+    }
+    if let Some(id) = get_id(details, "imdb") {
+        // DEV: Previews do not look up the IMDb id, so the item only has its title
+        let Some(tmdb_service) = tmdb_service else {
+            return Ok((lot, MediaSource::Tmdb, String::new()));
+        };
+        return match tmdb_service
+            .request(|s| s.find_by_external_id(&id, "imdb_id"))
+            .await
+        {
+            Ok((found_lot, identifier)) if found_lot == lot => {
+                Ok((lot, MediaSource::Tmdb, identifier))
+            }
+            Ok(_) => Err(format!("No {lot} found on TMDB for IMDb id {id}")),
+            Err(e) => Err(e.to_string()),
+        };
+    }
+    Err("Item does not have a TMDB or IMDb id".to_owned())
+}
+
+pub async fn import(
+    input: DeploySimklImportInput,
+    tmdb_service: Option<&RateLimited<NonMediaTmdbService>>,
+) -> Result<ImportResult> {
+    let client = get_base_http_client(
+        URL,
+        vec![
+            (ACCEPT, mime::JSON.to_string()),
+            (AUTHORIZATION, format!("Bearer {}", input.access_token)),
+            ("simkl-api-key".into(), input.client_id),
+        ],
+    );
+    let data: AllItems = client
+        .get("sync/all-items")
+        .query(&json!({ "extended": "full", "episode_watched_at": "yes" }))
+        .unwrap()
+        .await
+        .map_err(|e| anyhow!(e))?
+        .body_json()
+        .await
+        .map_err(|e| anyhow!(e))?;
+    tracing::debug!(
+        "Got {} movies, {} shows and {} anime from Simkl",
+        data.movies.len(),
+        data.shows.len(),
+        data.anime.len()
+    );
+
+    let items = data
+        .movies
+        .into_iter()
+        .map(|i| (MetadataLot::Movie, i))
+        .chain(data.shows.into_iter().map(|i| (MetadataLot::Show, i)))
+        .chain(data.anime.into_iter().map(|i| {
+            let lot = match i.anime_type.as_deref() {
+                Some("movie") => MetadataLot::Movie,
+                _ => MetadataLot::Show,
+            };
+            (lot, i)
+        }))
+        .collect::<Vec<_>>();
+    let total = items.len();
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    for (idx, (lot, item)) in items.into_iter().enumerate() {
+        let title = item.details.title.clone();
+        tracing::debug!("Getting details for {title:?} ({idx}/{total})");
+        // DEV: Anime that are not on TMDB are imported from AniList instead
+        let (lot, source, identifier) = match get_identifier(&item.details, lot, tmdb_service).await
+        {
+            Ok(i) => i,
+            Err(e) => {
+                match get_id(&item.details, "anilist").filter(|_| item.anime_type.is_some()) {
+                    Some(id) => (MetadataLot::Anime, MediaSource::Anilist, id),
+                    None => {
+                        failed_items.push(ImportFailedItem::new(
+                            Some(lot),
+                            ImportFailStep::ItemDetailsFromSource,
+                            title,
+                            Some(e),
+                        ));
+                        continue;
+                    }
+                }
+            }
+        };
+        let mut seen_history = item
+            .seasons
+            .iter()
+            .flat_map(|s| {
+                s.episodes.iter().map(move |e| match lot {
+                    MetadataLot::Anime => ImportOrExportMediaItemSeen {
+                        ended_on: e.watched_at,
+                        anime_episode_number: Some(e.number),
+                        ..Default::default()
+                    },
+                    _ => ImportOrExportMediaItemSeen {
+                        ended_on: e.watched_at,
+                        show_season_number: Some(s.number),
+                        show_episode_number: Some(e.number),
+                        ..Default::default()
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut collections = vec![];
+        match item.status.as_str() {
+            "completed" if seen_history.is_empty() && lot != MetadataLot::Show => {
+                seen_history.push(ImportOrExportMediaItemSeen {
+                    ended_on: item.last_watched_at,
+                    ..Default::default()
+                });
+            }
+            "plantowatch" => collections.push(DefaultCollection::Watchlist.to_string()),
+            "watching" => collections.push(DefaultCollection::InProgress.to_string()),
+            _ => {}
+        }
+        media.push(ImportOrExportMediaItem {
+            source_id: title.clone(),
+            lot,
+            source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title,
+            }),
+            seen_history,
+            reviews: Vec::from_iter(item.user_rating.map(|r| ImportOrExportItemRating {
+                rating: Some(RATING_SCALE.normalize(r)),
+                ..Default::default()
+            })),
+            collections,
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        });
+    }
+
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
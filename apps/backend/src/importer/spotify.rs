@@ -0,0 +1,173 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{BufReader, Read},
+};
+
+use async_graphql::Result;
+use database::{MediaSource, MetadataLot};
+use itertools::Itertools;
+use sea_orm::prelude::DateTimeUtc;
+use serde::{
+    de::{SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use zip::ZipArchive;
+
+use crate::{
+    importer::{
+        find_by_exact_title, podcast_seen_history, DeploySpotifyImportInput, ImportFailStep,
+        ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+        PlayedEpisode,
+    },
+    miscellaneous::resolver::Provider,
+};
+
+#[derive(Debug, Deserialize)]
+struct Play {
+    ts: DateTimeUtc,
+    #[serde(default)]
+    ms_played: u64,
+    spotify_track_uri: Option<String>,
+    spotify_episode_uri: Option<String>,
+    episode_name: Option<String>,
+    episode_show_name: Option<String>,
+}
+
+/// Calls the function with each play of a file, without reading the whole file
+/// into memory since the history can contain hundreds of thousands of plays.
+struct Plays<F>(F);
+
+impl<'de, F: FnMut(Play)> Visitor<'de> for Plays<F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of plays")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(play) = seq.next_element()? {
+            (self.0)(play);
+        }
+        Ok(())
+    }
+}
+
+// DEV: Older exports name the files "endsong_0.json", newer ones
+// "Streaming_History_Audio_2023.json" (and "Streaming_History_Video_*" for videos)
+fn is_history_file(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or_default();
+    (name.starts_with("endsong_") || name.starts_with("Streaming_History_"))
+        && name.ends_with(".json")
+}
+
+fn read_file(reader: impl Read, on_play: impl FnMut(Play)) -> serde_json::Result<()> {
+    serde_json::Deserializer::from_reader(BufReader::new(reader)).deserialize_seq(Plays(on_play))
+}
+
+pub async fn import(
+    input: DeploySpotifyImportInput,
+    itunes_service: Option<&Provider>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::Podcast;
+    let min_ms_played = u64::from(input.min_seconds_played.unwrap_or_default()) * 1000;
+    let mut archive = ZipArchive::new(File::open(&input.export_archive)?)?;
+    let files = archive
+        .file_names()
+        .filter(|f| is_history_file(f))
+        .map(String::from)
+        .sorted()
+        .collect_vec();
+
+    let mut failed_items = vec![];
+    let mut shows: HashMap<String, HashMap<String, PlayedEpisode>> = HashMap::new();
+    let (mut tracks, mut skipped) = (0, 0);
+    for file in files {
+        let result = read_file(archive.by_name(&file)?, |play| {
+            if play.ms_played < min_ms_played {
+                skipped += 1;
+                return;
+            }
+            if play.spotify_track_uri.is_some() {
+                tracks += 1;
+                return;
+            }
+            let (Some(uri), Some(show), Some(name)) = (
+                play.spotify_episode_uri,
+                play.episode_show_name,
+                play.episode_name,
+            ) else {
+                return;
+            };
+            let episode = shows.entry(show).or_default().entry(name).or_default();
+            episode.identifier = uri;
+            episode.played_on.push(play.ts);
+        });
+        if let Err(e) = result {
+            failed_items.push(ImportFailedItem::new(
+                None,
+                ImportFailStep::InputTransformation,
+                file,
+                Some(e.to_string()),
+            ));
+        }
+    }
+    tracing::debug!("Skipped {skipped} plays shorter than the minimum and {tracks} plays of songs");
+
+    let mut media = vec![];
+    let total = shows.len();
+    for (idx, (title, episodes)) in shows
+        .into_iter()
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .enumerate()
+    {
+        tracing::debug!("Searching for {title:?} ({idx}/{total})");
+        let identifier = match find_by_exact_title(itunes_service, lot, &title).await {
+            Ok(identifier) => identifier,
+            Err(failed) => {
+                failed_items.push(failed);
+                continue;
+            }
+        };
+        let Some(seen_history) = podcast_seen_history(
+            itunes_service,
+            &identifier,
+            &title,
+            episodes,
+            &mut failed_items,
+        )
+        .await
+        else {
+            continue;
+        };
+        media.push(ImportOrExportMediaItem {
+            source_id: title.clone(),
+            lot,
+            source: MediaSource::Itunes,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title,
+            }),
+            seen_history,
+            reviews: vec![],
+            collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: true,
+            status: None,
+        });
+    }
+
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
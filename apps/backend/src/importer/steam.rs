@@ -0,0 +1,190 @@
+use anyhow::anyhow;
+use async_graphql::Result;
+use chrono::DateTime;
+use database::{MediaSource, MetadataLot};
+use http_types::mime;
+use itertools::Itertools;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use surf::{http::headers::ACCEPT, Client};
+
+use crate::{
+    importer::{
+        find_by_exact_title, DeploySteamImportInput, ImportOrExportItemIdentifier,
+        ImportOrExportMediaItem, ImportResult,
+    },
+    miscellaneous::resolver::Provider,
+    models::media::ImportOrExportMediaItemSeen,
+    utils::get_base_http_client,
+};
+
+static URL: &str = "https://api.steampowered.com/";
+const OWNED_COLLECTION: &str = "Owned";
+
+#[derive(Debug, Deserialize)]
+struct Game {
+    appid: u32,
+    name: String,
+    /// The total playtime in minutes.
+    #[serde(default)]
+    playtime_forever: u32,
+    rtime_last_played: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OwnedGames {
+    #[serde(default)]
+    games: Vec<Game>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OwnedGamesResponse {
+    response: OwnedGames,
+}
+
+#[derive(Debug, Deserialize)]
+struct Achievement {
+    achieved: u8,
+    unlocktime: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayerStats {
+    #[serde(default)]
+    achievements: Vec<Achievement>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayerStatsResponse {
+    playerstats: PlayerStats,
+}
+
+async fn get<T: DeserializeOwned>(
+    client: &Client,
+    path: &str,
+    query: serde_json::Value,
+) -> Result<T> {
+    let data = client
+        .get(path)
+        .query(&query)
+        .unwrap()
+        .await
+        .map_err(|e| anyhow!(e))?
+        .body_json::<T>()
+        .await
+        .map_err(|e| anyhow!(e))?;
+    Ok(data)
+}
+
+/// The time the first achievement of the game was unlocked, which is the closest
+/// to when it was started since Steam does not record it.
+async fn first_achievement_unlocked_on(
+    client: &Client,
+    input: &DeploySteamImportInput,
+    appid: u32,
+) -> Option<i64> {
+    // DEV: Games without achievements return an error, which is the same as
+    // having none
+    let stats: PlayerStatsResponse = get(
+        client,
+        "ISteamUserStats/GetPlayerAchievements/v1/",
+        json!({ "key": input.api_key, "steamid": input.steam_id, "appid": appid }),
+    )
+    .await
+    .ok()?;
+    stats
+        .playerstats
+        .achievements
+        .into_iter()
+        .filter(|a| a.achieved == 1 && a.unlocktime > 0)
+        .map(|a| a.unlocktime)
+        .min()
+}
+
+pub async fn import(
+    input: DeploySteamImportInput,
+    igdb_service: Option<&Provider>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::VideoGame;
+    let client = get_base_http_client(URL, vec![(ACCEPT, mime::JSON)]);
+    let owned: OwnedGamesResponse = get(
+        &client,
+        "IPlayerService/GetOwnedGames/v1/",
+        json!({
+            "key": input.api_key,
+            "steamid": input.steam_id,
+            "include_appinfo": true,
+            "include_played_free_games": true,
+        }),
+    )
+    .await?;
+    let games = owned
+        .response
+        .games
+        .into_iter()
+        .sorted_by(|a, b| a.name.cmp(&b.name))
+        .collect_vec();
+
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let total = games.len();
+    for (idx, game) in games.into_iter().enumerate() {
+        tracing::debug!("Searching for {name:?} ({idx}/{total})", name = game.name);
+        let identifier = match find_by_exact_title(igdb_service, lot, &game.name).await {
+            Ok(identifier) => identifier,
+            Err(mut failed) => {
+                failed.error = Some(format!(
+                    "{error} (Steam app id: {appid})",
+                    error = failed.error.unwrap_or_default(),
+                    appid = game.appid
+                ));
+                failed_items.push(failed);
+                continue;
+            }
+        };
+        let mut seen_history = vec![];
+        let mut collections = vec![];
+        if game.playtime_forever > 0 {
+            let started_on = first_achievement_unlocked_on(&client, &input, game.appid).await;
+            let timestamp = |t: Option<i64>| {
+                t.filter(|t| *t > 0)
+                    .and_then(|t| DateTime::from_timestamp(t, 0))
+            };
+            seen_history.push(ImportOrExportMediaItemSeen {
+                started_on: timestamp(started_on),
+                ended_on: timestamp(game.rtime_last_played),
+                ..Default::default()
+            });
+        } else {
+            collections.push(OWNED_COLLECTION.to_owned());
+        }
+        media.push(ImportOrExportMediaItem {
+            source_id: game.name.clone(),
+            lot,
+            source: MediaSource::Igdb,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title: game.name,
+            }),
+            seen_history,
+            reviews: vec![],
+            collections,
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: true,
+            status: None,
+        });
+    }
+
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
@@ -1,36 +1,58 @@
 use std::fs;
 
 use async_graphql::Result;
-use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono::NaiveDate;
 use convert_case::{Case, Casing};
 use csv::Reader;
 use database::{MediaSource, MetadataLot};
 use itertools::Itertools;
+use rs_utils::convert_naive_to_utc;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use sea_orm::prelude::DateTimeUtc;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     importer::{
-        DeployStoryGraphImportInput, ImportFailStep, ImportFailedItem,
-        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+        mark_current_seen_in_progress, DeployStoryGraphImportInput, ImportFailStep,
+        ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportRatingScale,
+        ImportResult,
     },
+    miscellaneous::resolver::RateLimited,
+    miscellaneous::DefaultCollection,
     models::media::{
-        ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportMediaItemSeen,
+        ImportOrExportItemRating, ImportOrExportItemReview, ImportOrExportItemStatus,
+        ImportOrExportMediaItemSeen,
     },
-    providers::google_books::GoogleBooksService,
+    providers::{google_books::GoogleBooksService, openlibrary::OpenlibraryService},
 };
 
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Five;
+
+// DEV: Untagged enums only match unit variants against empty values, so the
+// status is converted from the string instead
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
+#[serde(from = "String")]
 enum ReadStatus {
-    #[serde(rename = "to-read")]
+    Read,
     ToRead,
-    #[serde(rename = "currently-reading")]
     CurrentlyReading,
+    DidNotFinish,
     Other(String),
 }
 
+impl From<String> for ReadStatus {
+    fn from(status: String) -> Self {
+        match status.as_str() {
+            "read" => Self::Read,
+            "to-read" => Self::ToRead,
+            "currently-reading" => Self::CurrentlyReading,
+            "did-not-finish" => Self::DidNotFinish,
+            _ => Self::Other(status),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct History {
     #[serde(rename = "Title")]
@@ -45,18 +67,51 @@ struct History {
     rating: Option<Decimal>,
     #[serde(rename = "Review")]
     review: Option<String>,
+    #[serde(rename = "Date Added", default)]
+    date_added: Option<String>,
     #[serde(rename = "Last Date Read")]
     last_date_read: Option<String>,
+    #[serde(rename = "Dates Read", default)]
+    dates_read: Option<String>,
     #[serde(rename = "Tags")]
     tags: Option<String>,
+    #[serde(rename = "Moods", default)]
+    moods: Option<String>,
+}
+
+fn parse_date(date: &str) -> Option<DateTimeUtc> {
+    NaiveDate::parse_from_str(date.trim(), "%Y/%m/%d")
+        .ok()
+        .map(convert_naive_to_utc)
+}
+
+/// Convert the "Dates Read" column into seen history. Each read is separated by a
+/// `;` (or a `,`) and is either a single date or a `start-end` range.
+fn parse_dates_read(dates: &str) -> Option<Vec<ImportOrExportMediaItemSeen>> {
+    dates
+        .split([';', ','])
+        .map(|d| d.trim())
+        .filter(|d| !d.is_empty())
+        .map(|d| match d.split_once('-') {
+            Some((start, end)) => Some(ImportOrExportMediaItemSeen {
+                started_on: Some(parse_date(start)?),
+                ended_on: Some(parse_date(end)?),
+                ..Default::default()
+            }),
+            None => Some(ImportOrExportMediaItemSeen {
+                ended_on: Some(parse_date(d)?),
+                ..Default::default()
+            }),
+        })
+        .collect()
 }
 
 pub async fn import(
     input: DeployStoryGraphImportInput,
-    isbn_service: &GoogleBooksService,
+    isbn_service: Option<&RateLimited<GoogleBooksService>>,
+    openlibrary_service: Option<&RateLimited<OpenlibraryService>>,
 ) -> Result<ImportResult> {
     let lot = MetadataLot::Book;
-    let source = MediaSource::GoogleBooks;
     let mut media = vec![];
     let mut failed_items = vec![];
     let export = fs::read_to_string(&input.export)?;
@@ -68,12 +123,12 @@ pub async fn import(
         let record: History = match result {
             Ok(r) => r,
             Err(e) => {
-                failed_items.push(ImportFailedItem {
-                    lot: Some(lot),
-                    step: ImportFailStep::InputTransformation,
-                    identifier: idx.to_string(),
-                    error: Some(e.to_string()),
-                });
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(e.to_string()),
+                ));
                 continue;
             }
         };
@@ -81,78 +136,139 @@ pub async fn import(
             "Getting details for {title:?} ({idx}/{total})",
             title = record.title
         );
-        if let Some(isbn) = record.isbn {
-            if let Some(identifier) = isbn_service.id_from_isbn(&isbn).await {
-                let mut seen_history = vec![
-                    ImportOrExportMediaItemSeen {
-                        started_on: None,
-                        ended_on: None,
-                        ..Default::default()
-                    };
-                    record.read_count
-                ];
-                if let Some(w) = record.last_date_read {
-                    let w = NaiveDate::parse_from_str(&w, "%Y/%m/%d").unwrap();
-                    let read_at = Some(DateTime::from_naive_utc_and_offset(
-                        NaiveDateTime::new(w, NaiveTime::from_hms_opt(0, 0, 0).unwrap()),
-                        Utc,
-                    ));
-                    seen_history.first_mut().unwrap().ended_on = read_at;
+        let Some(isbn) = record.isbn.filter(|i| !i.trim().is_empty()) else {
+            failed_items.push(ImportFailedItem::new(
+                Some(lot),
+                ImportFailStep::InputTransformation,
+                record.title,
+                Some("No ISBN found".to_owned()),
+            ));
+            continue;
+        };
+        let found = match isbn_service.zip(openlibrary_service) {
+            Some((isbn_service, openlibrary_service)) => {
+                match isbn_service.request(|s| s.id_from_isbn(&isbn)).await {
+                    Some(id) => Some((MediaSource::GoogleBooks, id)),
+                    None => openlibrary_service
+                        .request(|s| s.id_from_isbn(&isbn))
+                        .await
+                        .map(|id| (MediaSource::Openlibrary, id)),
                 }
-                let mut collections = vec![];
-                collections.push(match record.read_status {
-                    ReadStatus::ToRead => "Watchlist".to_owned(),
-                    ReadStatus::CurrentlyReading => "In Progress".to_owned(),
-                    ReadStatus::Other(s) => s.to_case(Case::Title),
-                });
-                if let Some(t) = record.tags {
-                    collections.extend(t.split(", ").map(|d| d.to_case(Case::Title)))
+            }
+            // DEV: Previews do not look up the ISBN, so the book only has its title
+            None => Some((MediaSource::GoogleBooks, String::new())),
+        };
+        let Some((source, identifier)) = found else {
+            failed_items.push(ImportFailedItem::new(
+                Some(lot),
+                ImportFailStep::InputTransformation,
+                record.title,
+                Some(format!(
+                    "Could not convert ISBN: {} to Google Books or Openlibrary ID",
+                    isbn
+                )),
+            ));
+            continue;
+        };
+        let mut seen_history = match record.dates_read.as_deref().map(parse_dates_read) {
+            Some(Some(seen)) if !seen.is_empty() => seen,
+            Some(None) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    record.title,
+                    Some(format!(
+                        "Could not parse dates read: {}",
+                        record.dates_read.unwrap_or_default()
+                    )),
+                ));
+                continue;
+            }
+            _ => {
+                let mut seen_history = vec![];
+                if record.read_count > 0 {
+                    seen_history.push(ImportOrExportMediaItemSeen {
+                        times: Some(record.read_count.try_into().unwrap()),
+                        ..Default::default()
+                    });
                 }
-                media.push(ImportOrExportMediaItem {
-                    source_id: record.title.clone(),
-                    lot,
-                    source,
-                    identifier: "".to_string(),
-                    internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
-                        identifier,
-                        title: record.title,
-                    }),
-                    seen_history,
-                    reviews: vec![ImportOrExportItemRating {
-                        rating: record
-                            .rating
-                            // DEV: Rates items out of 10
-                            .map(|d| d.saturating_mul(dec!(10))),
-                        review: record.review.map(|r| ImportOrExportItemReview {
-                            date: None,
-                            spoiler: Some(false),
-                            text: Some(r),
-                            visibility: None,
+                if let Some(read_at) = record.last_date_read.as_deref().and_then(parse_date) {
+                    match seen_history.first_mut() {
+                        Some(s) => s.ended_on = Some(read_at),
+                        None => seen_history.push(ImportOrExportMediaItemSeen {
+                            ended_on: Some(read_at),
+                            ..Default::default()
                         }),
-                        ..Default::default()
-                    }],
-                    collections,
-                    monitored: None,
-                })
-            } else {
-                failed_items.push(ImportFailedItem {
-                    lot: Some(lot),
-                    step: ImportFailStep::InputTransformation,
-                    identifier: record.title,
-                    error: Some(format!(
-                        "Could not convert ISBN: {} to Openlibrary ID",
-                        isbn
-                    )),
-                })
+                    }
+                }
+                seen_history
+            }
+        };
+        let mut collections = vec![];
+        let mut status = None;
+        match record.read_status {
+            ReadStatus::Read => {}
+            ReadStatus::DidNotFinish => status = Some(ImportOrExportItemStatus::Dropped),
+            ReadStatus::ToRead => collections.push(DefaultCollection::Watchlist.to_string()),
+            ReadStatus::CurrentlyReading => {
+                collections.push(DefaultCollection::InProgress.to_string());
+                mark_current_seen_in_progress(&mut seen_history);
             }
-        } else {
-            failed_items.push(ImportFailedItem {
-                lot: Some(lot),
-                step: ImportFailStep::InputTransformation,
-                identifier: record.title,
-                error: Some("No ISBN found".to_owned()),
+            ReadStatus::Other(s) => collections.push(s.to_case(Case::Title)),
+        };
+        let tags = [record.tags, record.moods]
+            .into_iter()
+            .flatten()
+            .flat_map(|t| {
+                t.split(',')
+                    .map(|d| d.trim())
+                    .filter(|d| !d.is_empty())
+                    .map(|d| d.to_case(Case::Title))
+                    .collect_vec()
             })
+            .unique()
+            .collect_vec();
+        let rating = record
+            .rating
+            .filter(|r| *r > dec!(0))
+            .map(|d| RATING_SCALE.normalize(d));
+        let review =
+            record
+                .review
+                .filter(|r| !r.trim().is_empty())
+                .map(|r| ImportOrExportItemReview {
+                    date: None,
+                    spoiler: Some(false),
+                    text: Some(r),
+                    visibility: None,
+                });
+        let mut reviews = vec![];
+        if review.is_some() || rating.is_some() {
+            reviews.push(ImportOrExportItemRating {
+                review,
+                rating,
+                ..Default::default()
+            });
         }
+        media.push(ImportOrExportMediaItem {
+            source_id: record.title.clone(),
+            lot,
+            source,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title: record.title,
+            }),
+            seen_history,
+            reviews,
+            collections,
+            added_on: record.date_added.as_deref().and_then(parse_date),
+            tags,
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status,
+        });
     }
     Ok(ImportResult {
         media,
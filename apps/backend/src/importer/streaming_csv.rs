@@ -0,0 +1,410 @@
+use std::{collections::HashMap, fs};
+
+use async_graphql::{Error, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use csv::{Reader, StringRecord};
+use database::{MediaSource, MetadataLot};
+use itertools::Itertools;
+use rs_utils::convert_naive_to_utc;
+use sea_orm::prelude::DateTimeUtc;
+
+use crate::{
+    importer::{
+        find_by_exact_title, normalize_title, DeployStreamingCsvImportInput, ImportFailStep,
+        ImportFailedItem, ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+        StreamingService,
+    },
+    miscellaneous::resolver::Provider,
+    models::media::{ImportOrExportMediaItemSeen, ShowSpecifics},
+};
+
+// DEV: The format of the dates depends on the country of the account, for eg:
+// "1/31/24" in the US and "31/01/2024" in the UK
+const DATE_FORMATS: [&str; 5] = ["%m/%d/%y", "%d/%m/%Y", "%m/%d/%Y", "%d.%m.%y", "%Y-%m-%d"];
+const DATE_TIME_FORMATS: [&str; 3] = ["%Y-%m-%d %H:%M:%S", "%m/%d/%Y %H:%M:%S", "%m/%d/%Y %H:%M"];
+
+/// The columns of the viewing activity export of a service.
+#[derive(Debug)]
+struct ColumnProfile {
+    /// The title of the show when the row is an episode, otherwise of the movie.
+    title: &'static str,
+    date: &'static str,
+    /// The name of the episode, if the service has a separate column for it.
+    episode_name: Option<&'static str>,
+    season_number: Option<&'static str>,
+    episode_number: Option<&'static str>,
+    /// Whether episodes are written in the title as "Show: Season 1: Episode Name".
+    combined_title: bool,
+}
+
+impl ColumnProfile {
+    fn of(service: StreamingService) -> Self {
+        match service {
+            StreamingService::Disney => Self {
+                title: "Series Title",
+                date: "Date Watched",
+                episode_name: Some("Program Title"),
+                season_number: Some("Season Number"),
+                episode_number: Some("Episode Number"),
+                combined_title: false,
+            },
+            StreamingService::Hulu => Self {
+                title: "Series Name",
+                date: "Last Watched",
+                episode_name: Some("Episode Name"),
+                season_number: Some("Season"),
+                episode_number: Some("Episode"),
+                combined_title: false,
+            },
+            StreamingService::Netflix => Self {
+                title: "Title",
+                date: "Date",
+                episode_name: None,
+                season_number: None,
+                episode_number: None,
+                combined_title: true,
+            },
+            StreamingService::Prime => Self {
+                title: "Title",
+                date: "Date Watched",
+                episode_name: Some("Episode Title"),
+                season_number: None,
+                episode_number: None,
+                combined_title: false,
+            },
+        }
+    }
+}
+
+/// The positions of the columns of a profile in the header of the file.
+#[derive(Debug)]
+struct Columns {
+    title: usize,
+    date: usize,
+    episode_name: Option<usize>,
+    season_number: Option<usize>,
+    episode_number: Option<usize>,
+    combined_title: bool,
+}
+
+impl Columns {
+    fn new(service: StreamingService, headers: &StringRecord) -> Result<Self> {
+        let profile = ColumnProfile::of(service);
+        let position = |name: &str| headers.iter().position(|h| h.trim() == name);
+        let required = |name: &str| {
+            position(name).ok_or_else(|| {
+                Error::new(format!(
+                    "The file does not have a {name:?} column, is it an export from {service:?}?"
+                ))
+            })
+        };
+        Ok(Self {
+            title: required(profile.title)?,
+            date: required(profile.date)?,
+            episode_name: profile.episode_name.and_then(position),
+            season_number: profile.season_number.and_then(position),
+            episode_number: profile.episode_number.and_then(position),
+            combined_title: profile.combined_title,
+        })
+    }
+
+    fn parse(&self, record: &StringRecord) -> Option<(Watched, Option<DateTimeUtc>)> {
+        let cell = |idx: Option<usize>| {
+            idx.and_then(|i| record.get(i))
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+        };
+        let watched_on = cell(Some(self.date)).and_then(parse_date);
+        let name = cell(self.episode_name).map(String::from);
+        // DEV: Some services leave the show empty for movies and put their
+        // title in the column of the episode name
+        let Some(title) = cell(Some(self.title)) else {
+            return name.map(|n| (Watched::Movie(n), watched_on));
+        };
+        if self.combined_title {
+            return Some((parse_title(title), watched_on));
+        }
+        let season = cell(self.season_number).and_then(|s| s.parse().ok());
+        let number = cell(self.episode_number).and_then(|e| e.parse().ok());
+        let watched = if name.is_none() && number.is_none() {
+            Watched::Movie(title.to_owned())
+        } else {
+            Watched::Episode(
+                title.to_owned(),
+                Episode {
+                    season,
+                    number,
+                    name,
+                },
+            )
+        };
+        Some((watched, watched_on))
+    }
+}
+
+/// An episode of a show, as written in a row.
+#[derive(Debug)]
+struct Episode {
+    season: Option<i32>,
+    number: Option<i32>,
+    name: Option<String>,
+}
+
+impl Episode {
+    fn describe(&self, show: &str) -> String {
+        let season = self
+            .season
+            .map(|s| format!(": Season {s}"))
+            .unwrap_or_default();
+        match (&self.name, self.number) {
+            (Some(name), _) => format!("{show}{season}: {name}"),
+            (None, Some(number)) => format!("{show}{season}: Episode {number}"),
+            (None, None) => format!("{show}{season}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Watched {
+    Movie(String),
+    Episode(String, Episode),
+}
+
+fn parse_date(date: &str) -> Option<DateTimeUtc> {
+    DATE_FORMATS
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(date, f).ok())
+        .map(convert_naive_to_utc)
+        .or_else(|| {
+            DATE_TIME_FORMATS
+                .iter()
+                .find_map(|f| NaiveDateTime::parse_from_str(date, f).ok())
+                .map(|d| d.and_utc())
+        })
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(date)
+                .ok()
+                .map(|d| d.with_timezone(&Utc))
+        })
+}
+
+/// The season number from the part of a title that names it, for eg: "Season 2"
+/// or "Limited Series". Exports in other languages use their own words, so any
+/// short part that ends with a number is accepted.
+fn parse_season(part: &str) -> Option<i32> {
+    let part = part.trim();
+    if ["Limited Series", "Miniseries"].contains(&part) {
+        return Some(1);
+    }
+    let (words, number) = part.rsplit_once(' ')?;
+    if words.split_whitespace().count() > 2 {
+        return None;
+    }
+    number.parse().ok()
+}
+
+/// Episodes are written as "Show: Season 1: Episode Name", where the name can
+/// contain colons as well. Everything else is taken to be a movie.
+fn parse_title(title: &str) -> Watched {
+    let parts = title.split(": ").collect_vec();
+    if let [show, season, name @ ..] = parts.as_slice() {
+        if let (Some(season), false) = (parse_season(season), name.is_empty()) {
+            return Watched::Episode(
+                show.trim().to_owned(),
+                Episode {
+                    season: Some(season),
+                    number: None,
+                    name: Some(name.join(": ")),
+                },
+            );
+        }
+    }
+    Watched::Movie(title.trim().to_owned())
+}
+
+/// Find the episode with the given name, first in the season it was listed under
+/// and then in the others since services sometimes split seasons into parts.
+fn find_episode(specifics: &ShowSpecifics, season: Option<i32>, name: &str) -> Option<(i32, i32)> {
+    let name = normalize_title(name);
+    specifics
+        .seasons
+        .iter()
+        .sorted_by_key(|s| Some(s.season_number) != season)
+        .find_map(|s| {
+            s.episodes
+                .iter()
+                .find(|e| normalize_title(&e.name) == name)
+                .map(|e| (s.season_number, e.episode_number))
+        })
+}
+
+fn media_item(
+    lot: MetadataLot,
+    identifier: String,
+    title: String,
+    seen_history: Vec<ImportOrExportMediaItemSeen>,
+) -> ImportOrExportMediaItem {
+    ImportOrExportMediaItem {
+        source_id: title.clone(),
+        lot,
+        source: MediaSource::Tmdb,
+        identifier: "".to_string(),
+        internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails { identifier, title }),
+        seen_history,
+        reviews: vec![],
+        collections: vec![],
+        added_on: None,
+        tags: vec![],
+        images: vec![],
+        monitored: None,
+        matched_by_title: true,
+        status: None,
+    }
+}
+
+pub async fn import(
+    input: DeployStreamingCsvImportInput,
+    movie_service: Option<&Provider>,
+    show_service: Option<&Provider>,
+) -> Result<ImportResult> {
+    let mut media = vec![];
+    let mut failed_items = vec![];
+    let export = fs::read_to_string(&input.csv)?;
+    let mut reader = Reader::from_reader(export.trim_start_matches('\u{feff}').as_bytes());
+    let columns = Columns::new(input.service, reader.headers()?)?;
+    let mut movies: HashMap<String, Vec<Option<DateTimeUtc>>> = HashMap::new();
+    let mut shows: HashMap<String, Vec<(Episode, Option<DateTimeUtc>)>> = HashMap::new();
+    for (idx, result) in reader.records().enumerate() {
+        let parsed = result.map_err(|e| e.to_string()).and_then(|r| {
+            columns
+                .parse(&r)
+                .ok_or_else(|| "The row has no title".to_owned())
+        });
+        let (watched, watched_on) = match parsed {
+            Ok(p) => p,
+            Err(error) => {
+                failed_items.push(ImportFailedItem::new(
+                    None,
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(error),
+                ));
+                continue;
+            }
+        };
+        match watched {
+            Watched::Movie(title) => movies.entry(title).or_default().push(watched_on),
+            Watched::Episode(show, episode) => {
+                shows.entry(show).or_default().push((episode, watched_on))
+            }
+        }
+    }
+
+    let movies_total = movies.len();
+    let total = movies_total + shows.len();
+    for (idx, (title, dates)) in movies.into_iter().sorted().enumerate() {
+        tracing::debug!("Searching for {title:?} ({idx}/{total})");
+        let identifier = match find_by_exact_title(movie_service, MetadataLot::Movie, &title).await
+        {
+            Ok(identifier) => identifier,
+            Err(failed) => {
+                failed_items.push(failed);
+                continue;
+            }
+        };
+        let seen_history = dates
+            .into_iter()
+            .map(|ended_on| ImportOrExportMediaItemSeen {
+                ended_on,
+                ..Default::default()
+            })
+            .collect();
+        media.push(media_item(
+            MetadataLot::Movie,
+            identifier,
+            title,
+            seen_history,
+        ));
+    }
+
+    let lot = MetadataLot::Show;
+    for (idx, (title, episodes)) in shows
+        .into_iter()
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .enumerate()
+    {
+        tracing::debug!(
+            "Searching for {title:?} ({idx}/{total})",
+            idx = movies_total + idx
+        );
+        let identifier = match find_by_exact_title(show_service, lot, &title).await {
+            Ok(identifier) => identifier,
+            Err(failed) => {
+                failed_items.push(failed);
+                continue;
+            }
+        };
+        // DEV: The details are only needed to match episodes by their name
+        let specifics = match show_service {
+            Some(show_service)
+                if !episodes
+                    .iter()
+                    .all(|(e, _)| e.season.is_some() && e.number.is_some()) =>
+            {
+                match show_service.metadata_details(&identifier).await {
+                    Ok(details) => details.show_specifics.unwrap_or_default(),
+                    Err(e) => {
+                        failed_items.push(ImportFailedItem::new(
+                            Some(lot),
+                            ImportFailStep::MediaDetailsFromProvider,
+                            title,
+                            Some(e.to_string()),
+                        ));
+                        continue;
+                    }
+                }
+            }
+            _ => ShowSpecifics::default(),
+        };
+        let mut seen_history = vec![];
+        for (episode, watched_on) in episodes {
+            let found = match (episode.season, episode.number, &episode.name) {
+                (Some(season), Some(number), _) => Some((season, number)),
+                (season, _, Some(name)) => find_episode(&specifics, season, name),
+                _ => None,
+            };
+            match found {
+                Some((season, number)) => seen_history.push(ImportOrExportMediaItemSeen {
+                    ended_on: watched_on,
+                    show_season_number: Some(season),
+                    show_episode_number: Some(number),
+                    ..Default::default()
+                }),
+                // DEV: Previews do not match episodes by their name, so they are
+                // counted without their number
+                None if show_service.is_none() => seen_history.push(ImportOrExportMediaItemSeen {
+                    ended_on: watched_on,
+                    ..Default::default()
+                }),
+                // DEV: The show is still imported so that it is in the library,
+                // only the episode has to be marked as seen manually
+                None => failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    episode.describe(&title),
+                    Some("Could not find this episode of the show".to_owned()),
+                )),
+            }
+        }
+        media.push(media_item(lot, identifier, title, seen_history));
+    }
+
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
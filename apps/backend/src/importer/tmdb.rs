@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use async_graphql::Result;
+use database::{MediaSource, MetadataLot};
+use http_types::mime;
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use surf::{http::headers::ACCEPT, Client};
+
+use crate::{
+    importer::{
+        DeployTmdbImportInput, ImportOrExportItemIdentifier, ImportOrExportMediaItem,
+        ImportRatingScale, ImportResult,
+    },
+    miscellaneous::DefaultCollection,
+    models::media::{CreateOrUpdateCollectionInput, ImportOrExportItemRating},
+    utils::get_base_http_client,
+};
+
+static URL: &str = "https://api.themoviedb.org/3/";
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Ten;
+
+#[derive(Debug, Deserialize)]
+struct Account {
+    id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Item {
+    id: i32,
+    #[serde(alias = "name")]
+    title: String,
+    rating: Option<Decimal>,
+}
+
+#[derive(Debug, Deserialize)]
+struct List {
+    id: i32,
+    name: String,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListItem {
+    id: i32,
+    media_type: String,
+    #[serde(alias = "name")]
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListDetails {
+    items: Vec<ListItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemsResponse<T> {
+    results: Vec<T>,
+    total_pages: i32,
+}
+
+async fn get<T: DeserializeOwned>(
+    client: &Client,
+    path: &str,
+    query: &serde_json::Value,
+) -> Result<T> {
+    let data = client
+        .get(path)
+        .query(query)
+        .unwrap()
+        .await
+        .map_err(|e| anyhow!(e))?
+        .body_json()
+        .await
+        .map_err(|e| anyhow!(e))?;
+    Ok(data)
+}
+
+async fn get_items<T: DeserializeOwned>(
+    client: &Client,
+    path: &str,
+    query: &serde_json::Value,
+) -> Result<Vec<T>> {
+    let mut items = vec![];
+    let mut page = 1;
+    loop {
+        let mut query = query.clone();
+        query["page"] = json!(page);
+        let response: ItemsResponse<T> = get(client, path, &query).await?;
+        items.extend(response.results);
+        if page >= response.total_pages {
+            break;
+        }
+        page += 1;
+    }
+    Ok(items)
+}
+
+fn media_entry(
+    media: &mut HashMap<(MetadataLot, i32), ImportOrExportMediaItem>,
+    lot: MetadataLot,
+    id: i32,
+    title: String,
+) -> &mut ImportOrExportMediaItem {
+    media
+        .entry((lot, id))
+        .or_insert_with(|| ImportOrExportMediaItem {
+            source_id: title.clone(),
+            lot,
+            source: MediaSource::Tmdb,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier: id.to_string(),
+                title,
+            }),
+            seen_history: vec![],
+            reviews: vec![],
+            collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        })
+}
+
+pub async fn import(input: DeployTmdbImportInput) -> Result<ImportResult> {
+    let client = get_base_http_client(URL, vec![(ACCEPT, mime::JSON.to_string())]);
+    let query = json!({ "api_key": input.api_key, "session_id": input.session_id });
+    let account: Account = get(&client, "account", &query).await?;
+
+    // DEV: An item can be both rated and in the watchlist, so the lists are
+    // merged by their TMDB id
+    let mut media: HashMap<(MetadataLot, i32), ImportOrExportMediaItem> = HashMap::new();
+    for (lot, kind) in [(MetadataLot::Movie, "movies"), (MetadataLot::Show, "tv")] {
+        for list in ["rated", "watchlist"] {
+            let path = format!("account/{}/{}/{}", account.id, list, kind);
+            let items: Vec<Item> = get_items(&client, &path, &query).await?;
+            tracing::debug!("Got {} {} {} from TMDB", items.len(), list, kind);
+            for item in items {
+                let entry = media_entry(&mut media, lot, item.id, item.title);
+                if list == "watchlist" {
+                    entry
+                        .collections
+                        .push(DefaultCollection::Watchlist.to_string());
+                } else if let Some(rating) = item.rating {
+                    entry.reviews.push(ImportOrExportItemRating {
+                        rating: Some(RATING_SCALE.normalize(rating)),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+    }
+
+    let lists: Vec<List> =
+        get_items(&client, &format!("account/{}/lists", account.id), &query).await?;
+    let mut collections = vec![];
+    for list in lists {
+        let details: ListDetails = get(&client, &format!("list/{}", list.id), &query).await?;
+        tracing::debug!("Got {} items in list {:?}", details.items.len(), list.name);
+        for item in details.items {
+            let lot = match item.media_type.as_str() {
+                "movie" => MetadataLot::Movie,
+                "tv" => MetadataLot::Show,
+                _ => continue,
+            };
+            media_entry(&mut media, lot, item.id, item.title)
+                .collections
+                .push(list.name.clone());
+        }
+        collections.push(CreateOrUpdateCollectionInput {
+            name: list.name,
+            description: list.description.filter(|d| !d.trim().is_empty()),
+            ..Default::default()
+        });
+    }
+
+    Ok(ImportResult {
+        media: media.into_values().collect(),
+        failed_items: vec![],
+        people: vec![],
+        workouts: vec![],
+        collections,
+        measurements: vec![],
+    })
+}
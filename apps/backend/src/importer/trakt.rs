@@ -1,10 +1,9 @@
 use async_graphql::Result;
 use convert_case::{Case, Casing};
-use database::{MediaSource, MetadataLot};
+use database::{MediaSource, MetadataLot, Visibility};
 use http_types::mime;
 use itertools::Itertools;
 use rust_decimal::Decimal;
-use rust_decimal_macros::dec;
 use sea_orm::prelude::DateTimeUtc;
 use serde::{Deserialize, Serialize};
 use surf::http::headers::CONTENT_TYPE;
@@ -12,7 +11,7 @@ use surf::http::headers::CONTENT_TYPE;
 use crate::{
     importer::{
         DeployTraktImportInput, ImportFailStep, ImportFailedItem, ImportOrExportItemIdentifier,
-        ImportOrExportMediaItem, ImportResult,
+        ImportOrExportMediaItem, ImportRatingScale, ImportResult,
     },
     models::media::{
         CreateOrUpdateCollectionInput, ImportOrExportItemRating, ImportOrExportItemReview,
@@ -21,6 +20,8 @@ use crate::{
     utils::get_base_http_client,
 };
 
+const RATING_SCALE: ImportRatingScale = ImportRatingScale::Ten;
+
 const API_URL: &str = "https://api.trakt.tv";
 const CLIENT_ID: &str = "b3d93fd4c53d78d61b18e0f0bf7ad5153de323788dbc0be1a3627205a36e89f5";
 const API_VERSION: &str = "2";
@@ -53,6 +54,9 @@ struct ListItemResponse {
 struct ListResponse {
     name: String,
     description: Option<String>,
+    /// One of "private", "link", "friends" or "public".
+    #[serde(default)]
+    privacy: Option<String>,
     ids: Id,
     #[serde(default)]
     items: Vec<ListItemResponse>,
@@ -62,11 +66,12 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
     let mut media_items = vec![];
     let mut failed_items = vec![];
 
+    let client_id = input.client_id.unwrap_or_else(|| CLIENT_ID.to_owned());
     let client = get_base_http_client(
         &format!("{}/users/{}/", API_URL, input.username),
         vec![
             (CONTENT_TYPE, mime::JSON.to_string().as_str()),
-            ("trakt-api-key".into(), CLIENT_ID),
+            ("trakt-api-key".into(), client_id.as_str()),
             ("trakt-api-version".into(), API_VERSION),
         ],
     );
@@ -87,6 +92,7 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
         lists.push(ListResponse {
             name: list.to_owned(),
             description: None,
+            privacy: None,
             ids: Id {
                 trakt: 0,
                 tmdb: None,
@@ -118,31 +124,34 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
                     Some(s.to_owned())
                 }
             }),
+            visibility: l.privacy.as_deref().map(|p| match p {
+                "public" => Visibility::Public,
+                _ => Visibility::Private,
+            }),
             ..Default::default()
         })
         .collect_vec();
 
-    for typ in ["movies", "shows"] {
+    for typ in ["movies", "shows", "episodes"] {
         let mut rsp = client.get(format!("ratings/{}", typ)).await.unwrap();
         let ratings: Vec<ListItemResponse> = rsp.body_json().await.unwrap();
         for item in ratings.iter() {
             match process_item(item) {
                 Ok(mut d) => {
                     d.reviews.push(ImportOrExportItemRating {
-                        rating: item
-                            .rating
-                            // DEV: Rates items out of 10
-                            .map(|e| e * dec!(10)),
+                        rating: item.rating.map(|r| RATING_SCALE.normalize(r)),
                         review: Some(ImportOrExportItemReview {
                             spoiler: Some(false),
                             text: None,
                             date: item.rated_at,
                             visibility: None,
                         }),
+                        show_season_number: item.episode.as_ref().and_then(|e| e.season),
+                        show_episode_number: item.episode.as_ref().and_then(|e| e.number),
                         ..Default::default()
                     });
                     if let Some(a) = media_items.iter_mut().find(|i| i.source_id == d.source_id) {
-                        a.reviews = d.reviews;
+                        a.reviews.extend(d.reviews);
                     } else {
                         media_items.push(d)
                     }
@@ -190,15 +199,15 @@ pub async fn import(input: DeployTraktImportInput) -> Result<ImportResult> {
                 if d.lot == MetadataLot::Show
                     && (show_season_number.is_none() || show_episode_number.is_none())
                 {
-                    failed_items.push(ImportFailedItem {
-                        lot: Some(d.lot),
-                        step: ImportFailStep::ItemDetailsFromSource,
-                        identifier: "".to_owned(),
-                        error: Some(
+                    failed_items.push(ImportFailedItem::new(
+                        Some(d.lot),
+                        ImportFailStep::ItemDetailsFromSource,
+                        "".to_owned(),
+                        Some(
                             "Item is a show but does not have a season or episode number"
                                 .to_owned(),
                         ),
-                    });
+                    ));
                     continue;
                 }
                 d.seen_history.push(ImportOrExportMediaItemSeen {
@@ -234,12 +243,12 @@ fn process_item(
     } else if let Some(d) = i.show.as_ref() {
         (d.ids.trakt, d.ids.tmdb, MetadataLot::Show, d.title.clone())
     } else {
-        return Err(ImportFailedItem {
-            lot: None,
-            step: ImportFailStep::ItemDetailsFromSource,
-            identifier: "".to_owned(),
-            error: Some("Item is neither a movie or a show".to_owned()),
-        });
+        return Err(ImportFailedItem::new(
+            None,
+            ImportFailStep::ItemDetailsFromSource,
+            "".to_owned(),
+            Some("Item is neither a movie or a show".to_owned()),
+        ));
     };
     let title = title.unwrap_or_default();
     match identifier {
@@ -255,13 +264,18 @@ fn process_item(
             seen_history: vec![],
             reviews: vec![],
             collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
             monitored: None,
+            matched_by_title: false,
+            status: None,
         }),
-        None => Err(ImportFailedItem {
-            lot: None,
-            step: ImportFailStep::ItemDetailsFromSource,
-            identifier: "".to_owned(),
-            error: Some("Item does not have an associated TMDB id".to_owned()),
-        }),
+        None => Err(ImportFailedItem::new(
+            None,
+            ImportFailStep::ItemDetailsFromSource,
+            "".to_owned(),
+            Some("Item does not have an associated TMDB id".to_owned()),
+        )),
     }
 }
@@ -0,0 +1,184 @@
+use std::{collections::HashMap, fs::File, io::Read};
+
+use async_graphql::{Error, Result};
+use chrono::NaiveDateTime;
+use chrono_tz::Tz;
+use csv::Reader;
+use database::{MediaSource, MetadataLot};
+use itertools::Itertools;
+use sea_orm::prelude::DateTimeUtc;
+use serde::Deserialize;
+use zip::ZipArchive;
+
+use crate::{
+    importer::{
+        local_to_utc, DeployTvTimeImportInput, ImportFailStep, ImportFailedItem,
+        ImportOrExportItemIdentifier, ImportOrExportMediaItem, ImportResult,
+    },
+    miscellaneous::resolver::RateLimited,
+    models::media::ImportOrExportMediaItemSeen,
+    providers::tmdb::NonMediaTmdbService,
+};
+
+// DEV: Older exports do not have the `-v2` suffix
+const RECORD_FILES: [&str; 2] = ["tracking-prod-records-v2.csv", "tracking-prod-records.csv"];
+
+#[derive(Debug, Deserialize)]
+struct Record {
+    #[serde(alias = "s_id")]
+    series_id: Option<String>,
+    #[serde(alias = "tv_show_name")]
+    series_name: Option<String>,
+    season_number: Option<i32>,
+    episode_number: Option<i32>,
+    #[serde(alias = "watched_at")]
+    created_at: Option<String>,
+}
+
+// DEV: Older exports have the dates in the local time of the user, newer ones in UTC
+fn parse_date(date: &str, timezone: Option<Tz>) -> Option<DateTimeUtc> {
+    let date = date.trim();
+    NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S")
+        .map(|d| local_to_utc(d, timezone))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.fZ").map(|d| d.and_utc())
+        })
+        .ok()
+}
+
+pub async fn import(
+    input: DeployTvTimeImportInput,
+    tmdb_service: Option<&RateLimited<NonMediaTmdbService>>,
+    timezone: Option<Tz>,
+) -> Result<ImportResult> {
+    let lot = MetadataLot::Show;
+    let mut archive = ZipArchive::new(File::open(&input.export_archive)?)?;
+    let mut contents = String::new();
+    for name in RECORD_FILES {
+        if let Ok(mut f) = archive.by_name(name) {
+            f.read_to_string(&mut contents)?;
+            break;
+        }
+    }
+    if contents.is_empty() {
+        return Err(Error::new(
+            "The export does not contain the tracking records of TV Time",
+        ));
+    }
+
+    let mut failed_items = vec![];
+    // DEV: Shows are keyed by their TVDB id and keep their name to report failures
+    let mut shows: HashMap<String, (String, Vec<ImportOrExportMediaItemSeen>)> = HashMap::new();
+    for (idx, result) in Reader::from_reader(contents.as_bytes())
+        .deserialize::<Record>()
+        .enumerate()
+    {
+        let record = match result {
+            Ok(r) => r,
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::InputTransformation,
+                    idx.to_string(),
+                    Some(format!("{e:#?}")),
+                ));
+                continue;
+            }
+        };
+        // DEV: Rows without an episode are follows and other events, not watches
+        let (Some(season), Some(episode)) = (record.season_number, record.episode_number) else {
+            continue;
+        };
+        let name = record.series_name.unwrap_or_default();
+        let Some(series_id) = record.series_id.filter(|i| !i.trim().is_empty()) else {
+            failed_items.push(ImportFailedItem::new(
+                Some(lot),
+                ImportFailStep::ItemDetailsFromSource,
+                name,
+                Some("Row does not reference a series".to_owned()),
+            ));
+            continue;
+        };
+        shows
+            .entry(series_id.trim().to_owned())
+            .or_insert_with(|| (name, vec![]))
+            .1
+            .push(ImportOrExportMediaItemSeen {
+                ended_on: record
+                    .created_at
+                    .as_deref()
+                    .and_then(|d| parse_date(d, timezone)),
+                show_season_number: Some(season),
+                show_episode_number: Some(episode),
+                ..Default::default()
+            });
+    }
+
+    let shows = shows
+        .into_iter()
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .collect_vec();
+    let total = shows.len();
+    let mut media = vec![];
+    for (idx, (series_id, (name, seen_history))) in shows.into_iter().enumerate() {
+        tracing::debug!("Getting details for {name:?} ({idx}/{total})");
+        let found = match tmdb_service {
+            Some(tmdb_service) => {
+                tmdb_service
+                    .request(|s| s.find_by_external_id(&series_id, "tvdb_id"))
+                    .await
+            }
+            // DEV: Previews do not look up the series, so the show only has its name
+            None => Ok((lot, String::new())),
+        };
+        let identifier = match found {
+            Ok((MetadataLot::Show, identifier)) => identifier,
+            Ok(_) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::MediaDetailsFromProvider,
+                    name,
+                    Some(format!("No show found on TMDB for TVDB id {series_id}")),
+                ));
+                continue;
+            }
+            Err(e) => {
+                failed_items.push(ImportFailedItem::new(
+                    Some(lot),
+                    ImportFailStep::MediaDetailsFromProvider,
+                    name,
+                    Some(e.to_string()),
+                ));
+                continue;
+            }
+        };
+        media.push(ImportOrExportMediaItem {
+            source_id: series_id,
+            lot,
+            source: MediaSource::Tmdb,
+            identifier: "".to_string(),
+            internal_identifier: Some(ImportOrExportItemIdentifier::NeedsDetails {
+                identifier,
+                title: name,
+            }),
+            seen_history,
+            reviews: vec![],
+            collections: vec![],
+            added_on: None,
+            tags: vec![],
+            images: vec![],
+            monitored: None,
+            matched_by_title: false,
+            status: None,
+        });
+    }
+
+    Ok(ImportResult {
+        media,
+        failed_items,
+        people: vec![],
+        workouts: vec![],
+        collections: vec![],
+        measurements: vec![],
+    })
+}
@@ -41,16 +41,17 @@ use utils::TEMP_DIR;
 
 use crate::{
     background::{
-        media_jobs, perform_application_job, perform_core_application_job, user_jobs,
-        yank_integrations_data,
+        media_jobs, perform_application_job, perform_core_application_job, perform_import_job,
+        user_jobs, yank_integrations_data,
     },
     entities::prelude::Exercise,
     graphql::get_schema,
     models::CompleteExport,
     routes::{
-        config_handler, graphql_handler, graphql_playground, integration_webhook, upload_file,
+        config_handler, graphql_handler, graphql_playground, graphql_subscription_handler,
+        integration_webhook, upload_file,
     },
-    utils::{create_app_services, BASE_DIR, VERSION},
+    utils::{create_app_services, set_user_agent, BASE_DIR, VERSION},
 };
 
 mod background;
@@ -84,6 +85,7 @@ async fn main() -> Result<()> {
     tracing::info!("Running version: {}", VERSION);
 
     let config = Arc::new(config::load_app_config()?);
+    set_user_agent(&config.server.user_agent);
     let cors_origins = config
         .server
         .cors_origins
@@ -91,6 +93,7 @@ async fn main() -> Result<()> {
         .map(|f| f.parse().unwrap())
         .collect_vec();
     let rate_limit_num = config.scheduler.rate_limit_num;
+    let import_workers = config.importer.workers.max(1);
     let user_cleanup_every = config.scheduler.user_cleanup_every;
     let pull_every = config.integration.pull_every;
     let max_file_size = config.server.max_file_size;
@@ -143,6 +146,7 @@ async fn main() -> Result<()> {
 
     let perform_application_job_storage = create_storage(pool.clone()).await;
     let perform_core_application_job_storage = create_storage(pool.clone()).await;
+    let perform_import_job_storage = create_storage(pool.clone()).await;
 
     let tz: chrono_tz::Tz = env::var("TZ")
         .map(|s| s.parse().unwrap())
@@ -155,6 +159,7 @@ async fn main() -> Result<()> {
         config,
         &perform_application_job_storage,
         &perform_core_application_job_storage,
+        &perform_import_job_storage,
         tz,
     )
     .await;
@@ -221,6 +226,7 @@ async fn main() -> Result<()> {
         .nest("/webhooks", webhook_routes)
         .route("/config", get(config_handler))
         .route("/graphql", gql)
+        .route("/graphql/ws", get(graphql_subscription_handler))
         .route("/upload", post(upload_file))
         .layer(Extension(app_services.config.clone()))
         .layer(Extension(app_services.media_service.clone()))
@@ -305,13 +311,19 @@ async fn main() -> Result<()> {
                         rate_limit_num,
                         Duration::new(5, 0),
                     ))
-                    .layer(ApalisExtension(importer_service_1.clone()))
                     .layer(ApalisExtension(exporter_service_1.clone()))
                     .layer(ApalisExtension(media_service_4.clone()))
                     .layer(ApalisExtension(exercise_service_1.clone()))
                     .with_storage(perform_application_job_storage.clone())
                     .build_fn(perform_application_job)
             })
+            .register_with_count(import_workers, move |c| {
+                WorkerBuilder::new(format!("perform_import_job-{c}"))
+                    .layer(ApalisTraceLayer::new())
+                    .layer(ApalisExtension(importer_service_1.clone()))
+                    .with_storage(perform_import_job_storage.clone())
+                    .build_fn(perform_import_job)
+            })
             .run()
             .await
             .unwrap();
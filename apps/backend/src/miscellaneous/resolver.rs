@@ -1,9 +1,11 @@
 use std::{
     collections::{HashMap, HashSet},
     fs::File,
+    future::Future,
     iter::zip,
     str::FromStr,
-    sync::Arc,
+    sync::{Arc, Mutex},
+    time::{Duration as StdDuration, Instant},
 };
 
 use anyhow::anyhow;
@@ -12,6 +14,7 @@ use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use async_graphql::{
     Context, Enum, Error, InputObject, InputType, Object, OneofObject, Result, SimpleObject, Union,
 };
+use async_trait::async_trait;
 use chrono::{Datelike, Days, Duration as ChronoDuration, NaiveDate, Utc};
 use database::{
     AliasedExercise, AliasedMetadata, AliasedMetadataGroup, AliasedMetadataToGenre, AliasedPerson,
@@ -35,7 +38,7 @@ use sea_orm::{
     prelude::DateTimeUtc, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait,
     DatabaseBackend, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult,
     ItemsAndPagesNumber, Iterable, JoinType, ModelTrait, Order, PaginatorTrait, QueryFilter,
-    QueryOrder, QuerySelect, QueryTrait, RelationTrait, Statement,
+    QueryOrder, QuerySelect, QueryTrait, RelationTrait, Statement, TransactionTrait,
 };
 use sea_query::{
     extension::postgres::PgExpr, Alias, Asterisk, Cond, Condition, Expr, Func, NullOrdering,
@@ -50,8 +53,9 @@ use crate::{
     background::{ApplicationJob, CoreApplicationJob},
     entities::{
         calendar_event, collection, collection_to_entity, exercise, genre, metadata,
-        metadata_group, metadata_to_genre, metadata_to_metadata, metadata_to_metadata_group,
-        metadata_to_person, person,
+        metadata_group::{self, MetadataGroupWithoutId},
+        metadata_to_genre, metadata_to_metadata, metadata_to_metadata_group, metadata_to_person,
+        person,
         prelude::{
             CalendarEvent, Collection, CollectionToEntity, Exercise, Genre, Metadata,
             MetadataGroup, MetadataToGenre, MetadataToMetadata, MetadataToMetadataGroup,
@@ -74,20 +78,20 @@ use crate::{
         media::{
             AnimeSpecifics, AudioBookSpecifics, BookSpecifics, CommitPersonInput,
             CreateOrUpdateCollectionInput, GenreListItem, ImportOrExportItemRating,
-            ImportOrExportItemReview, ImportOrExportItemReviewComment, ImportOrExportMediaItem,
-            ImportOrExportMediaItemSeen, ImportOrExportPersonItem, MangaSpecifics,
-            MediaCreatorSearchItem, MediaDetails, MediaListItem, MetadataFreeCreator,
-            MetadataGroupListItem, MetadataImage, MetadataImageForMediaDetails, MetadataImageLot,
-            MetadataSearchItem, MetadataSearchItemResponse, MetadataSearchItemWithLot,
-            MetadataVideo, MetadataVideoSource, MovieSpecifics, PartialMetadata,
-            PartialMetadataPerson, PartialMetadataWithoutId, PeopleSearchItem,
-            PersonSourceSpecifics, PodcastSpecifics, PostReviewInput, ProgressUpdateError,
-            ProgressUpdateErrorVariant, ProgressUpdateInput, ProgressUpdateResultUnion,
-            PublicCollectionItem, ReviewPostedEvent, SeenAnimeExtraInformation,
-            SeenMangaExtraInformation, SeenPodcastExtraInformation, SeenShowExtraInformation,
-            ShowSpecifics, ToggleMediaMonitorInput, UserMediaOwnership, UserMediaReminder,
-            UserSummary, UserToMediaReason, VideoGameSpecifics, VisualNovelSpecifics,
-            WatchProvider,
+            ImportOrExportItemReview, ImportOrExportItemReviewComment, ImportOrExportItemStatus,
+            ImportOrExportMediaItem, ImportOrExportMediaItemSeen, ImportOrExportPersonItem,
+            MangaSpecifics, MediaCreatorSearchItem, MediaDetails, MediaListItem,
+            MetadataFreeCreator, MetadataGroupListItem, MetadataImage,
+            MetadataImageForMediaDetails, MetadataImageLot, MetadataPerson, MetadataSearchItem,
+            MetadataSearchItemResponse, MetadataSearchItemWithLot, MetadataVideo,
+            MetadataVideoSource, MovieSpecifics, PartialMetadata, PartialMetadataPerson,
+            PartialMetadataWithoutId, PeopleSearchItem, PersonSourceSpecifics, PodcastSpecifics,
+            PostReviewInput, ProgressUpdateError, ProgressUpdateErrorVariant, ProgressUpdateInput,
+            ProgressUpdateResultUnion, PublicCollectionItem, ReviewPostedEvent,
+            SeenAnimeExtraInformation, SeenMangaExtraInformation, SeenPodcastExtraInformation,
+            SeenShowExtraInformation, ShowSpecifics, ToggleMediaMonitorInput, UserMediaOwnership,
+            UserMediaReminder, UserSummary, UserToMediaReason, VideoGameSpecifics,
+            VisualNovelSpecifics, WatchProvider,
         },
         BackgroundJob, ChangeCollectionToEntityInput, EntityLot, IdAndNamedObject, IdObject,
         MediaStateChanged, SearchDetails, SearchInput, SearchResults, StoredUrl,
@@ -124,7 +128,111 @@ use crate::{
     },
 };
 
-type Provider = Box<(dyn MediaProvider + Send + Sync)>;
+pub type Provider = Box<(dyn MediaProvider + Send + Sync)>;
+
+/// Spaces out the requests made to the providers so that they do not go over the
+/// configured number of requests per second.
+pub struct ProviderRateLimiter {
+    config: Arc<config::AppConfig>,
+    /// The earliest time at which the next request can be made to each provider.
+    slots: Mutex<HashMap<MediaSource, Instant>>,
+}
+
+impl ProviderRateLimiter {
+    fn new(config: Arc<config::AppConfig>) -> Self {
+        Self {
+            config,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait until a request can be made to the provider. Requests are spaced
+    /// evenly, so concurrent callers are queued one after the other.
+    async fn wait(&self, source: MediaSource) {
+        let limit = match source {
+            MediaSource::Tmdb => self.config.media.tmdb_requests_per_second,
+            MediaSource::Igdb => self.config.media.igdb_requests_per_second,
+            _ => self.config.media.other_requests_per_second,
+        };
+        if limit == 0 {
+            return;
+        }
+        let interval = StdDuration::from_secs(1) / limit;
+        let wait = {
+            let mut slots = self.slots.lock().unwrap();
+            let now = Instant::now();
+            let slot = slots.get(&source).map_or(now, |s| (*s).max(now));
+            slots.insert(source, slot + interval);
+            slot - now
+        };
+        if !wait.is_zero() {
+            tracing::trace!("Waiting {wait:?} before making a request to {source}");
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// A provider service whose requests are limited by the rate limit of its source.
+pub struct RateLimited<T> {
+    service: T,
+    source: MediaSource,
+    limiter: Arc<ProviderRateLimiter>,
+}
+
+impl<T> RateLimited<T> {
+    /// Make a request to the provider once the rate limit allows it.
+    pub async fn request<'a, F: Future>(&'a self, request: impl FnOnce(&'a T) -> F) -> F::Output {
+        self.limiter.wait(self.source).await;
+        request(&self.service).await
+    }
+}
+
+#[async_trait]
+impl MediaProvider for RateLimited<Provider> {
+    async fn metadata_search(
+        &self,
+        query: &str,
+        page: Option<i32>,
+        display_nsfw: bool,
+    ) -> anyhow::Result<SearchResults<MetadataSearchItem>> {
+        self.request(|s| s.metadata_search(query, page, display_nsfw))
+            .await
+    }
+
+    async fn metadata_details(&self, identifier: &str) -> anyhow::Result<MediaDetails> {
+        self.request(|s| s.metadata_details(identifier)).await
+    }
+
+    async fn people_search(
+        &self,
+        query: &str,
+        page: Option<i32>,
+        source_specifics: &Option<PersonSourceSpecifics>,
+    ) -> anyhow::Result<SearchResults<PeopleSearchItem>> {
+        self.request(|s| s.people_search(query, page, source_specifics))
+            .await
+    }
+
+    async fn person_details(
+        &self,
+        identity: &str,
+        source_specifics: &Option<PersonSourceSpecifics>,
+    ) -> anyhow::Result<MetadataPerson> {
+        self.request(|s| s.person_details(identity, source_specifics))
+            .await
+    }
+
+    async fn metadata_group_details(
+        &self,
+        identifier: &str,
+    ) -> anyhow::Result<(MetadataGroupWithoutId, Vec<PartialMetadataWithoutId>)> {
+        self.request(|s| s.metadata_group_details(identifier)).await
+    }
+}
+
+/// The minimum number of seconds between two recalculations of the summary of
+/// a user.
+const SUMMARY_DEBOUNCE_SECONDS: i64 = 60;
 
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone)]
 struct CreateCustomMetadataInput {
@@ -231,10 +339,10 @@ struct UserInput {
 }
 
 #[derive(Debug, InputObject)]
-struct CommitMetadataInput {
-    lot: MetadataLot,
-    source: MediaSource,
-    identifier: String,
+pub struct CommitMetadataInput {
+    pub lot: MetadataLot,
+    pub source: MediaSource,
+    pub identifier: String,
 }
 
 #[derive(Enum, Clone, Debug, Copy, PartialEq, Eq)]
@@ -304,6 +412,7 @@ enum CollectionContentsSortBy {
     #[default]
     LastUpdatedOn,
     Date,
+    AddedOn,
 }
 
 #[derive(Debug, Serialize, Deserialize, InputObject, Clone, Default)]
@@ -1379,6 +1488,10 @@ pub struct MiscellaneousService {
     file_storage_service: Arc<FileStorageService>,
     seen_progress_cache: Arc<Cache<ProgressUpdateCache, ()>>,
     config: Arc<config::AppConfig>,
+    provider_limiter: Arc<ProviderRateLimiter>,
+    /// The time at which the latest summary recalculation of each user was
+    /// scheduled to run.
+    summary_jobs: Mutex<HashMap<i32, DateTimeUtc>>,
 }
 
 impl AuthProvider for MiscellaneousService {}
@@ -1407,12 +1520,14 @@ impl MiscellaneousService {
 
         Self {
             db: db.clone(),
+            provider_limiter: Arc::new(ProviderRateLimiter::new(config.clone())),
             config,
             timezone,
             file_storage_service,
             seen_progress_cache,
             perform_application_job: perform_application_job.clone(),
             perform_core_application_job: perform_core_application_job.clone(),
+            summary_jobs: Mutex::new(HashMap::new()),
         }
     }
 }
@@ -2432,6 +2547,68 @@ impl MiscellaneousService {
         // update only if media has not been consumed for this user in the last `n` duration
         respect_cache: bool,
     ) -> Result<ProgressUpdateResultUnion> {
+        let seen = match self
+            .progress_update_seen(&self.db, input, user_id, respect_cache)
+            .await?
+        {
+            Ok(seen) => seen,
+            Err(error) => return Ok(ProgressUpdateResultUnion::Error(error)),
+        };
+        let id = seen.id;
+        self.after_media_seen_tasks(seen).await?;
+        Ok(ProgressUpdateResultUnion::Ok(IdObject { id }))
+    }
+
+    /// Same as `progress_update`, but all the seen items are saved in a single
+    /// transaction. Each item is saved in its own savepoint so that one that fails
+    /// is rolled back and reported without losing the others. The tasks that run
+    /// after an item is seen are performed once the transaction has been committed.
+    pub async fn progress_update_bulk(
+        &self,
+        input: Vec<ProgressUpdateInput>,
+        user_id: i32,
+    ) -> Result<Vec<Result<ProgressUpdateResultUnion>>> {
+        let txn = self.db.begin().await?;
+        let mut results = vec![];
+        let mut seen_items = vec![];
+        for progress in input {
+            let savepoint = txn.begin().await?;
+            results.push(
+                match self
+                    .progress_update_seen(&savepoint, progress, user_id, false)
+                    .await
+                {
+                    Ok(Ok(seen)) => {
+                        savepoint.commit().await?;
+                        let id = seen.id;
+                        seen_items.push(seen);
+                        Ok(ProgressUpdateResultUnion::Ok(IdObject { id }))
+                    }
+                    Ok(Err(error)) => {
+                        savepoint.rollback().await?;
+                        Ok(ProgressUpdateResultUnion::Error(error))
+                    }
+                    Err(e) => {
+                        savepoint.rollback().await?;
+                        Err(e)
+                    }
+                },
+            );
+        }
+        txn.commit().await?;
+        for seen in seen_items {
+            self.after_media_seen_tasks(seen).await?;
+        }
+        Ok(results)
+    }
+
+    pub async fn progress_update_seen<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        input: ProgressUpdateInput,
+        user_id: i32,
+        respect_cache: bool,
+    ) -> Result<std::result::Result<seen::Model, ProgressUpdateError>> {
         let cache = ProgressUpdateCache {
             user_id,
             metadata_id: input.metadata_id,
@@ -2442,7 +2619,7 @@ impl MiscellaneousService {
             manga_chapter_number: input.manga_chapter_number,
         };
         if respect_cache && self.seen_progress_cache.get(&cache).await.is_some() {
-            return Ok(ProgressUpdateResultUnion::Error(ProgressUpdateError {
+            return Ok(Err(ProgressUpdateError {
                 error: ProgressUpdateErrorVariant::AlreadySeen,
             }));
         }
@@ -2454,9 +2631,8 @@ impl MiscellaneousService {
             .filter(seen::Column::State.ne(SeenState::Dropped))
             .filter(seen::Column::MetadataId.eq(input.metadata_id))
             .order_by_desc(seen::Column::LastUpdatedOn)
-            .all(&self.db)
-            .await
-            .unwrap();
+            .all(db)
+            .await?;
         #[derive(Debug, Serialize, Deserialize, Enum, Clone, PartialEq, Eq, Copy)]
         enum ProgressUpdateAction {
             Update,
@@ -2495,13 +2671,15 @@ impl MiscellaneousService {
         };
         tracing::debug!("Progress update action = {:?}", action);
         let err = || {
-            Ok(ProgressUpdateResultUnion::Error(ProgressUpdateError {
+            Ok(Err(ProgressUpdateError {
                 error: ProgressUpdateErrorVariant::NoSeenInProgress,
             }))
         };
         let seen = match action {
             ProgressUpdateAction::Update => {
-                let progress = input.progress.unwrap();
+                let progress = input
+                    .progress
+                    .ok_or_else(|| Error::new("Progress is required for progress update"))?;
                 let mut updated_at = prev_seen[0].updated_at.clone();
                 let now = Utc::now();
                 updated_at.push(now);
@@ -2512,7 +2690,7 @@ impl MiscellaneousService {
                 if progress == 100 {
                     last_seen.finished_on = ActiveValue::Set(Some(now.date_naive()));
                 }
-                last_seen.update(&self.db).await.unwrap()
+                last_seen.update(db).await?
             }
             ProgressUpdateAction::ChangeState => {
                 let new_state = input.change_state.unwrap_or(SeenState::Dropped);
@@ -2520,9 +2698,8 @@ impl MiscellaneousService {
                     .filter(seen::Column::UserId.eq(user_id))
                     .filter(seen::Column::MetadataId.eq(input.metadata_id))
                     .order_by_desc(seen::Column::LastUpdatedOn)
-                    .one(&self.db)
-                    .await
-                    .unwrap();
+                    .one(db)
+                    .await?;
                 match last_seen {
                     Some(ls) => {
                         let mut updated_at = ls.updated_at.clone();
@@ -2531,7 +2708,7 @@ impl MiscellaneousService {
                         let mut last_seen: seen::ActiveModel = ls.into();
                         last_seen.state = ActiveValue::Set(new_state);
                         last_seen.updated_at = ActiveValue::Set(updated_at);
-                        last_seen.update(&self.db).await.unwrap()
+                        last_seen.update(db).await?
                     }
                     None => {
                         return err();
@@ -2542,10 +2719,9 @@ impl MiscellaneousService {
             | ProgressUpdateAction::InThePast
             | ProgressUpdateAction::JustStarted => {
                 let meta = Metadata::find_by_id(input.metadata_id)
-                    .one(&self.db)
-                    .await
-                    .unwrap()
-                    .unwrap();
+                    .one(db)
+                    .await?
+                    .ok_or_else(|| Error::new("Metadata with the given id does not exist"))?;
                 tracing::debug!("Progress update meta = {:?}", meta.title);
 
                 let show_ei = if matches!(meta.lot, MetadataLot::Show) {
@@ -2607,11 +2783,10 @@ impl MiscellaneousService {
                     manga_extra_information: ActiveValue::Set(manga_ei),
                     ..Default::default()
                 };
-                seen_insert.insert(&self.db).await.unwrap()
+                seen_insert.insert(db).await?
             }
         };
         tracing::debug!("Progress update = {:?}", seen);
-        let id = seen.id;
         if seen.state == SeenState::Completed && respect_cache {
             self.seen_progress_cache
                 .insert(
@@ -2624,8 +2799,7 @@ impl MiscellaneousService {
                 )
                 .await;
         }
-        self.after_media_seen_tasks(seen).await?;
-        Ok(ProgressUpdateResultUnion::Ok(IdObject { id }))
+        Ok(Ok(seen))
     }
 
     pub async fn deploy_bulk_progress_update(
@@ -2645,9 +2819,7 @@ impl MiscellaneousService {
         user_id: i32,
         input: Vec<ProgressUpdateInput>,
     ) -> Result<bool> {
-        for seen in input {
-            self.progress_update(seen, user_id, false).await.ok();
-        }
+        self.progress_update_bulk(input, user_id).await?;
         Ok(true)
     }
 
@@ -2665,9 +2837,35 @@ impl MiscellaneousService {
                     .await?;
             }
             BackgroundJob::CalculateSummary => {
-                sqlite_storage
-                    .push(ApplicationJob::RecalculateUserSummary(user_id))
-                    .await?;
+                // DEV: Recalculations requested shortly after another one (for eg: by
+                // imports that finish together) are coalesced into a single job that
+                // runs once the debounce window of the previous one is over
+                let now = Utc::now();
+                let window = ChronoDuration::try_seconds(SUMMARY_DEBOUNCE_SECONDS).unwrap();
+                let run_at = {
+                    let mut jobs = self.summary_jobs.lock().unwrap();
+                    let run_at = match jobs.get(&user_id) {
+                        Some(at) if *at > now => None,
+                        Some(at) if *at + window > now => Some(*at + window),
+                        _ => Some(now),
+                    };
+                    if let Some(at) = run_at {
+                        jobs.insert(user_id, at);
+                    }
+                    run_at
+                };
+                let job = ApplicationJob::RecalculateUserSummary(user_id);
+                match run_at {
+                    Some(at) if at > now => {
+                        sqlite_storage.schedule(job, at).await?;
+                    }
+                    Some(_) => {
+                        sqlite_storage.push(job).await?;
+                    }
+                    None => tracing::debug!(
+                        "Summary recalculation for user {user_id} is already scheduled"
+                    ),
+                }
             }
             BackgroundJob::UpdateAllMetadata => {
                 self.admin_account_guard(user_id).await?;
@@ -3049,7 +3247,11 @@ impl MiscellaneousService {
         meta.is_partial = ActiveValue::Set(Some(false));
         meta.provider_rating = ActiveValue::Set(input.provider_rating);
         meta.description = ActiveValue::Set(input.description);
-        meta.images = ActiveValue::Set(Some(images));
+        // DEV: Media that the provider has no images of keeps the ones it has,
+        // for eg: the ones that were added by an import
+        if !images.is_empty() {
+            meta.images = ActiveValue::Set(Some(images));
+        }
         meta.videos = ActiveValue::Set(Some(input.videos));
         meta.production_status = ActiveValue::Set(input.production_status);
         meta.original_language = ActiveValue::Set(input.original_language);
@@ -3645,23 +3847,60 @@ impl MiscellaneousService {
         Ok(results)
     }
 
-    pub async fn get_openlibrary_service(&self) -> Result<OpenlibraryService> {
-        Ok(OpenlibraryService::new(
+    fn rate_limited<T>(&self, source: MediaSource, service: T) -> RateLimited<T> {
+        RateLimited {
+            service,
+            source,
+            limiter: self.provider_limiter.clone(),
+        }
+    }
+
+    async fn openlibrary_service(&self) -> OpenlibraryService {
+        OpenlibraryService::new(
             &self.config.books.openlibrary,
             self.config.frontend.page_size,
         )
-        .await)
+        .await
     }
 
-    pub async fn get_isbn_service(&self) -> Result<GoogleBooksService> {
-        Ok(GoogleBooksService::new(
+    async fn isbn_service(&self) -> GoogleBooksService {
+        GoogleBooksService::new(
             &self.config.books.google_books,
             self.config.frontend.page_size,
         )
-        .await)
+        .await
+    }
+
+    async fn tmdb_non_media_service(&self) -> NonMediaTmdbService {
+        NonMediaTmdbService::new(
+            self.config.movies_and_shows.tmdb.access_token.clone(),
+            self.config.movies_and_shows.tmdb.locale.clone(),
+        )
+        .await
     }
 
-    async fn get_metadata_provider(
+    pub async fn get_openlibrary_service(&self) -> Result<RateLimited<OpenlibraryService>> {
+        let service = self.openlibrary_service().await;
+        Ok(self.rate_limited(MediaSource::Openlibrary, service))
+    }
+
+    pub async fn get_isbn_service(&self) -> Result<RateLimited<GoogleBooksService>> {
+        let service = self.isbn_service().await;
+        Ok(self.rate_limited(MediaSource::GoogleBooks, service))
+    }
+
+    pub async fn get_itunes_service(&self) -> Result<RateLimited<ITunesService>> {
+        let service =
+            ITunesService::new(&self.config.podcasts.itunes, self.config.frontend.page_size).await;
+        Ok(self.rate_limited(MediaSource::Itunes, service))
+    }
+
+    pub async fn get_tmdb_non_media_service(&self) -> Result<RateLimited<NonMediaTmdbService>> {
+        let service = self.tmdb_non_media_service().await;
+        Ok(self.rate_limited(MediaSource::Tmdb, service))
+    }
+
+    pub async fn get_metadata_provider(
         &self,
         lot: MetadataLot,
         source: MediaSource,
@@ -3671,12 +3910,12 @@ impl MiscellaneousService {
             MediaSource::Vndb => Box::new(
                 VndbService::new(&self.config.visual_novels, self.config.frontend.page_size).await,
             ),
-            MediaSource::Openlibrary => Box::new(self.get_openlibrary_service().await?),
+            MediaSource::Openlibrary => Box::new(self.openlibrary_service().await),
             MediaSource::Itunes => Box::new(
                 ITunesService::new(&self.config.podcasts.itunes, self.config.frontend.page_size)
                     .await,
             ),
-            MediaSource::GoogleBooks => Box::new(self.get_isbn_service().await?),
+            MediaSource::GoogleBooks => Box::new(self.isbn_service().await),
             MediaSource::Audible => Box::new(
                 AudibleService::new(
                     &self.config.audio_books.audible,
@@ -3751,7 +3990,7 @@ impl MiscellaneousService {
             ),
             MediaSource::Custom => return err(),
         };
-        Ok(service)
+        Ok(Box::new(self.rate_limited(source, service)))
     }
 
     async fn get_non_metadata_provider(&self, source: MediaSource) -> Result<Provider> {
@@ -3760,7 +3999,7 @@ impl MiscellaneousService {
             MediaSource::Vndb => Box::new(
                 VndbService::new(&self.config.visual_novels, self.config.frontend.page_size).await,
             ),
-            MediaSource::Openlibrary => Box::new(self.get_openlibrary_service().await?),
+            MediaSource::Openlibrary => Box::new(self.openlibrary_service().await),
             MediaSource::Itunes => Box::new(
                 ITunesService::new(&self.config.podcasts.itunes, self.config.frontend.page_size)
                     .await,
@@ -3793,20 +4032,14 @@ impl MiscellaneousService {
                 )
                 .await,
             ),
-            MediaSource::Tmdb => Box::new(
-                NonMediaTmdbService::new(
-                    self.config.movies_and_shows.tmdb.access_token.clone(),
-                    self.config.movies_and_shows.tmdb.locale.clone(),
-                )
-                .await,
-            ),
+            MediaSource::Tmdb => Box::new(self.tmdb_non_media_service().await),
             MediaSource::Anilist => {
                 Box::new(NonMediaAnilistService::new(self.config.frontend.page_size).await)
             }
             MediaSource::Mal => Box::new(NonMediaMalService::new().await),
             MediaSource::Custom => return err(),
         };
-        Ok(service)
+        Ok(Box::new(self.rate_limited(source, service)))
     }
 
     async fn details_from_provider(
@@ -3820,7 +4053,7 @@ impl MiscellaneousService {
         Ok(results)
     }
 
-    async fn commit_metadata(&self, input: CommitMetadataInput) -> Result<IdObject> {
+    pub async fn commit_metadata(&self, input: CommitMetadataInput) -> Result<IdObject> {
         if let Some(m) = Metadata::find()
             .filter(metadata::Column::Lot.eq(input.lot))
             .filter(metadata::Column::Source.eq(input.source))
@@ -4120,6 +4353,9 @@ impl MiscellaneousService {
                         CollectionContentsSortBy::LastUpdatedOn => {
                             Expr::col(collection_to_entity::Column::LastUpdatedOn)
                         }
+                        CollectionContentsSortBy::AddedOn => {
+                            Expr::col(collection_to_entity::Column::CreatedOn)
+                        }
                         CollectionContentsSortBy::Title => Expr::expr(Func::coalesce([
                             Expr::col((AliasedMetadata::Table, metadata::Column::Title)).into(),
                             Expr::col((AliasedMetadataGroup::Table, metadata_group::Column::Title))
@@ -4238,6 +4474,23 @@ impl MiscellaneousService {
     }
 
     pub async fn post_review(&self, user_id: i32, input: PostReviewInput) -> Result<IdObject> {
+        let is_new = input.review_id.is_none();
+        let insert = self.post_review_in(&self.db, user_id, input).await?;
+        let id = insert.id.clone().unwrap();
+        self.after_review_posted_tasks(user_id, insert, is_new)
+            .await?;
+        Ok(IdObject { id })
+    }
+
+    /// Same as `post_review`, but the review is saved using the given connection
+    /// (which can be a transaction). The tasks that run after a review is posted
+    /// have to be performed by the caller using `after_review_posted_tasks`.
+    pub async fn post_review_in<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        user_id: i32,
+        input: PostReviewInput,
+    ) -> Result<review::ActiveModel> {
         if self.config.users.reviews_disabled {
             return Err(Error::new("Posting reviews on this instance is disabled"));
         }
@@ -4301,7 +4554,15 @@ impl MiscellaneousService {
         if let Some(d) = input.date {
             review_obj.posted_on = ActiveValue::Set(d);
         }
-        let insert = review_obj.save(&self.db).await.unwrap();
+        Ok(review_obj.save(db).await?)
+    }
+
+    pub async fn after_review_posted_tasks(
+        &self,
+        user_id: i32,
+        insert: review::ActiveModel,
+        is_new: bool,
+    ) -> Result<()> {
         if insert.visibility.unwrap() == Visibility::Public {
             let (obj_id, obj_title, entity_lot) = if let Some(mi) = insert.metadata_id.unwrap() {
                 (
@@ -4344,7 +4605,7 @@ impl MiscellaneousService {
             };
             let user = user_by_id(&self.db, insert.user_id.unwrap()).await?;
             // DEV: Do not send notification if updating a review
-            if input.review_id.is_none() {
+            if is_new {
                 self.perform_application_job
                     .clone()
                     .push(ApplicationJob::ReviewPosted(ReviewPostedEvent {
@@ -4357,9 +4618,7 @@ impl MiscellaneousService {
                     .await?;
             }
         }
-        Ok(IdObject {
-            id: insert.id.unwrap(),
-        })
+        Ok(())
     }
 
     pub async fn delete_review(&self, user_id: i32, review_id: i32) -> Result<bool> {
@@ -4451,6 +4710,69 @@ impl MiscellaneousService {
         Ok(resp)
     }
 
+    /// Move the entities and reviews of the duplicate collections to the target
+    /// collection and delete the duplicates. Entities that are already in the
+    /// target collection are dropped.
+    pub async fn merge_collections(
+        &self,
+        user_id: i32,
+        target_id: i32,
+        duplicate_ids: Vec<i32>,
+    ) -> Result<()> {
+        let txn = self.db.begin().await?;
+        let duplicate_ids = Collection::find()
+            .select_only()
+            .column(collection::Column::Id)
+            .filter(collection::Column::Id.is_in(duplicate_ids))
+            .filter(collection::Column::Id.ne(target_id))
+            .filter(collection::Column::UserId.eq(user_id))
+            .into_tuple::<i32>()
+            .all(&txn)
+            .await?;
+        let mut existing = CollectionToEntity::find()
+            .filter(collection_to_entity::Column::CollectionId.eq(target_id))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|e| {
+                (
+                    e.metadata_id,
+                    e.person_id,
+                    e.metadata_group_id,
+                    e.exercise_id,
+                )
+            })
+            .collect::<HashSet<_>>();
+        let entities = CollectionToEntity::find()
+            .filter(collection_to_entity::Column::CollectionId.is_in(duplicate_ids.clone()))
+            .all(&txn)
+            .await?;
+        for entity in entities {
+            let key = (
+                entity.metadata_id,
+                entity.person_id,
+                entity.metadata_group_id,
+                entity.exercise_id.clone(),
+            );
+            if existing.insert(key) {
+                let mut entity: collection_to_entity::ActiveModel = entity.into();
+                entity.collection_id = ActiveValue::Set(target_id);
+                entity.update(&txn).await?;
+            }
+        }
+        Review::update_many()
+            .col_expr(review::Column::CollectionId, Expr::value(target_id))
+            .filter(review::Column::CollectionId.is_in(duplicate_ids.clone()))
+            .exec(&txn)
+            .await?;
+        Collection::delete_many()
+            .filter(collection::Column::Id.is_in(duplicate_ids))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
     pub async fn add_entity_to_collection(
         &self,
         user_id: i32,
@@ -6729,6 +7051,15 @@ GROUP BY
                 .all(&self.db)
                 .await
                 .unwrap();
+            // DEV: Completed and planned media can be told apart by their seen history
+            let status = seen_history
+                .iter()
+                .max_by_key(|s| s.last_updated_on)
+                .and_then(|s| match s.state {
+                    SeenState::Dropped => Some(ImportOrExportItemStatus::Dropped),
+                    SeenState::OnAHold => Some(ImportOrExportItemStatus::OnHold),
+                    _ => None,
+                });
             let seen_history = seen_history
                 .into_iter()
                 .map(|s| {
@@ -6748,6 +7079,8 @@ GROUP BY
                         podcast_episode_number,
                         anime_episode_number,
                         manga_chapter_number,
+                        times: None,
+                        watched_through: None,
                     }
                 })
                 .collect();
@@ -6779,7 +7112,12 @@ GROUP BY
                 seen_history,
                 reviews,
                 collections,
+                added_on: None,
+                tags: vec![],
+                images: vec![],
                 monitored: rm.media_monitored,
+                matched_by_title: false,
+                status,
             };
             writer.serialize_value(&exp).unwrap();
         }
@@ -123,6 +123,8 @@ pub struct IdObject {
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, Schematic)]
 pub struct CompleteExport {
+    /// The version of Ryot that created the export.
+    pub version: Option<String>,
     /// Data about user's media.
     pub media: Option<Vec<media::ImportOrExportMediaItem>>,
     /// Data about user's people.
@@ -140,6 +142,8 @@ pub struct ChangeCollectionToEntityInput {
     pub person_id: Option<i32>,
     pub media_group_id: Option<i32>,
     pub exercise_id: Option<String>,
+    /// When the entity was added to the collection, if it was earlier than now.
+    pub added_on: Option<DateTimeUtc>,
 }
 
 #[derive(Debug, SimpleObject, Serialize, Deserialize, Default, Clone, PartialEq, Eq, Schematic)]
@@ -1044,6 +1048,13 @@ pub mod media {
         pub anime_episode_number: Option<i32>,
         /// If for a manga, the chapter which was seen.
         pub manga_chapter_number: Option<i32>,
+        /// The number of times it was seen, for eg: for a movie that was watched
+        /// again. Only one of the entries that are created has the dates.
+        pub times: Option<u32>,
+        /// If for a show, whether every episode up to the season and episode of
+        /// this entry was seen, for eg: when the source only records how far the
+        /// user has caught up. An entry is created for each of these episodes.
+        pub watched_through: Option<bool>,
     }
 
     /// Review data associated to a rating.
@@ -1082,6 +1093,17 @@ pub mod media {
         pub comments: Option<Vec<ImportOrExportItemReviewComment>>,
     }
 
+    /// The status of a media item in the source, for eg: a shelf on Goodreads.
+    #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Schematic)]
+    pub enum ImportOrExportItemStatus {
+        Completed,
+        /// The media was not finished, for eg: the "Did Not Finish" shelf.
+        Dropped,
+        OnHold,
+        /// The media has not been started yet.
+        Planned,
+    }
+
     /// Details about a specific media item that needs to be imported or exported.
     #[skip_serializing_none]
     #[derive(Debug, Serialize, Deserialize, Clone, Schematic)]
@@ -1104,8 +1126,29 @@ pub mod media {
         pub reviews: Vec<ImportOrExportItemRating>,
         /// The collections this entity was added to.
         pub collections: Vec<String>,
+        /// When the item was added to its collections in the source, which is kept
+        /// instead of the time of the import.
+        pub added_on: Option<DateTimeUtc>,
+        /// The tags of the item in the source, for eg: genres or moods. They are
+        /// imported as collections.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub tags: Vec<String>,
+        /// URLs of images of the item in the source, for eg: covers of books that
+        /// are only on the computer of the user. They are only used if the
+        /// provider does not have any images of the media.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub images: Vec<String>,
         /// Whether the media is being monitored.
         pub monitored: Option<bool>,
+        /// The status of the media in the source. The last seen entry of media that
+        /// was dropped or put on hold keeps its progress, and planned media is only
+        /// added to the watchlist.
+        pub status: Option<ImportOrExportItemStatus>,
+        // DEV: Only to be used internally. Set when the identifier was found by
+        // searching for the title of the item on the provider.
+        #[serde(skip)]
+        #[schema(exclude)]
+        pub matched_by_title: bool,
     }
 
     /// Details about a specific creator item that needs to be exported.
@@ -10,7 +10,10 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use strum::{Display, EnumIter, IntoEnumIterator};
-use surf::{http::headers::ACCEPT, Client};
+use surf::{
+    http::headers::{ACCEPT, USER_AGENT},
+    Client,
+};
 
 use crate::{
     entities::metadata_group::MetadataGroupWithoutId,
@@ -23,7 +26,7 @@ use crate::{
         NamedObject, SearchDetails, SearchResults,
     },
     traits::{MediaProvider, MediaProviderLanguages},
-    utils::get_base_http_client,
+    utils::{get_base_http_client, user_agent},
 };
 
 static LOCALES: [&str; 10] = ["au", "ca", "de", "es", "fr", "in", "it", "jp", "gb", "us"];
@@ -202,6 +205,7 @@ impl MediaProvider for AudibleService {
         let internal_page: usize = page.unwrap_or(1).try_into().unwrap();
         let req_internal_page = internal_page - 1;
         let data: Vec<AudibleAuthor> = surf::get(format!("{}/authors", AUDNEX_URL))
+            .header(USER_AGENT, user_agent())
             .query(&json!({ "region": self.locale, "name": query }))
             .unwrap()
             .await
@@ -242,6 +246,7 @@ impl MediaProvider for AudibleService {
         _source_specifics: &Option<PersonSourceSpecifics>,
     ) -> Result<MetadataPerson> {
         let data: AudnexResponse = surf::get(format!("{}/authors/{}", AUDNEX_URL, identity))
+            .header(USER_AGENT, user_agent())
             .query(&json!({ "region": self.locale }))
             .unwrap()
             .await
@@ -11,7 +11,10 @@ use sea_orm::prelude::DateTimeUtc;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use serde_with::{formats::Flexible, serde_as, TimestampSeconds};
-use surf::{http::headers::AUTHORIZATION, Client};
+use surf::{
+    http::headers::{AUTHORIZATION, USER_AGENT},
+    Client,
+};
 
 use crate::{
     entities::metadata_group::MetadataGroupWithoutId,
@@ -24,7 +27,7 @@ use crate::{
         IdObject, NamedObject, SearchDetails, SearchResults, StoredUrl,
     },
     traits::{MediaProvider, MediaProviderLanguages},
-    utils::{get_base_http_client, TEMP_DIR},
+    utils::{get_base_http_client, user_agent, TEMP_DIR},
 };
 
 static URL: &str = "https://api.igdb.com/v4/";
@@ -37,6 +40,9 @@ struct Settings {
     access_token: String,
 }
 
+/// The id of the "Erotic" theme.
+const EROTIC_THEME: i32 = 42;
+
 static GAME_FIELDS: &str = "
 fields
     id,
@@ -55,7 +61,8 @@ fields
     platforms.name,
     collection.id,
     videos.*,
-    genres.*;
+    genres.*,
+    themes;
 where version_parent = null;
 ";
 static INVOLVED_COMPANY_FIELDS: &str = "
@@ -140,6 +147,7 @@ struct IgdbItemResponse {
     artworks: Option<Vec<IgdbImage>>,
     genres: Option<Vec<NamedObject>>,
     platforms: Option<Vec<NamedObject>>,
+    themes: Option<Vec<i32>>,
     similar_games: Option<Vec<IgdbItemResponse>>,
     version_parent: Option<i32>,
     collection: Option<IdObject>,
@@ -504,6 +512,7 @@ impl IgdbService {
             identifier: item.id.to_string(),
             lot: MetadataLot::VideoGame,
             source: MediaSource::Igdb,
+            is_nsfw: item.themes.map(|t| t.contains(&EROTIC_THEME)),
             title: item.name.unwrap(),
             description: item.summary,
             people,
@@ -550,6 +559,7 @@ impl IgdbService {
 
 async fn get_access_token(config: &config::VideoGameConfig) -> String {
     let mut access_res = surf::post(AUTH_URL)
+        .header(USER_AGENT, user_agent())
         .query(&json!({
             "client_id": config.twitch.client_id.to_owned(),
             "client_secret": config.twitch.client_secret.to_owned(),
@@ -48,6 +48,38 @@ impl ITunesService {
             page_limit,
         }
     }
+
+    /// Get a podcast's ID from the URL of its RSS feed. The podcast is searched by
+    /// its title since iTunes can not be queried by the feed URL.
+    pub async fn id_from_feed_url(&self, title: &str, feed_url: &str) -> Option<String> {
+        // DEV: Feeds are often listed with a different scheme or trailing slash
+        let normalize = |url: &str| {
+            url.trim()
+                .trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .trim_end_matches('/')
+                .to_lowercase()
+        };
+        let mut rsp = self
+            .client
+            .get("search")
+            .query(&serde_json::json!({
+                "term": title,
+                "media": "podcast",
+                "entity": "podcast",
+                "lang": self.language
+            }))
+            .unwrap()
+            .await
+            .ok()?;
+        let search: SearchResponse = rsp.body_json().await.ok()?;
+        let feed_url = normalize(feed_url);
+        search
+            .results?
+            .into_iter()
+            .find(|i| i.feed_url.as_deref().map(normalize).as_ref() == Some(&feed_url))
+            .map(|i| i.collection_id.to_string())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -74,6 +106,9 @@ struct ITunesItem {
     artwork_url_60: Option<String>,
     artwork_url_600: Option<String>,
     track_time_millis: Option<i32>,
+    feed_url: Option<String>,
+    /// For eg: "explicit" or "notExplicit".
+    collection_explicitness: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -119,6 +154,7 @@ impl MediaProvider for ITunesService {
             })
             .collect();
         let total_episodes = ht.track_count.unwrap();
+        let is_nsfw = ht.collection_explicitness.as_ref().map(|e| e == "explicit");
         let details = get_search_response(ht);
         let mut rsp = self
             .client
@@ -165,6 +201,7 @@ impl MediaProvider for ITunesService {
         Ok(MediaDetails {
             identifier: details.identifier,
             title: details.title,
+            is_nsfw,
             publish_date,
             publish_year: publish_date.map(|d| d.year()),
             source: MediaSource::Itunes,
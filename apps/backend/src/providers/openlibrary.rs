@@ -551,6 +551,23 @@ impl OpenlibraryService {
         )
     }
 
+    /// Get a book's ID from its ISBN
+    pub async fn id_from_isbn(&self, isbn: &str) -> Option<String> {
+        let mut rsp = self
+            .client
+            .get("search.json")
+            .query(&json!({
+                "isbn": isbn,
+                "fields": "key",
+                "limit": 1,
+            }))
+            .unwrap()
+            .await
+            .ok()?;
+        let search: OpenLibrarySearchResponse<OpenlibraryKey> = rsp.body_json().await.ok()?;
+        Some(get_key(&search.docs.first()?.key))
+    }
+
     fn parse_date(input: &str) -> Option<NaiveDate> {
         let formats = ["%b %d, %Y", "%Y", "%b %d, %Y"];
         for format in formats.iter() {
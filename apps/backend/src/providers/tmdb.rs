@@ -100,6 +100,12 @@ struct TmdbListResponse {
     total_pages: i32,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+struct TmdbFindByExternalSourceResponse {
+    movie_results: Vec<TmdbEntry>,
+    tv_results: Vec<TmdbEntry>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct TmdbVideo {
     key: String,
@@ -204,6 +210,34 @@ impl NonMediaTmdbService {
             base: TmdbService { language, settings },
         }
     }
+
+    /// Get the TMDB ID and type of a media item from an external ID (eg: `imdb_id`,
+    /// `tvdb_id`).
+    pub async fn find_by_external_id(
+        &self,
+        external_id: &str,
+        external_source: &str,
+    ) -> Result<(MetadataLot, String)> {
+        let mut rsp = self
+            .client
+            .get(format!("find/{}", external_id))
+            .query(&json!({
+                "external_source": external_source,
+                "language": self.base.language,
+            }))
+            .unwrap()
+            .await
+            .map_err(|e| anyhow!(e))?;
+        let data: TmdbFindByExternalSourceResponse =
+            rsp.body_json().await.map_err(|e| anyhow!(e))?;
+        if let Some(movie) = data.movie_results.first() {
+            Ok((MetadataLot::Movie, movie.id.to_string()))
+        } else if let Some(show) = data.tv_results.first() {
+            Ok((MetadataLot::Show, show.id.to_string()))
+        } else {
+            Err(anyhow!("No TMDB entry found for {}", external_id))
+        }
+    }
 }
 
 #[async_trait]
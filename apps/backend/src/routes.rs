@@ -1,12 +1,15 @@
 use std::{fs::write, path::PathBuf, sync::Arc};
 
 use anyhow::Result;
-use async_graphql::http::GraphiQLSource;
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use async_graphql::{
+    http::{GraphiQLSource, ALL_WEBSOCKET_PROTOCOLS},
+    Data,
+};
+use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
 use axum::{
-    extract::{Multipart, Path},
+    extract::{ws::WebSocketUpgrade, Multipart, Path},
     http::StatusCode,
-    response::{Html, IntoResponse},
+    response::{Html, IntoResponse, Response},
     Extension, Json,
 };
 use nanoid::nanoid;
@@ -15,7 +18,7 @@ use serde_json::json;
 use crate::{
     graphql::GraphqlSchema,
     miscellaneous::resolver::MiscellaneousService,
-    utils::{AuthContext, TEMP_DIR},
+    utils::{user_id_from_token, AuthContext, TEMP_DIR},
 };
 
 pub async fn graphql_handler(
@@ -26,10 +29,46 @@ pub async fn graphql_handler(
     schema.execute(req.into_inner().data(gql_ctx)).await.into()
 }
 
+/// Serve GraphQL subscriptions over a websocket. Browsers can not set headers on
+/// websocket requests, so the auth token can also be sent in the payload of the
+/// connection init message.
+pub async fn graphql_subscription_handler(
+    Extension(schema): Extension<GraphqlSchema>,
+    Extension(config): Extension<Arc<config::AppConfig>>,
+    gql_ctx: AuthContext,
+    protocol: GraphQLProtocol,
+    websocket: WebSocketUpgrade,
+) -> Response {
+    websocket
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |stream| {
+            GraphQLWebSocket::new(stream, schema, protocol)
+                .on_connection_init(move |payload| async move {
+                    let auth_token = payload
+                        .get("Authorization")
+                        .or_else(|| payload.get("X-Auth-Token"))
+                        .and_then(|t| t.as_str())
+                        .map(|t| t.replace("Bearer ", ""));
+                    let gql_ctx = match auth_token {
+                        Some(auth_token) => AuthContext {
+                            user_id: user_id_from_token(&auth_token, &config.users.jwt_secret).ok(),
+                            auth_token: Some(auth_token),
+                        },
+                        None => gql_ctx,
+                    };
+                    let mut data = Data::default();
+                    data.insert(gql_ctx);
+                    Ok(data)
+                })
+                .serve()
+        })
+}
+
 pub async fn graphql_playground() -> impl IntoResponse {
     Html(
         GraphiQLSource::build()
             .endpoint("/backend/graphql")
+            .subscription_endpoint("/backend/graphql/ws")
             .finish(),
     )
 }
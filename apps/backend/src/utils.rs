@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use apalis::sqlite::SqliteStorage;
 use async_graphql::{Error, Result};
@@ -22,7 +22,7 @@ use surf::{
 };
 
 use crate::{
-    background::{ApplicationJob, CoreApplicationJob},
+    background::{ApplicationJob, CoreApplicationJob, ImportJob},
     entities::{
         collection, collection_to_entity,
         prelude::{Collection, CollectionToEntity, User, UserToEntity},
@@ -72,6 +72,7 @@ pub async fn create_app_services(
     config: Arc<config::AppConfig>,
     perform_application_job: &SqliteStorage<ApplicationJob>,
     perform_core_application_job: &SqliteStorage<CoreApplicationJob>,
+    perform_import_job: &SqliteStorage<ImportJob>,
     timezone: chrono_tz::Tz,
 ) -> AppServices {
     let timezone = Arc::new(timezone);
@@ -98,8 +99,10 @@ pub async fn create_app_services(
         .await,
     );
     let importer_service = Arc::new(ImporterService::new(
+        config.clone(),
         media_service.clone(),
         exercise_service.clone(),
+        perform_import_job,
         timezone.clone(),
     ));
     let exporter_service = Arc::new(ExporterService::new(
@@ -179,13 +182,28 @@ pub fn user_id_from_token(token: &str, jwt_secret: &str) -> Result<i32> {
         .map_err(|e| Error::new(format!("Encountered error: {:?}", e)))
 }
 
+static USER_AGENT_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Use a different User-Agent for requests to providers and import sources. It
+/// can only be set once, when the server starts.
+pub fn set_user_agent(user_agent: &str) {
+    if !user_agent.is_empty() {
+        USER_AGENT_OVERRIDE.set(user_agent.to_owned()).ok();
+    }
+}
+
+/// The User-Agent that is sent with requests to providers and import sources.
+pub fn user_agent() -> &'static str {
+    USER_AGENT_OVERRIDE
+        .get()
+        .map_or(USER_AGENT_STR, String::as_str)
+}
+
 pub fn get_base_http_client(
     url: &str,
     headers: Vec<(impl Into<HeaderName>, impl ToHeaderValues)>,
 ) -> Client {
-    let mut config = Config::new()
-        .add_header(USER_AGENT, USER_AGENT_STR)
-        .unwrap();
+    let mut config = Config::new().add_header(USER_AGENT, user_agent()).unwrap();
     for (header, value) in headers.into_iter() {
         config = config.add_header(header, value).unwrap();
     }
@@ -239,8 +257,8 @@ pub async fn entity_in_collections(
     Ok(resp)
 }
 
-pub async fn add_entity_to_collection(
-    db: &DatabaseConnection,
+pub async fn add_entity_to_collection<C: ConnectionTrait>(
+    db: &C,
     user_id: i32,
     input: ChangeCollectionToEntityInput,
 ) -> Result<bool> {
@@ -266,8 +284,12 @@ pub async fn add_entity_to_collection(
         .one(db)
         .await?
     {
+        let added_on = input.added_on.filter(|a| *a < etc.created_on);
         let mut to_update: collection_to_entity::ActiveModel = etc.into();
         to_update.last_updated_on = ActiveValue::Set(Utc::now());
+        if let Some(added_on) = added_on {
+            to_update.created_on = ActiveValue::Set(added_on);
+        }
         to_update.update(db).await.is_ok()
     } else {
         let mut created_collection = collection_to_entity::ActiveModel {
@@ -278,6 +300,9 @@ pub async fn add_entity_to_collection(
         created_collection.person_id = ActiveValue::Set(input.person_id);
         created_collection.metadata_group_id = ActiveValue::Set(input.media_group_id);
         created_collection.exercise_id = ActiveValue::Set(input.exercise_id);
+        if let Some(added_on) = input.added_on {
+            created_collection.created_on = ActiveValue::Set(added_on);
+        }
         created_collection.insert(db).await.is_ok()
     };
     Ok(resp)
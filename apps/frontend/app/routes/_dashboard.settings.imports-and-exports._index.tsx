@@ -187,7 +187,7 @@ const traktImportFormSchema = z.object({ username: z.string() });
 const goodreadsImportFormSchema = z.object({ csvPath: z.string() });
 
 const movaryImportFormSchema = z.object({
-	ratings: z.string(),
+	ratings: z.string().optional(),
 	history: z.string(),
 	watchlist: z.string(),
 });
@@ -345,7 +345,6 @@ export default function Page() {
 													<FileInput
 														label="Ratings CSV file"
 														accept=".csv"
-														required
 														name="ratings"
 													/>
 													<FileInput
@@ -115,16 +115,41 @@ export interface ImportOrExportMediaItemSeen {
 	showSeasonNumber: number | null;
 	/** The timestamp when started watching. */
 	startedOn: string | null;
+	/**
+	 * The number of times it was seen, for eg: for a movie that was watched
+	 * again. Only one of the entries that are created has the dates.
+	 */
+	times: number | null;
+	/**
+	 * If for a show, whether every episode up to the season and episode of
+	 * this entry was seen, for eg: when the source only records how far the
+	 * user has caught up. An entry is created for each of these episodes.
+	 */
+	watchedThrough: boolean | null;
 }
 
 export type MediaSource = 'Anilist' | 'Audible' | 'Custom' | 'GoogleBooks' | 'Igdb' | 'Itunes' | 'Listennotes' | 'MangaUpdates' | 'Mal' | 'Openlibrary' | 'Tmdb' | 'Vndb';
 
+/** The status of a media item in the source, for eg: a shelf on Goodreads. */
+export type ImportOrExportItemStatus = 'Completed' | 'Dropped' | 'OnHold' | 'Planned';
+
 /** Details about a specific media item that needs to be imported or exported. */
 export interface ImportOrExportMediaItem {
+	/**
+	 * When the item was added to its collections in the source, which is kept
+	 * instead of the time of the import.
+	 */
+	addedOn: string | null;
 	/** The collections this entity was added to. */
 	collections: string[];
 	/** The provider identifier. For eg: TMDB-ID, Openlibrary ID and so on. */
 	identifier: string;
+	/**
+	 * URLs of images of the item in the source, for eg: covers of books that
+	 * are only on the computer of the user. They are only used if the
+	 * provider does not have any images of the media.
+	 */
+	images: string[];
 	/**
 	 * The type of media.
 	 *
@@ -145,6 +170,17 @@ export interface ImportOrExportMediaItem {
 	source: MediaSource;
 	/** An string to help identify it in the original source. */
 	sourceId: string;
+	/**
+	 * The status of the media in the source. The last seen entry of media that
+	 * was dropped or put on hold keeps its progress, and planned media is only
+	 * added to the watchlist.
+	 */
+	status: ImportOrExportItemStatus | null;
+	/**
+	 * The tags of the item in the source, for eg: genres or moods. They are
+	 * imported as collections.
+	 */
+	tags: string[];
 }
 
 export interface PersonSourceSpecifics {
@@ -277,6 +313,8 @@ export interface CompleteExport {
 	media: ImportOrExportMediaItem[] | null;
 	/** Data about user's people. */
 	people: ImportOrExportPersonItem[] | null;
+	/** The version of Ryot that created the export. */
+	version: string | null;
 	/** Data about user's workouts. */
 	workouts: Workout[] | null;
 }
@@ -123,7 +123,20 @@ pub struct ExerciseConfig {}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
 #[config(rename_all = "snake_case", env_prefix = "MEDIA_")]
-pub struct MediaConfig {}
+pub struct MediaConfig {
+    /// The maximum number of requests per second made to TMDB, including
+    /// searches. Set to `0` to disable the limit.
+    #[setting(default = 40)]
+    pub tmdb_requests_per_second: u32,
+    /// The maximum number of requests per second made to IGDB, including
+    /// searches. Set to `0` to disable the limit.
+    #[setting(default = 4)]
+    pub igdb_requests_per_second: u32,
+    /// The maximum number of requests per second made to all other providers,
+    /// including searches. Set to `0` to disable the limit.
+    #[setting(default = 0)]
+    pub other_requests_per_second: u32,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
 #[config(rename_all = "snake_case", env_prefix = "MOVIES_AND_SHOWS_TMDB_")]
@@ -273,6 +286,37 @@ pub struct FrontendConfig {
     pub umami: FrontendUmamiConfig,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Config)]
+#[config(rename_all = "snake_case", env_prefix = "IMPORTER_")]
+pub struct ImporterConfig {
+    /// The number of media items to fetch from providers concurrently during
+    /// an import.
+    #[setting(default = 5)]
+    pub concurrency: usize,
+    /// The number of times a request to a provider during an import is retried
+    /// when the provider is limiting requests or did not respond in time.
+    #[setting(default = 3)]
+    pub commit_retries: usize,
+    /// The number of hours after which an import that has not finished is
+    /// stopped and marked as failed.
+    #[setting(default = 24)]
+    pub job_timeout_hours: i64,
+    /// The number of imports that can run at the same time. Imports have their
+    /// own workers, so they do not hold up the other application jobs.
+    #[setting(default = 3)]
+    pub workers: u16,
+    /// The number of imports of a user that can be waiting or running at the
+    /// same time, so that one user can not keep all the workers busy. Set to
+    /// `0` to not limit them.
+    #[setting(default = 0)]
+    pub max_imports_per_user: usize,
+    /// The hosts that the callback URL of an import can point to even though they
+    /// resolve to a loopback, private or link-local address, for eg: the host of
+    /// an automation service running on the same network.
+    #[setting(default = vec![], parse_env = schematic::env::split_comma)]
+    pub callback_allowed_hosts: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
 #[config(rename_all = "snake_case", env_prefix = "INTEGRATION_")]
 pub struct IntegrationConfig {
@@ -361,6 +405,10 @@ pub struct ServerConfig {
     /// Disable all background jobs.
     #[setting(default = false)]
     pub disable_background_jobs: bool,
+    /// The User-Agent that is sent with requests to providers and import sources.
+    /// One that identifies this application is used if empty.
+    #[setting(default = "")]
+    pub user_agent: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Config)]
@@ -407,6 +455,9 @@ pub struct AppConfig {
     /// Settings related to frontend storage.
     #[setting(nested)]
     pub frontend: FrontendConfig,
+    /// Settings related to importing data.
+    #[setting(nested)]
+    pub importer: ImporterConfig,
     /// Settings related to external integrations.
     #[setting(nested)]
     pub integration: IntegrationConfig,
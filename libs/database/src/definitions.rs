@@ -156,10 +156,36 @@ pub enum Visibility {
 )]
 #[sea_orm(rs_type = "String", db_type = "String(None)")]
 pub enum ImportSource {
+    #[sea_orm(string_value = "AN")]
+    Anilist,
+    #[sea_orm(string_value = "AU")]
+    Audible,
     #[sea_orm(string_value = "AB")]
     Audiobookshelf,
+    #[sea_orm(string_value = "BL")]
+    Backloggd,
+    #[sea_orm(string_value = "CA")]
+    Calibre,
+    #[sea_orm(string_value = "GC")]
+    GenericCsv,
     #[sea_orm(string_value = "GO")]
     Goodreads,
+    #[sea_orm(string_value = "HC")]
+    Hardcover,
+    #[sea_orm(string_value = "HL")]
+    HowLongToBeat,
+    #[sea_orm(string_value = "IM")]
+    Imdb,
+    #[sea_orm(string_value = "JF")]
+    Jellyfin,
+    #[sea_orm(string_value = "KI")]
+    Kitsu,
+    #[sea_orm(string_value = "KO")]
+    Kodi,
+    #[sea_orm(string_value = "LF")]
+    LastFm,
+    #[sea_orm(string_value = "LE")]
+    Letterboxd,
     #[sea_orm(string_value = "MA")]
     Mal,
     #[sea_orm(string_value = "MEJ")]
@@ -170,14 +196,38 @@ pub enum ImportSource {
     MediaJson,
     #[sea_orm(string_value = "PJ")]
     PeopleJson,
+    #[sea_orm(string_value = "PL")]
+    Plex,
+    #[sea_orm(string_value = "PO")]
+    PodcastOpml,
     #[sea_orm(string_value = "TR")]
     Trakt,
+    #[sea_orm(string_value = "TT")]
+    TvTime,
     #[sea_orm(string_value = "MO")]
     Movary,
+    #[sea_orm(string_value = "NE")]
+    Netflix,
+    #[sea_orm(string_value = "OL")]
+    OpenlibraryLog,
+    #[sea_orm(string_value = "RY")]
+    Ryot,
+    #[sea_orm(string_value = "SE")]
+    Serializd,
+    #[sea_orm(string_value = "SI")]
+    Simkl,
+    #[sea_orm(string_value = "SP")]
+    Spotify,
+    #[sea_orm(string_value = "SM")]
+    Steam,
     #[sea_orm(string_value = "ST")]
     StoryGraph,
     #[sea_orm(string_value = "SA")]
     StrongApp,
+    #[sea_orm(string_value = "SC")]
+    StreamingCsv,
+    #[sea_orm(string_value = "TM")]
+    Tmdb,
     #[sea_orm(string_value = "WJ")]
     WorkoutsJson,
 }
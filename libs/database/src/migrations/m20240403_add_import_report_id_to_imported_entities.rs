@@ -0,0 +1,27 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for table in ["seen", "review", "collection_to_entity"] {
+            if !manager.has_column(table, "import_report_id").await? {
+                db.execute_unprepared(&format!(
+                    r#"
+alter table {table} add column import_report_id integer;
+alter table {table} add constraint "{table}_to_import_report_foreign_key" foreign key ("import_report_id") references "import_report" ("id") on update cascade on delete set null;
+"#
+                ))
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        if !manager.has_column("import_report", "job_id").await? {
+            db.execute_unprepared("alter table import_report add column job_id text")
+                .await?;
+        }
+        if !manager.has_column("import_report", "cursor").await? {
+            db.execute_unprepared("alter table import_report add column cursor jsonb")
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        if !manager
+            .has_column("collection_to_entity", "created_on")
+            .await?
+        {
+            db.execute_unprepared(
+                r#"
+alter table collection_to_entity add column created_on timestamp with time zone not null default current_timestamp;
+update collection_to_entity set created_on = last_updated_on;
+"#,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -62,6 +62,15 @@ mod m20240307_add_column_to_metadata_for_watch_providers;
 mod m20240309_change_generic_to_media_json;
 mod m20240310_add_source_specifics_field_to_person;
 mod m20240324_perform_v4_migration;
+mod m20240401_add_progress_to_import_report;
+mod m20240402_add_was_cancelled_to_import_report;
+mod m20240403_add_import_report_id_to_imported_entities;
+mod m20240404_add_cursor_to_import_report;
+mod m20240405_add_scheduled_imports_to_user;
+mod m20240406_add_idempotency_key_to_import_report;
+mod m20240407_add_application_job_id_to_import_report;
+mod m20240408_add_bulk_id_to_import_report;
+mod m20240409_add_created_on_to_collection_to_entity;
 
 pub use m20230410_create_metadata::Metadata as AliasedMetadata;
 pub use m20230413_create_person::Person as AliasedPerson;
@@ -141,6 +150,15 @@ impl MigratorTrait for Migrator {
             Box::new(m20240309_change_generic_to_media_json::Migration),
             Box::new(m20240310_add_source_specifics_field_to_person::Migration),
             Box::new(m20240324_perform_v4_migration::Migration),
+            Box::new(m20240401_add_progress_to_import_report::Migration),
+            Box::new(m20240402_add_was_cancelled_to_import_report::Migration),
+            Box::new(m20240403_add_import_report_id_to_imported_entities::Migration),
+            Box::new(m20240404_add_cursor_to_import_report::Migration),
+            Box::new(m20240405_add_scheduled_imports_to_user::Migration),
+            Box::new(m20240406_add_idempotency_key_to_import_report::Migration),
+            Box::new(m20240407_add_application_job_id_to_import_report::Migration),
+            Box::new(m20240408_add_bulk_id_to_import_report::Migration),
+            Box::new(m20240409_add_created_on_to_collection_to_entity::Migration),
         ]
     }
 }